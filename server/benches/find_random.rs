@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+use std::env;
+
+use chess_trainer::infrastructure::postgres;
+use chess_trainer::puzzle::index::IndexedPuzzleRepository;
+use chess_trainer::puzzle::postgres::PostgresPuzzleRepository;
+use chess_trainer::puzzle::puzzle_repository::{
+    CreatePuzzle, InMemoryPuzzleRepository, PuzzleRepository,
+};
+use chess_trainer::puzzle::types::{Theme, ThemeChoice};
+use criterion::{criterion_group, criterion_main, Criterion};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::runtime::Runtime;
+
+const PUZZLES: usize = 100_000;
+const SET_SIZE: usize = 200;
+const THEMES: [Theme; 6] = [
+    Theme::Fork,
+    Theme::Pin,
+    Theme::Skewer,
+    Theme::Mate,
+    Theme::Endgame,
+    Theme::Middlegame,
+];
+
+fn sample_puzzles() -> Vec<CreatePuzzle> {
+    let mut rng = StdRng::seed_from_u64(42);
+    (0..PUZZLES)
+        .map(|n| CreatePuzzle {
+            fen: "8/8/8/8/8/8/8/8 w - - 0 1".to_string(),
+            moves: "e2e4".to_string(),
+            lichess_id: format!("{:05}", n),
+            lichess_rating: rng.gen_range(600..=2800),
+            lichess_rating_deviation: 75,
+            lichess_popularity: 90,
+            lichess_play_count: 100,
+            themes: THEMES
+                .iter()
+                .copied()
+                .filter(|_| rng.gen_bool(0.2))
+                .collect(),
+            lichess_game_url: String::new(),
+        })
+        .collect()
+}
+
+fn find_random(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let rating = 1400..=1700;
+    let themes = ThemeChoice::Themes(vec![Theme::Fork, Theme::Pin]);
+    let excluded = HashSet::new();
+
+    let make_in_memory = || {
+        runtime.block_on(async {
+            let repository = InMemoryPuzzleRepository::new();
+            for puzzle in sample_puzzles() {
+                repository.create(puzzle).await.unwrap();
+            }
+            repository
+        })
+    };
+    let in_memory = make_in_memory();
+    let in_memory_indexed = runtime
+        .block_on(IndexedPuzzleRepository::load(make_in_memory()))
+        .unwrap();
+
+    let mut group = c.benchmark_group("find_random");
+    group.bench_function("linear_scan", |b| {
+        b.to_async(&runtime).iter(|| async {
+            in_memory
+                .find_random(SET_SIZE, &rating, &themes, &excluded)
+                .await
+                .unwrap()
+        })
+    });
+    group.bench_function("indexed", |b| {
+        b.to_async(&runtime).iter(|| async {
+            in_memory_indexed
+                .find_random(SET_SIZE, &rating, &themes, &excluded)
+                .await
+                .unwrap()
+        })
+    });
+
+    if let Ok(url) = env::var("BENCH_DATABASE_URL") {
+        let repository = runtime.block_on(async {
+            let pool = postgres::connect(url.parse().unwrap(), 4).await.unwrap();
+            let repository = PostgresPuzzleRepository::new(pool);
+            if repository
+                .count_matching(&(0..=u16::MAX), &ThemeChoice::HealthyMix, &excluded)
+                .await
+                .unwrap()
+                < PUZZLES
+            {
+                for puzzle in sample_puzzles() {
+                    repository.create(puzzle).await.unwrap();
+                }
+            }
+            repository
+        });
+        group.bench_function("postgres", |b| {
+            b.to_async(&runtime).iter(|| async {
+                repository
+                    .find_random(SET_SIZE, &rating, &themes, &excluded)
+                    .await
+                    .unwrap()
+            })
+        });
+
+        let indexed = runtime
+            .block_on(IndexedPuzzleRepository::load(repository))
+            .unwrap();
+        group.bench_function("postgres_indexed", |b| {
+            b.to_async(&runtime).iter(|| async {
+                indexed
+                    .find_random(SET_SIZE, &rating, &themes, &excluded)
+                    .await
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, find_random);
+criterion_main!(benches);
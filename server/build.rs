@@ -0,0 +1,45 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const UI_BUILD_PATH: &str = "../web-ui/build";
+const PLACEHOLDER_VAR: &str = "EMBED_UI_PLACEHOLDER";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_EMBED_UI").is_none() {
+        return;
+    }
+    println!("cargo:rerun-if-changed={}", UI_BUILD_PATH);
+    println!("cargo:rerun-if-env-changed={}", PLACEHOLDER_VAR);
+
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let ui_build = manifest_dir.join(UI_BUILD_PATH);
+    let folder = if ui_build.join("index.html").is_file() {
+        ui_build
+    } else if env::var_os(PLACEHOLDER_VAR).is_some_and(|value| value == "1") {
+        println!(
+            "cargo:warning={} has no index.html, embedding a placeholder page.",
+            UI_BUILD_PATH
+        );
+        placeholder(&PathBuf::from(env::var_os("OUT_DIR").unwrap()))
+    } else {
+        panic!(
+            "{} has no index.html. Run `npm ci && npm run build` in web-ui to embed the web UI, \
+             or set {}=1 to embed a placeholder page instead.",
+            UI_BUILD_PATH, PLACEHOLDER_VAR
+        );
+    };
+    println!("cargo:rustc-env=EMBED_UI_FOLDER={}", folder.display());
+}
+
+fn placeholder(out_dir: &Path) -> PathBuf {
+    let folder = out_dir.join("web-ui-placeholder");
+    fs::create_dir_all(&folder).unwrap();
+    fs::write(
+        folder.join("index.html"),
+        "<!DOCTYPE html><html><body><p>The web UI was not built into this server.</p></body></html>\n",
+    )
+    .unwrap();
+    folder
+}
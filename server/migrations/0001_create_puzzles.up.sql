@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS puzzles (
+    id BIGSERIAL PRIMARY KEY,
+    fen TEXT NOT NULL,
+    moves TEXT NOT NULL,
+    lichess_id TEXT NOT NULL UNIQUE,
+    lichess_rating INTEGER NOT NULL,
+    lichess_rating_deviation INTEGER NOT NULL,
+    lichess_popularity SMALLINT NOT NULL,
+    lichess_play_count BIGINT NOT NULL,
+    themes TEXT[] NOT NULL,
+    lichess_game_url TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS puzzles_lichess_rating_idx ON puzzles (lichess_rating);
+CREATE INDEX IF NOT EXISTS puzzles_themes_idx ON puzzles USING GIN (themes);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS training_set_puzzles;
+DROP TABLE IF EXISTS training_sets;
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS training_sets (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    rating_min INTEGER NOT NULL,
+    rating_max INTEGER NOT NULL,
+    themes TEXT[],
+    current_progress BIGINT NOT NULL,
+    cycles_done BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS training_set_puzzles (
+    training_set_id UUID NOT NULL REFERENCES training_sets (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    puzzle_id BIGINT NOT NULL REFERENCES puzzles (id),
+    PRIMARY KEY (training_set_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS training_set_puzzles_puzzle_id_idx ON training_set_puzzles (puzzle_id);
@@ -0,0 +1,3 @@
+ALTER TABLE training_sets DROP COLUMN IF EXISTS user_id;
+DROP TABLE IF EXISTS sessions;
+DROP TABLE IF EXISTS users;
@@ -0,0 +1,25 @@
+CREATE TABLE IF NOT EXISTS attempts (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    training_set_id UUID NOT NULL REFERENCES training_sets (id) ON DELETE CASCADE,
+    puzzle_id BIGINT NOT NULL REFERENCES puzzles (id),
+    cycle BIGINT NOT NULL,
+    solved BOOLEAN NOT NULL,
+    solve_time_ms BIGINT NOT NULL,
+    attempted_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS attempts_user_id_idx ON attempts (user_id, attempted_at);
+CREATE INDEX IF NOT EXISTS attempts_training_set_id_idx ON attempts (training_set_id);
+
+CREATE TABLE IF NOT EXISTS rating_history (
+    attempt_id UUID PRIMARY KEY REFERENCES attempts (id) ON DELETE CASCADE,
+    sequence BIGSERIAL NOT NULL,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    rating DOUBLE PRECISION NOT NULL,
+    deviation DOUBLE PRECISION NOT NULL,
+    volatility DOUBLE PRECISION NOT NULL,
+    rated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS rating_history_user_id_idx ON rating_history (user_id, rated_at);
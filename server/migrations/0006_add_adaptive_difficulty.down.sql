@@ -0,0 +1 @@
+ALTER TABLE training_sets DROP COLUMN IF EXISTS target_success_percent;
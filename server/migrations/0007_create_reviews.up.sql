@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS reviews (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    puzzle_id BIGINT NOT NULL REFERENCES puzzles (id),
+    repetitions BIGINT NOT NULL,
+    interval_days BIGINT NOT NULL,
+    ease DOUBLE PRECISION NOT NULL,
+    due_at TIMESTAMPTZ NOT NULL,
+    reviewed_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (user_id, puzzle_id)
+);
+
+CREATE INDEX IF NOT EXISTS reviews_user_id_idx ON reviews (user_id, due_at);
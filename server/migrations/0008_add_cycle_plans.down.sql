@@ -0,0 +1 @@
+ALTER TABLE training_sets DROP COLUMN IF EXISTS cycle_days;
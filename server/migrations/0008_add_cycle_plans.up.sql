@@ -0,0 +1 @@
+ALTER TABLE training_sets ADD COLUMN IF NOT EXISTS cycle_days BIGINT[];
@@ -2,30 +2,25 @@ use std::env;
 
 use anyhow::Context;
 
-use chess_trainer::puzzle::make_service;
-use chess_trainer::puzzle::types::LichessPuzzleImport;
-use chess_trainer::puzzle::PuzzleService;
+use chess_trainer::infrastructure::config::Config;
+use chess_trainer::infrastructure::{logging, postgres};
+use chess_trainer::puzzle::{import_csv, make_service};
 
-fn main() -> anyhow::Result<()> {
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
     let path = env::args().nth(1).context("missing input file")?;
-    let mut reader = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .flexible(true)
-        .from_path(path)?;
-    let puzzle_service = make_service();
+    let config = Config::load()?;
+    logging::init(&config.server)?;
+    let database = postgres::connect_configured(&config.database).await?;
+    let puzzle_service = make_service(&config, database.clone()).await?;
 
-    let count = reader
-        .deserialize()
-        .map(|record: Result<LichessPuzzleImport, _>| puzzle_service.import_puzzle(record?))
-        .inspect(|result| {
-            if let Err(error) = result {
-                eprintln!("{}", error)
-            }
-        })
-        .filter(Result::is_ok)
-        .count();
+    let count = import_csv(&puzzle_service, path).await?;
 
     println!("imported {} puzzles.", count);
 
+    if let Some(database) = database {
+        database.close();
+    }
+
     Ok(())
 }
@@ -1,16 +1,97 @@
-use std::net::SocketAddr;
+use std::env;
+use std::path::PathBuf;
 
+use anyhow::{bail, Context};
+use tokio::signal;
+
+use chess_trainer::infrastructure::config::Config;
 use chess_trainer::infrastructure::rest::make_router;
-use chess_trainer::puzzle;
+use chess_trainer::infrastructure::{logging, migrations, postgres};
+use chess_trainer::{puzzle, user};
 
 #[tokio::main]
-async fn main() {
-    let puzzle_service = puzzle::make_service();
-    let app = make_router(puzzle_service);
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => serve(None).await,
+        Some("import") => {
+            let path = args.get(1).context("import requires an input file")?;
+            serve(Some(PathBuf::from(path))).await
+        }
+        Some("migrate") => migrate(&args[1..]).await,
+        Some(command) => bail!(
+            "unknown command {}, expected import, migrate or nothing.",
+            command
+        ),
+    }
+}
+
+async fn serve(import: Option<PathBuf>) -> anyhow::Result<()> {
+    let config = Config::load()?;
+    logging::init(&config.server)?;
+    let database = postgres::connect_configured(&config.database).await?;
+    let puzzle_service = puzzle::make_service(&config, database.clone()).await?;
+    if let Some(path) = import {
+        let puzzle_service = puzzle_service.clone();
+        tokio::spawn(async move {
+            match puzzle::import_csv(&puzzle_service, &path).await {
+                Ok(count) => tracing::info!(count, path = %path.display(), "import finished"),
+                Err(error) => {
+                    tracing::error!(error = ?error, path = %path.display(), "import failed")
+                }
+            }
+        });
+    }
+    let user_service = user::make_service(&config, database.clone());
+    let app = make_router(
+        puzzle_service,
+        user_service,
+        database.clone(),
+        &config.server,
+    );
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 5000));
-    axum::Server::bind(&addr)
+    tracing::info!(bind = %config.server.bind, "listening");
+    axum::Server::bind(&config.server.bind)
         .serve(app.into_make_service())
-        .await
-        .unwrap();
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    if let Some(database) = database {
+        database.close();
+    }
+    tracing::info!("server stopped");
+
+    Ok(())
+}
+
+async fn shutdown_signal() {
+    let interrupt = async {
+        signal::ctrl_c().await.expect("failed to listen for SIGINT");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {},
+        _ = terminate => {},
+    }
+    tracing::info!("shutting down, draining in-flight requests");
+}
+
+async fn migrate(args: &[String]) -> anyhow::Result<()> {
+    let config = Config::load()?;
+    logging::init(&config.server)?;
+    let Some(url) = config.database.url else {
+        bail!("migrate requires database.url.");
+    };
+    let mut client = postgres::connect_client(&url.parse()?).await?;
+    migrations::run_command(&mut client, args).await
 }
@@ -0,0 +1,383 @@
+use std::env;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context};
+use axum::http::{HeaderValue, Uri};
+use figment::providers::{Env, Format, Serialized, Toml};
+use figment::Figment;
+use serde::{Deserialize, Serialize};
+
+use crate::puzzle::SetLimits;
+use crate::user::AuthConfig;
+
+const CONFIG_PATH_VAR: &str = "CHESS_TRAINER_CONFIG";
+const DEFAULT_CONFIG_PATH: &str = "chess-trainer.toml";
+const ENV_PREFIX: &str = "CHESS_TRAINER_";
+const DATABASE_URL_VAR: &str = "DATABASE_URL";
+
+const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub limits: SetLimits,
+    pub auth: AuthConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: SocketAddr,
+    pub log_level: String,
+    pub log_format: LogFormat,
+    pub cors_origins: Vec<CorsOrigin>,
+    pub static_path: Option<PathBuf>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: SocketAddr::from(([0, 0, 0, 0], 5000)),
+            log_level: "info".to_string(),
+            log_format: LogFormat::Text,
+            cors_origins: Vec::new(),
+            static_path: None,
+        }
+    }
+}
+
+/// Origin allowed to call the API, like `https://example.com`, or `*` for any.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CorsOrigin {
+    origin: String,
+    header: HeaderValue,
+}
+
+impl CorsOrigin {
+    pub fn is_any(&self) -> bool {
+        self.origin == "*"
+    }
+
+    pub fn header(&self) -> &HeaderValue {
+        &self.header
+    }
+}
+
+impl TryFrom<String> for CorsOrigin {
+    type Error = anyhow::Error;
+
+    fn try_from(origin: String) -> anyhow::Result<CorsOrigin> {
+        let valid = origin == "*"
+            || origin.parse::<Uri>().is_ok_and(|uri| {
+                matches!(uri.scheme_str(), Some("http" | "https"))
+                    && uri.authority().is_some_and(|authority| {
+                        origin == format!("{}://{}", uri.scheme_str().unwrap(), authority)
+                    })
+            });
+        ensure!(
+            valid,
+            "server.cors_origins: {:?} is not an origin like https://example.com or *.",
+            origin
+        );
+        let header = HeaderValue::from_str(&origin)?;
+        Ok(CorsOrigin { origin, header })
+    }
+}
+
+impl From<CorsOrigin> for String {
+    fn from(origin: CorsOrigin) -> String {
+        origin.origin
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: Option<String>,
+    pub pool_size: usize,
+    pub index: bool,
+    pub index_refresh_seconds: u64,
+    pub fixture: Option<PathBuf>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: None,
+            pool_size: 10,
+            index: false,
+            index_refresh_seconds: 60,
+            fixture: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> anyhow::Result<Config> {
+        let path = match env::var_os(CONFIG_PATH_VAR) {
+            Some(path) => {
+                let path = PathBuf::from(path);
+                ensure!(
+                    path.is_file(),
+                    "configuration file {} does not exist.",
+                    path.display()
+                );
+                path
+            }
+            None => PathBuf::from(DEFAULT_CONFIG_PATH),
+        };
+        Config::from_figment(Config::figment(&path))
+    }
+
+    pub fn figment(path: &Path) -> Figment {
+        Figment::from(Serialized::defaults(Config::default()))
+            .merge(Toml::file(path))
+            .merge(
+                Env::raw()
+                    .only(&[DATABASE_URL_VAR])
+                    .map(|_| "database.url".into()),
+            )
+            .merge(Env::prefixed(ENV_PREFIX).ignore(&["config"]).split("__"))
+    }
+
+    pub fn from_figment(figment: Figment) -> anyhow::Result<Config> {
+        let config: Config = figment.extract().context("invalid configuration")?;
+        config.validate().context("invalid configuration")?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        self.server.validate()?;
+        self.database.validate()?;
+        self.limits.validate()?;
+        self.auth.validate()
+    }
+}
+
+impl ServerConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            LOG_LEVELS.contains(&self.log_level.as_str()),
+            "server.log_level must be one of {}, got {}.",
+            LOG_LEVELS.join(", "),
+            self.log_level
+        );
+        if let Some(static_path) = &self.static_path {
+            ensure!(
+                static_path.is_dir(),
+                "server.static_path: {} is not a directory.",
+                static_path.display()
+            );
+            ensure!(
+                static_path.join("index.html").is_file(),
+                "server.static_path: {} has no index.html.",
+                static_path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl DatabaseConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(self.pool_size > 0, "database.pool_size must be positive.");
+        ensure!(
+            self.index_refresh_seconds > 0,
+            "database.index_refresh_seconds must be positive."
+        );
+        if let Some(url) = &self.url {
+            if let Err(error) = url.parse::<tokio_postgres::Config>() {
+                bail!("database.url is invalid: {}.", error);
+            }
+            ensure!(
+                self.fixture.is_none(),
+                "database.fixture is only supported without database.url."
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::result_large_err)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::path::Path;
+
+    use figment::Jail;
+
+    use crate::infrastructure::config::{Config, CorsOrigin, DatabaseConfig, LogFormat};
+    use crate::puzzle::SetLimits;
+
+    const CONFIG_FILE: &str = "chess-trainer.toml";
+
+    fn load() -> anyhow::Result<Config> {
+        Config::from_figment(Config::figment(Path::new(CONFIG_FILE)))
+    }
+
+    #[test]
+    fn should_use_defaults_without_file() {
+        Jail::expect_with(|_| {
+            // when config is loaded without file or environment:
+            let config = load().unwrap();
+
+            // then defaults are used:
+            assert_eq!(config, Config::default());
+            assert_eq!(config.server.bind, SocketAddr::from(([0, 0, 0, 0], 5000)));
+            assert_eq!(config.database.url, None);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn should_override_file_with_environment() {
+        Jail::expect_with(|jail| {
+            // given config file:
+            jail.create_file(
+                CONFIG_FILE,
+                r#"
+                    [server]
+                    bind = "127.0.0.1:8080"
+                    log_level = "debug"
+                    log_format = "json"
+                    cors_origins = ["http://localhost:3000"]
+
+                    [database]
+                    url = "postgres://file@localhost/chess_trainer"
+                    pool_size = 4
+
+                    [limits]
+                    max_set_size = 500
+                "#,
+            )?;
+
+            // and environment overrides:
+            jail.set_env("DATABASE_URL", "postgres://env@localhost/chess_trainer");
+            jail.set_env("CHESS_TRAINER_LIMITS__MIN_SET_SIZE", "10");
+            jail.set_env("CHESS_TRAINER_DATABASE__INDEX", "true");
+
+            // when config is loaded:
+            let config = load().unwrap();
+
+            // then environment takes precedence over file:
+            assert_eq!(config.server.bind, SocketAddr::from(([127, 0, 0, 1], 8080)));
+            assert_eq!(config.server.log_level, "debug");
+            assert_eq!(config.server.log_format, LogFormat::Json);
+            assert_eq!(
+                config.server.cors_origins,
+                [CorsOrigin::try_from("http://localhost:3000".to_string()).unwrap()]
+            );
+            assert_eq!(
+                config.database,
+                DatabaseConfig {
+                    url: Some("postgres://env@localhost/chess_trainer".to_string()),
+                    pool_size: 4,
+                    index: true,
+                    index_refresh_seconds: 60,
+                    fixture: None,
+                }
+            );
+            assert_eq!(
+                config.limits,
+                SetLimits {
+                    max_set_size: 500,
+                    min_set_size: 10,
+                    ..SetLimits::default()
+                }
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn should_reject_invalid_values() {
+        Jail::expect_with(|jail| {
+            for (key, value, message) in [
+                ("SERVER__LOG_LEVEL", "loud", "server.log_level"),
+                (
+                    "SERVER__CORS_ORIGINS",
+                    "[\"localhost\"]",
+                    "server.cors_origins",
+                ),
+                (
+                    "SERVER__CORS_ORIGINS",
+                    "[\"https://example.com/app\"]",
+                    "server.cors_origins",
+                ),
+                ("SERVER__STATIC_PATH", "missing", "server.static_path"),
+                ("DATABASE__POOL_SIZE", "0", "database.pool_size"),
+                (
+                    "DATABASE__INDEX_REFRESH_SECONDS",
+                    "0",
+                    "database.index_refresh_seconds",
+                ),
+                ("LIMITS__MIN_SET_SIZE", "2000", "limits.min_set_size"),
+                ("AUTH__SESSION_TTL_HOURS", "0", "auth.session_ttl_hours"),
+                ("AUTH__SESSION_TTL_HOURS", "8761", "auth.session_ttl_hours"),
+            ] {
+                // given invalid value:
+                jail.clear_env();
+                jail.set_env(format!("CHESS_TRAINER_{}", key), value);
+
+                // when config is loaded:
+                let error = load().unwrap_err();
+
+                // then it's refused with the offending key:
+                assert!(
+                    format!("{:#}", error).contains(message),
+                    "{:#} should mention {}",
+                    error,
+                    message
+                );
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn should_accept_only_plain_origins() {
+        for (origin, valid) in [
+            ("*", true),
+            ("http://localhost:3000", true),
+            ("https://example.com", true),
+            ("https://example.com/", false),
+            ("https://example.com?query", false),
+            ("ftp://example.com", false),
+            ("https://exa\u{1}mple.com", false),
+            ("https://ex\u{e4}mple.com", false),
+            ("https://example.com\n", false),
+        ] {
+            // when origin is parsed:
+            let result = CorsOrigin::try_from(origin.to_string());
+
+            // then only a scheme and host, or any origin, is accepted:
+            assert_eq!(result.is_ok(), valid, "{:?}", origin);
+        }
+    }
+
+    #[test]
+    fn should_reject_unknown_keys() {
+        Jail::expect_with(|jail| {
+            // given config file with typo:
+            jail.create_file(CONFIG_FILE, "[server]\nbnid = \"127.0.0.1:8080\"")?;
+
+            // when config is loaded:
+            let error = load().unwrap_err();
+
+            // then it's refused:
+            assert!(format!("{:#}", error).contains("bnid"));
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use anyhow::{ensure, Context as _};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::Router;
+
+use crate::infrastructure::migrations;
+use crate::infrastructure::postgres::PostgresPool;
+use crate::infrastructure::rest::Context;
+use crate::puzzle::PuzzleService;
+
+pub fn make_router<P>() -> Router<Arc<Context<P>>>
+where
+    P: PuzzleService + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+}
+
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+pub async fn readyz<P>(State(ctx): State<Arc<Context<P>>>) -> (StatusCode, &'static str)
+where
+    P: PuzzleService + Send + Sync + 'static,
+{
+    match check_ready(&ctx).await {
+        Ok(()) => (StatusCode::OK, "ready"),
+        Err(error) => {
+            tracing::warn!(error = ?error, "not ready");
+            (StatusCode::SERVICE_UNAVAILABLE, "not ready")
+        }
+    }
+}
+
+async fn check_ready<P>(ctx: &Context<P>) -> anyhow::Result<()>
+where
+    P: PuzzleService + Send + Sync + 'static,
+{
+    if let Some(database) = &ctx.database {
+        check_database(database).await?;
+    }
+    let has_puzzles = ctx
+        .puzzle_service
+        .has_puzzles()
+        .await
+        .context("failed to look for puzzles")?;
+    ensure!(has_puzzles, "no puzzles imported.");
+    Ok(())
+}
+
+async fn check_database(database: &PostgresPool) -> anyhow::Result<()> {
+    let client = database.get().await.context("database unreachable")?;
+    let version = migrations::current_version(&client)
+        .await
+        .context("failed to read schema version")?;
+    ensure!(
+        version == migrations::latest_version(),
+        "database schema version {} differs from expected {}.",
+        version,
+        migrations::latest_version()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    use crate::infrastructure::migrations;
+    use crate::infrastructure::postgres::testing::test_pool;
+    use crate::infrastructure::test_support::TestServices;
+    use crate::puzzle::types::{LichessPuzzleImport, Theme};
+
+    async fn get(services: TestServices, uri: &str) -> (StatusCode, String) {
+        let response = services
+            .router()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn get_status(services: TestServices, uri: &str) -> StatusCode {
+        get(services, uri).await.0
+    }
+
+    fn sample_lichess_puzzle() -> LichessPuzzleImport {
+        LichessPuzzleImport {
+            puzzle_id: "sample-lichess-id".to_string(),
+            fen: "sample-fen".to_string(),
+            moves: "sample-moves".to_string(),
+            rating: 1500,
+            rating_deviation: 50,
+            popularity: 50,
+            play_count: 1000,
+            themes: vec![Theme::Fork],
+            game_url: "sample-lichess-game-url".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_be_alive() {
+        // when liveness is checked:
+        let status = get_status(TestServices::new(None).await, "/healthz").await;
+
+        // then it's ok:
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_not_be_ready_without_puzzles() {
+        // when readiness is checked before import:
+        let status = get_status(TestServices::new(None).await, "/readyz").await;
+
+        // then it's unavailable:
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn should_be_ready_with_puzzles() {
+        // given imported puzzle:
+        let services = TestServices::new(None).await;
+        services
+            .puzzle_service
+            .import_puzzle(sample_lichess_puzzle())
+            .await
+            .unwrap();
+
+        // when readiness is checked:
+        let status = get_status(services, "/readyz").await;
+
+        // then it's ok:
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn should_check_database_readiness() {
+        // given migrated database with puzzle:
+        let database = test_pool().await;
+        let services = TestServices::new(Some(database.clone())).await;
+        services
+            .puzzle_service
+            .import_puzzle(sample_lichess_puzzle())
+            .await
+            .unwrap();
+
+        // when readiness is checked:
+        let (status, _) = get(services, "/readyz").await;
+        assert_eq!(status, StatusCode::OK);
+
+        // and checked again after a migration is reverted:
+        database
+            .get()
+            .await
+            .unwrap()
+            .execute(
+                "DELETE FROM schema_migrations WHERE version = $1",
+                &[&migrations::latest_version()],
+            )
+            .await
+            .unwrap();
+        let services = TestServices::new(Some(database)).await;
+        let (status, body) = get(services, "/readyz").await;
+
+        // then it's unavailable, without revealing why:
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body, "not ready");
+    }
+}
@@ -0,0 +1,17 @@
+use anyhow::anyhow;
+use tracing_subscriber::EnvFilter;
+
+use crate::infrastructure::config::{LogFormat, ServerConfig};
+
+pub fn init(config: &ServerConfig) -> anyhow::Result<()> {
+    let filter = match EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) => EnvFilter::try_new(&config.log_level)?,
+    };
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    match config.log_format {
+        LogFormat::Text => subscriber.try_init(),
+        LogFormat::Json => subscriber.json().try_init(),
+    }
+    .map_err(|error| anyhow!(error))
+}
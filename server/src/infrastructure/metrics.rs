@@ -0,0 +1,188 @@
+use std::sync::{Arc, LazyLock};
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, State};
+use axum::http::{Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+use crate::infrastructure::rest::Context;
+use crate::puzzle::PuzzleService;
+
+pub static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+pub static HTTP_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new("http_requests_total", "HTTP requests handled."),
+        &["method", "route", "status"],
+    ))
+});
+
+pub static HTTP_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "http_request_duration_seconds",
+            "HTTP request latencies in seconds.",
+        ),
+        &["method", "route"],
+    ))
+});
+
+pub static TRAINING_SETS_CREATED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "training_set_creations_total",
+            "Training set creation attempts by outcome.",
+        ),
+        &["outcome"],
+    ))
+});
+
+pub static PUZZLE_ATTEMPTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "puzzle_attempts_total",
+            "Puzzle attempts recorded by outcome.",
+        ),
+        &["outcome"],
+    ))
+});
+
+pub static PUZZLE_REVIEWS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "puzzle_reviews_total",
+            "Puzzle reviews recorded by outcome.",
+        ),
+        &["outcome"],
+    ))
+});
+
+pub static PUZZLES_IMPORTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "puzzles_imported_total",
+            "Puzzles imported by this process by outcome.",
+        ),
+        &["outcome"],
+    ))
+});
+
+pub static PUZZLES: LazyLock<IntGauge> = LazyLock::new(|| {
+    register(IntGauge::new(
+        "puzzles",
+        "Estimated puzzles in the database.",
+    ))
+});
+
+fn register<T, E>(metric: Result<T, E>) -> T
+where
+    T: prometheus::core::Collector + Clone + 'static,
+    E: std::fmt::Debug,
+{
+    let metric = metric.unwrap();
+    REGISTRY.register(Box::new(metric.clone())).unwrap();
+    metric
+}
+
+pub fn make_router<P>() -> Router<Arc<Context<P>>>
+where
+    P: PuzzleService + Send + Sync + 'static,
+{
+    Router::new().route("/metrics", get(render))
+}
+
+pub async fn render<P>(State(ctx): State<Arc<Context<P>>>) -> (StatusCode, String)
+where
+    P: PuzzleService + Send + Sync + 'static,
+{
+    match ctx.puzzle_service.estimate_puzzle_count().await {
+        Ok(count) => PUZZLES.set(count as i64),
+        Err(error) => tracing::warn!(error = ?error, "failed to estimate puzzle count"),
+    }
+
+    let mut buffer = Vec::new();
+    match TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer) {
+        Ok(()) => (
+            StatusCode::OK,
+            String::from_utf8_lossy(&buffer).into_owned(),
+        ),
+        Err(error) => (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()),
+    }
+}
+
+pub async fn track_requests<B>(request: Request<B>, next: Next<B>) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", MatchedPath::as_str)
+        .to_string();
+
+    let response = next.run(request).await;
+
+    HTTP_REQUESTS
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .inc();
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[&method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    use crate::infrastructure::metrics::HTTP_REQUESTS;
+    use crate::infrastructure::test_support::test_router;
+
+    #[tokio::test]
+    async fn should_count_requests_per_route() {
+        // given router:
+        let router = test_router().await;
+        let before = HTTP_REQUESTS
+            .with_label_values(&["GET", "/api/v1/sets/preview", "401"])
+            .get();
+
+        // when route is requested without authentication:
+        router
+            .clone()
+            .oneshot(
+                Request::get("/api/v1/sets/preview?size=5&min_rating=0&max_rating=3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // then it's counted:
+        assert_eq!(
+            HTTP_REQUESTS
+                .with_label_values(&["GET", "/api/v1/sets/preview", "401"])
+                .get(),
+            before + 1
+        );
+
+        // and exposed:
+        let response = router
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(
+            r#"http_requests_total{method="GET",route="/api/v1/sets/preview",status="401"}"#
+        ));
+        assert!(body.contains("puzzles 0"));
+    }
+}
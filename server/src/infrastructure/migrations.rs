@@ -0,0 +1,347 @@
+use anyhow::{bail, ensure, Context};
+use tokio_postgres::Client;
+
+const LOCK_KEY: i64 = 0x6368_6573_735f_7472;
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+macro_rules! migration {
+    ($version:literal, $name:literal, $file:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            up: include_str!(concat!("../../migrations/", $file, ".up.sql")),
+            down: include_str!(concat!("../../migrations/", $file, ".down.sql")),
+        }
+    };
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    migration!(1, "create_puzzles", "0001_create_puzzles"),
+    migration!(2, "create_training_sets", "0002_create_training_sets"),
+    migration!(3, "create_users", "0003_create_users"),
+    migration!(4, "create_api_tokens", "0004_create_api_tokens"),
+    migration!(5, "create_attempts", "0005_create_attempts"),
+    migration!(6, "add_adaptive_difficulty", "0006_add_adaptive_difficulty"),
+    migration!(7, "create_reviews", "0007_create_reviews"),
+    migration!(8, "add_cycle_plans", "0008_add_cycle_plans"),
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub applied_at: Option<String>,
+    pub known: bool,
+}
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+async fn ensure_table(client: &mut Client) -> anyhow::Result<()> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version BIGINT PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        )",
+        )
+        .await?;
+    Ok(())
+}
+
+async fn applied_versions(client: &mut Client) -> anyhow::Result<Vec<i64>> {
+    Ok(client
+        .query(
+            "SELECT version FROM schema_migrations ORDER BY version",
+            &[],
+        )
+        .await?
+        .iter()
+        .map(|row| row.get(0))
+        .collect())
+}
+
+pub async fn current_version(client: &Client) -> anyhow::Result<i64> {
+    let row = client
+        .query_one(
+            "SELECT COALESCE(max(version), 0) FROM schema_migrations",
+            &[],
+        )
+        .await?;
+    Ok(row.try_get(0)?)
+}
+
+fn check_not_newer(applied: &[i64]) -> anyhow::Result<()> {
+    if let Some(&newest) = applied.last() {
+        ensure!(
+            newest <= latest_version(),
+            "database schema version {} is newer than the latest version {} known to this binary.",
+            newest,
+            latest_version()
+        );
+    }
+    Ok(())
+}
+
+async fn lock(client: &mut Client) -> anyhow::Result<()> {
+    client
+        .execute("SELECT pg_advisory_lock($1)", &[&LOCK_KEY])
+        .await?;
+    ensure_table(client).await
+}
+
+async fn unlock(client: &mut Client) -> anyhow::Result<()> {
+    client
+        .execute("SELECT pg_advisory_unlock($1)", &[&LOCK_KEY])
+        .await?;
+    Ok(())
+}
+
+pub async fn status(client: &mut Client) -> anyhow::Result<Vec<MigrationStatus>> {
+    ensure_table(client).await?;
+    let applied: Vec<(i64, String, String)> = client
+        .query(
+            "SELECT version, name, applied_at::TEXT FROM schema_migrations ORDER BY version",
+            &[],
+        )
+        .await?
+        .iter()
+        .map(|row| (row.get(0), row.get(1), row.get(2)))
+        .collect();
+
+    let mut statuses: Vec<MigrationStatus> = MIGRATIONS
+        .iter()
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            name: migration.name.to_string(),
+            applied_at: applied
+                .iter()
+                .find(|(version, _, _)| *version == migration.version)
+                .map(|(_, _, applied_at)| applied_at.clone()),
+            known: true,
+        })
+        .collect();
+    statuses.extend(
+        applied
+            .into_iter()
+            .filter(|(version, _, _)| MIGRATIONS.iter().all(|m| m.version != *version))
+            .map(|(version, name, applied_at)| MigrationStatus {
+                version,
+                name,
+                applied_at: Some(applied_at),
+                known: false,
+            }),
+    );
+    statuses.sort_by_key(|status| status.version);
+    Ok(statuses)
+}
+
+pub async fn up(
+    client: &mut Client,
+    target: Option<i64>,
+) -> anyhow::Result<Vec<&'static Migration>> {
+    lock(client).await?;
+    let result = apply(client, target).await;
+    unlock(client).await?;
+    result
+}
+
+async fn apply(
+    client: &mut Client,
+    target: Option<i64>,
+) -> anyhow::Result<Vec<&'static Migration>> {
+    let applied = applied_versions(client).await?;
+    check_not_newer(&applied)?;
+
+    let target = target.unwrap_or_else(latest_version);
+    let pending: Vec<_> = MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version <= target)
+        .filter(|migration| !applied.contains(&migration.version))
+        .collect();
+
+    for migration in &pending {
+        let transaction = client.transaction().await?;
+        transaction
+            .batch_execute(migration.up)
+            .await
+            .with_context(|| format!("migration {} failed", migration.version))?;
+        transaction
+            .execute(
+                "INSERT INTO schema_migrations (version, name) VALUES ($1, $2)",
+                &[&migration.version, &migration.name],
+            )
+            .await?;
+        transaction.commit().await?;
+    }
+    Ok(pending)
+}
+
+pub async fn down(client: &mut Client, steps: usize) -> anyhow::Result<Vec<&'static Migration>> {
+    lock(client).await?;
+    let result = revert(client, steps).await;
+    unlock(client).await?;
+    result
+}
+
+async fn revert(client: &mut Client, steps: usize) -> anyhow::Result<Vec<&'static Migration>> {
+    let applied = applied_versions(client).await?;
+    check_not_newer(&applied)?;
+
+    let mut reverted = Vec::new();
+    for version in applied.iter().rev().take(steps) {
+        let Some(migration) = MIGRATIONS.iter().find(|m| m.version == *version) else {
+            bail!("migration {} is unknown.", version);
+        };
+        let transaction = client.transaction().await?;
+        transaction
+            .batch_execute(migration.down)
+            .await
+            .with_context(|| format!("reverting migration {} failed", migration.version))?;
+        transaction
+            .execute(
+                "DELETE FROM schema_migrations WHERE version = $1",
+                &[&migration.version],
+            )
+            .await?;
+        transaction.commit().await?;
+        reverted.push(migration);
+    }
+    Ok(reverted)
+}
+
+pub async fn run_command(client: &mut Client, args: &[String]) -> anyhow::Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [] | ["status"] => {
+            for status in status(client).await? {
+                println!(
+                    "{:04} {:<30} {}{}",
+                    status.version,
+                    status.name,
+                    status.applied_at.as_deref().unwrap_or("pending"),
+                    if status.known { "" } else { " (unknown)" }
+                );
+            }
+        }
+        ["up", target @ ..] => {
+            let target = match target {
+                [] => None,
+                [target] => Some(target.parse().context("invalid target version")?),
+                _ => bail!("usage: migrate up [VERSION]"),
+            };
+            for migration in up(client, target).await? {
+                println!("applied {:04} {}", migration.version, migration.name);
+            }
+        }
+        ["down", steps @ ..] => {
+            let steps = match steps {
+                [] => 1,
+                [steps] => steps.parse().context("invalid number of steps")?,
+                _ => bail!("usage: migrate down [STEPS]"),
+            };
+            for migration in down(client, steps).await? {
+                println!("reverted {:04} {}", migration.version, migration.name);
+            }
+        }
+        _ => bail!("usage: migrate [status | up [VERSION] | down [STEPS]]"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_postgres::Client;
+
+    use crate::infrastructure::migrations::{
+        current_version, down, latest_version, status, up, MIGRATIONS,
+    };
+    use crate::infrastructure::postgres::connect_client;
+    use crate::infrastructure::postgres::testing::TestSchema;
+
+    async fn test_client() -> (Client, TestSchema) {
+        let schema = TestSchema::create().await;
+        (connect_client(&schema.config()).await.unwrap(), schema)
+    }
+
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn should_apply_all_migrations() {
+        // given empty database:
+        let (mut client, _schema) = test_client().await;
+
+        // when migrated up:
+        let applied = up(&mut client, None).await.unwrap();
+
+        // then every migration is applied and recorded:
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert!(status(&mut client)
+            .await
+            .unwrap()
+            .iter()
+            .all(|status| status.applied_at.is_some() && status.known));
+
+        // and nothing is left to apply:
+        assert!(up(&mut client, None).await.unwrap().is_empty());
+        assert_eq!(current_version(&client).await.unwrap(), latest_version());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn should_migrate_up_to_target_and_back_down() {
+        // given database migrated to the first version:
+        let (mut client, _schema) = test_client().await;
+        up(&mut client, Some(1)).await.unwrap();
+        assert_eq!(
+            status(&mut client)
+                .await
+                .unwrap()
+                .iter()
+                .filter(|status| status.applied_at.is_some())
+                .count(),
+            1
+        );
+
+        // when migrated down:
+        let reverted = down(&mut client, 1).await.unwrap();
+
+        // then the migration is reverted:
+        assert_eq!(reverted[0].version, 1);
+        assert!(status(&mut client)
+            .await
+            .unwrap()
+            .iter()
+            .all(|status| status.applied_at.is_none()));
+        assert!(client.query("SELECT 1 FROM puzzles", &[]).await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn should_refuse_schema_newer_than_binary() {
+        // given database migrated by a newer binary:
+        let (mut client, _schema) = test_client().await;
+        up(&mut client, None).await.unwrap();
+        client
+            .execute(
+                "INSERT INTO schema_migrations (version, name) VALUES ($1, 'from_the_future')",
+                &[&(latest_version() + 1)],
+            )
+            .await
+            .unwrap();
+
+        // when migrated up:
+        let result = up(&mut client, None).await;
+
+        // then it's refused:
+        assert!(result.is_err());
+        assert!(!status(&mut client).await.unwrap().last().unwrap().known);
+    }
+}
@@ -1 +1,12 @@
+pub mod api_error;
+pub mod config;
+pub mod health;
+pub mod logging;
+pub mod metrics;
+pub mod migrations;
+pub mod openapi;
+pub mod postgres;
 pub mod rest;
+#[cfg(test)]
+pub mod test_support;
+pub mod web_ui;
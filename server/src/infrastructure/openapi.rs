@@ -0,0 +1,285 @@
+use axum::routing::get;
+use axum::{Json, Router};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_redoc::{Redoc, Servable};
+
+use crate::infrastructure::api_error::ApiError;
+use crate::puzzle::rest as puzzle_rest;
+use crate::puzzle::types::{
+    AdaptiveDifficulty, Attempt, AttemptResult, CreateTrainingSetOptions, CriteriaFilter,
+    CyclePlan, CycleSchedule, CycleStats, DailySession, Performance, PlanStatus, Puzzle,
+    PuzzleStats, Rating, RatingBandStats, RatingHistoryEntry, RatingSummary, RecordAttemptOptions,
+    RecordReviewOptions, Review, Schedule, ScheduleState, SessionItem, SetSuggestion, Statistics,
+    Theme, ThemeChoice, ThemeStats, ThemeWeakness, TrainingSet, TrainingSetPreview,
+};
+use crate::user::rest as user_rest;
+use crate::user::types::{
+    ApiToken, CreateApiTokenOptions, Credentials, NewApiToken, Scope, SessionToken, User,
+};
+
+pub const SPEC_PATH: &str = "/api/openapi.json";
+pub const DOCS_PATH: &str = "/api/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Chess Trainer",
+        description = "Woodpecker method training on Lichess puzzles."
+    ),
+    paths(
+        puzzle_rest::list_puzzles,
+        puzzle_rest::list_sets,
+        puzzle_rest::get_set,
+        puzzle_rest::create_set,
+        puzzle_rest::preview_set,
+        puzzle_rest::record_attempt,
+        puzzle_rest::set_plan,
+        puzzle_rest::plan_status,
+        puzzle_rest::set_stats,
+        puzzle_rest::get_rating,
+        puzzle_rest::rating_history,
+        puzzle_rest::due_reviews,
+        puzzle_rest::record_review,
+        puzzle_rest::daily_session,
+        puzzle_rest::user_stats,
+        puzzle_rest::suggest_set,
+        user_rest::register,
+        user_rest::current_user,
+        user_rest::login,
+        user_rest::logout,
+        user_rest::list_api_tokens,
+        user_rest::create_api_token,
+        user_rest::revoke_api_token,
+    ),
+    components(schemas(
+        AdaptiveDifficulty,
+        ApiError,
+        ApiToken,
+        Attempt,
+        AttemptResult,
+        CreateApiTokenOptions,
+        CreateTrainingSetOptions,
+        Credentials,
+        CriteriaFilter,
+        CyclePlan,
+        CycleSchedule,
+        CycleStats,
+        DailySession,
+        NewApiToken,
+        Performance,
+        PlanStatus,
+        Puzzle,
+        PuzzleStats,
+        Rating,
+        RatingBandStats,
+        RatingHistoryEntry,
+        RatingSummary,
+        RecordAttemptOptions,
+        RecordReviewOptions,
+        Review,
+        Schedule,
+        ScheduleState,
+        Scope,
+        SessionItem,
+        SessionToken,
+        SetSuggestion,
+        Statistics,
+        Theme,
+        ThemeChoice,
+        ThemeStats,
+        ThemeWeakness,
+        TrainingSet,
+        TrainingSetPreview,
+        User,
+    )),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "puzzles", description = "Imported Lichess puzzles."),
+        (name = "sets", description = "Training sets and attempts."),
+        (name = "rating", description = "Glicko-2 puzzle rating of the user."),
+        (name = "reviews", description = "Spaced repetition of failed puzzles and daily sessions."),
+        (name = "stats", description = "Accuracy and solve time of attempts, and weaknesses they reveal."),
+        (name = "users", description = "Accounts, sessions and API tokens."),
+    )
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+}
+
+pub fn make_router<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route(SPEC_PATH, get(|| async { Json(ApiDoc::openapi()) }))
+        .merge(Redoc::with_url(DOCS_PATH, ApiDoc::openapi()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use tower::ServiceExt;
+    use utoipa::openapi::PathItemType;
+    use utoipa::OpenApi;
+
+    use crate::infrastructure::openapi::{ApiDoc, DOCS_PATH, SPEC_PATH};
+    use crate::infrastructure::rest::API_PREFIX;
+    use crate::infrastructure::test_support::test_router;
+    use crate::puzzle::PuzzleService;
+    use crate::{puzzle, user};
+
+    fn snapshot_path() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("openapi.json")
+    }
+
+    fn method(item_type: &PathItemType) -> Method {
+        match item_type {
+            PathItemType::Get => Method::GET,
+            PathItemType::Post => Method::POST,
+            PathItemType::Put => Method::PUT,
+            PathItemType::Delete => Method::DELETE,
+            PathItemType::Options => Method::OPTIONS,
+            PathItemType::Head => Method::HEAD,
+            PathItemType::Patch => Method::PATCH,
+            PathItemType::Trace => Method::TRACE,
+            PathItemType::Connect => Method::CONNECT,
+        }
+    }
+
+    #[test]
+    fn should_match_snapshot() {
+        // given generated spec:
+        let spec = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(snapshot_path(), &spec).unwrap();
+        }
+
+        // when it's compared with the committed one:
+        let snapshot = std::fs::read_to_string(snapshot_path()).unwrap_or_default();
+
+        // then they're equal:
+        assert!(
+            spec == snapshot,
+            "openapi.json is out of date, rerun with UPDATE_OPENAPI=1 and commit it"
+        );
+    }
+
+    #[tokio::test]
+    async fn should_route_documented_operations() {
+        // given router:
+        let router = test_router().await;
+
+        for (path, item) in ApiDoc::openapi().paths.paths {
+            for item_type in item.operations.keys() {
+                // when documented operation is requested:
+                let request = Request::builder()
+                    .method(method(item_type))
+                    .uri(&path)
+                    .body(Body::empty())
+                    .unwrap();
+                let response = router.clone().oneshot(request).await.unwrap();
+
+                // then it's handled:
+                assert_ne!(response.status(), StatusCode::NOT_FOUND, "{}", path);
+                assert_ne!(
+                    response.status(),
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    "{}",
+                    path
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_document_routed_operations() {
+        // given router and documented operations:
+        let router = test_router().await;
+        let documented: HashSet<(String, Method)> = ApiDoc::openapi()
+            .paths
+            .paths
+            .into_iter()
+            .flat_map(|(path, item)| {
+                item.operations
+                    .into_keys()
+                    .map(move |item_type| (path.clone(), method(&item_type)))
+            })
+            .collect();
+
+        let routes = puzzle::rest::routes::<Arc<dyn PuzzleService + Send + Sync>>()
+            .into_iter()
+            .chain(user::rest::routes())
+            .map(|(path, _)| path);
+        for route in routes {
+            let path = format!("{}{}", API_PREFIX, route.replace(":id", "{id}"));
+            for method in [
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::DELETE,
+                Method::PATCH,
+            ] {
+                // when route is requested:
+                let request = Request::builder()
+                    .method(method.clone())
+                    .uri(format!("{}{}", API_PREFIX, route.replace(":id", "1")))
+                    .body(Body::empty())
+                    .unwrap();
+                let response = router.clone().oneshot(request).await.unwrap();
+
+                // then each method it accepts is documented:
+                if response.status() != StatusCode::METHOD_NOT_ALLOWED {
+                    assert!(
+                        documented.contains(&(path.clone(), method.clone())),
+                        "{} {} is routed but not documented",
+                        method,
+                        path
+                    );
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_serve_spec_and_docs() {
+        // given router:
+        let router = test_router().await;
+
+        // when spec is requested:
+        let response = router
+            .clone()
+            .oneshot(Request::get(SPEC_PATH).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        // then it's the generated one:
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let spec: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(spec, serde_json::to_value(ApiDoc::openapi()).unwrap());
+
+        // and docs are served:
+        let response = router
+            .oneshot(Request::get(DOCS_PATH).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,139 @@
+use anyhow::Context;
+use deadpool_postgres::{Manager, ManagerConfig, PoolBuilder, RecyclingMethod};
+use tokio_postgres::{Client, Config, NoTls};
+
+use crate::infrastructure::config::DatabaseConfig;
+use crate::infrastructure::migrations;
+
+pub type PostgresPool = deadpool_postgres::Pool;
+
+pub async fn connect(config: Config, pool_size: usize) -> anyhow::Result<PostgresPool> {
+    migrate(pool_builder(config, pool_size).build()?).await
+}
+
+fn pool_builder(config: Config, pool_size: usize) -> PoolBuilder {
+    let manager_config = ManagerConfig {
+        recycling_method: RecyclingMethod::Fast,
+    };
+    let manager = Manager::from_config(config, NoTls, manager_config);
+    PostgresPool::builder(manager).max_size(pool_size)
+}
+
+async fn migrate(pool: PostgresPool) -> anyhow::Result<PostgresPool> {
+    let mut client = pool.get().await?;
+    migrations::up(&mut client, None).await?;
+    drop(client);
+    Ok(pool)
+}
+
+pub async fn connect_configured(config: &DatabaseConfig) -> anyhow::Result<Option<PostgresPool>> {
+    match &config.url {
+        Some(url) => {
+            let pool = connect(url.parse()?, config.pool_size)
+                .await
+                .context("failed to connect to the database")?;
+            Ok(Some(pool))
+        }
+        None => Ok(None),
+    }
+}
+
+pub async fn connect_client(config: &Config) -> anyhow::Result<Client> {
+    let (client, connection) = config.connect(NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(error) = connection.await {
+            tracing::error!(%error, "database connection failed");
+        }
+    });
+    Ok(client)
+}
+
+#[cfg(test)]
+pub mod testing {
+    use std::{env, thread};
+
+    use deadpool_postgres::Hook;
+    use tokio_postgres::Config;
+    use uuid::Uuid;
+
+    use crate::infrastructure::postgres::{connect_client, migrate, pool_builder, PostgresPool};
+
+    pub const TEST_DATABASE_URL_VAR: &str = "TEST_DATABASE_URL";
+
+    /// Schema isolating a test, dropped with it.
+    pub struct TestSchema {
+        name: String,
+        config: Config,
+    }
+
+    impl TestSchema {
+        pub async fn create() -> TestSchema {
+            let url = env::var(TEST_DATABASE_URL_VAR)
+                .unwrap_or_else(|_| panic!("{} must be set", TEST_DATABASE_URL_VAR));
+            let config: Config = url.parse().unwrap();
+
+            let name = format!("test_{}", Uuid::new_v4().simple());
+            connect_client(&config)
+                .await
+                .unwrap()
+                .batch_execute(&format!("CREATE SCHEMA {}", name))
+                .await
+                .unwrap();
+
+            TestSchema { name, config }
+        }
+
+        pub fn config(&self) -> Config {
+            let mut config = self.config.clone();
+            config.options(format!("-c search_path={}", self.name));
+            config.application_name(&self.name);
+            config
+        }
+    }
+
+    impl Drop for TestSchema {
+        fn drop(&mut self) {
+            let config = self.config.clone();
+            let name = self.name.clone();
+            // Drop can't await, and the test's runtime may be the one running it. That runtime
+            // won't roll back transactions left open by the test either, so their connections
+            // are terminated to release the locks.
+            let dropped = thread::spawn(move || {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()?
+                    .block_on(async {
+                        let client = connect_client(&config).await?;
+                        client
+                            .execute(
+                                "SELECT pg_terminate_backend(pid) FROM pg_stat_activity \
+                                 WHERE application_name = $1",
+                                &[&name],
+                            )
+                            .await?;
+                        client
+                            .batch_execute(&format!("DROP SCHEMA {} CASCADE", name))
+                            .await?;
+                        Ok::<_, anyhow::Error>(())
+                    })
+            })
+            .join();
+            if !matches!(dropped, Ok(Ok(()))) {
+                eprintln!("failed to drop test schema {}", self.name);
+            }
+        }
+    }
+
+    pub async fn test_pool() -> PostgresPool {
+        let schema = TestSchema::create().await;
+        // The pool keeps the schema until its last handle is dropped with the test.
+        let pool = pool_builder(schema.config(), 2)
+            .post_create(Hook::sync_fn(move |_, _| {
+                let _ = &schema;
+                Ok(())
+            }))
+            .build()
+            .unwrap();
+        migrate(pool).await.unwrap()
+    }
+}
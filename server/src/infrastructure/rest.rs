@@ -1,24 +1,269 @@
 use std::sync::Arc;
+use std::time::Duration;
 
+use axum::http::header::HeaderName;
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE, LINK};
+use axum::http::{HeaderValue, Method, Request, Response};
+use axum::middleware::{self, Next};
 use axum::Router;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use tracing::{field, Span};
 
+use crate::infrastructure::api_error::ApiError;
+use crate::infrastructure::config::{CorsOrigin, ServerConfig};
+use crate::infrastructure::postgres::PostgresPool;
+use crate::infrastructure::{health, metrics, openapi, web_ui};
 use crate::puzzle;
 use crate::puzzle::PuzzleService;
+use crate::user;
+use crate::user::UserService;
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+const DEPRECATION_HEADER: &str = "deprecation";
+
+pub const API_PREFIX: &str = "/api/v1";
 
 pub struct Context<P>
 where
     P: PuzzleService + Send + Sync + 'static,
 {
     pub puzzle_service: P,
+    pub user_service: Box<dyn UserService + Send + Sync>,
+    pub database: Option<PostgresPool>,
 }
 
-pub fn make_router<P>(puzzle_service: P) -> Router
+pub fn make_router<P>(
+    puzzle_service: P,
+    user_service: Box<dyn UserService + Send + Sync>,
+    database: Option<PostgresPool>,
+    server: &ServerConfig,
+) -> Router
 where
     P: PuzzleService + Send + Sync + 'static,
 {
-    let ctx = Context { puzzle_service };
+    let ctx = Context {
+        puzzle_service,
+        user_service,
+        database,
+    };
 
-    Router::new()
-        .merge(puzzle::make_router())
+    let mut router = Router::new()
+        .merge(health::make_router())
+        .merge(metrics::make_router())
+        .merge(openapi::make_router())
+        .merge(puzzle::make_legacy_router().route_layer(middleware::from_fn(deprecated)))
+        .route_layer(middleware::from_fn(metrics::track_requests))
+        .nest(
+            API_PREFIX,
+            puzzle::make_router()
+                .merge(user::make_router())
+                .route_layer(middleware::from_fn(metrics::track_requests))
+                .fallback(|| async { ApiError::not_found() }),
+        );
+    let web_ui = match &server.static_path {
+        Some(static_path) => Some(web_ui::make_router(static_path)),
+        None => web_ui::make_embedded_router(),
+    };
+    if let Some(web_ui) = web_ui {
+        router = router.merge(web_ui);
+    }
+
+    let mut router = router
         .with_state(Arc::new(ctx))
+        .layer(PropagateRequestIdLayer::x_request_id());
+    if !server.cors_origins.is_empty() {
+        router = router.layer(make_cors_layer(&server.cors_origins));
+    }
+    router
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(make_request_span)
+                .on_response(record_response),
+        )
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+}
+
+fn make_cors_layer(origins: &[CorsOrigin]) -> CorsLayer {
+    let allow_origin = if origins.iter().any(CorsOrigin::is_any) {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(origins.iter().map(|origin| origin.header().clone()))
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_headers([CONTENT_TYPE, AUTHORIZATION])
+        .expose_headers([HeaderName::from_static(REQUEST_ID_HEADER)])
+        .max_age(Duration::from_secs(3600))
+}
+
+async fn deprecated<B>(request: Request<B>, next: Next<B>) -> axum::response::Response {
+    let successor = format!(
+        "<{}{}>; rel=\"successor-version\"",
+        API_PREFIX,
+        request.uri().path()
+    );
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(DEPRECATION_HEADER, HeaderValue::from_static("true"));
+    if let Ok(successor) = HeaderValue::from_str(&successor) {
+        headers.insert(LINK, successor);
+    }
+    response
+}
+
+fn make_request_span<B>(request: &Request<B>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|request_id| request_id.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id,
+        status = field::Empty,
+    )
+}
+
+fn record_response<B>(response: &Response<B>, latency: Duration, span: &Span) {
+    span.record("status", response.status().as_u16());
+    tracing::info!(latency_ms = latency.as_millis() as u64, "finished request");
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::header::{
+        ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_METHOD, AUTHORIZATION, LINK, ORIGIN,
+    };
+    use axum::http::{HeaderValue, Request, Response, StatusCode};
+    use tower::ServiceExt;
+
+    use crate::infrastructure::config::ServerConfig;
+    use crate::infrastructure::rest::{DEPRECATION_HEADER, REQUEST_ID_HEADER};
+    use crate::infrastructure::test_support::TestServices;
+
+    async fn send(request: Request<Body>, server: &ServerConfig) -> Response<axum::body::BoxBody> {
+        TestServices::new(None)
+            .await
+            .router_with(server)
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    async fn send_authenticated(mut request: Request<Body>) -> Response<axum::body::BoxBody> {
+        let services = TestServices::new(None).await;
+        let token = services.login("magnus").await;
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        services.router().oneshot(request).await.unwrap()
+    }
+
+    async fn get_request_id(request: Request<Body>) -> Option<HeaderValue> {
+        send(request, &ServerConfig::default())
+            .await
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .cloned()
+    }
+
+    fn sample_cors_config() -> ServerConfig {
+        ServerConfig {
+            cors_origins: vec!["http://localhost:3000".to_string().try_into().unwrap()],
+            ..ServerConfig::default()
+        }
+    }
+
+    fn preflight(origin: &str) -> Request<Body> {
+        Request::options("/api/v1/sets")
+            .header(ORIGIN, origin)
+            .header(ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_assign_request_id() {
+        // when request without id is sent:
+        let request = Request::get("/healthz").body(Body::empty()).unwrap();
+        let request_id = get_request_id(request).await;
+
+        // then generated id is returned:
+        assert_eq!(request_id.unwrap().len(), 36);
+    }
+
+    #[tokio::test]
+    async fn should_propagate_request_id() {
+        // when request with id is sent:
+        let request = Request::get("/healthz")
+            .header(REQUEST_ID_HEADER, "sample-request-id")
+            .body(Body::empty())
+            .unwrap();
+        let request_id = get_request_id(request).await;
+
+        // then the same id is returned:
+        assert_eq!(request_id.unwrap(), "sample-request-id");
+    }
+
+    #[tokio::test]
+    async fn should_serve_api_under_version_prefix() {
+        // when versioned route is requested:
+        let request = Request::get("/api/v1/puzzles").body(Body::empty()).unwrap();
+        let response = send_authenticated(request).await;
+
+        // then it's served without deprecation:
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(DEPRECATION_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn should_mark_unversioned_routes_deprecated() {
+        // when legacy route is requested:
+        let request = Request::get("/puzzles").body(Body::empty()).unwrap();
+        let response = send_authenticated(request).await;
+
+        // then it's still served but points to its successor:
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[DEPRECATION_HEADER], "true");
+        assert_eq!(
+            response.headers()[LINK],
+            "</api/v1/puzzles>; rel=\"successor-version\""
+        );
+    }
+
+    #[tokio::test]
+    async fn should_allow_configured_origins() {
+        // when preflight request comes from configured origin:
+        let response = send(preflight("http://localhost:3000"), &sample_cors_config()).await;
+
+        // then it's allowed:
+        assert_eq!(
+            response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "http://localhost:3000"
+        );
+    }
+
+    #[tokio::test]
+    async fn should_not_allow_other_origins() {
+        // when preflight request comes from other origin:
+        let response = send(preflight("http://evil.example"), &sample_cors_config()).await;
+
+        // then it's not allowed:
+        assert!(response
+            .headers()
+            .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
 }
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use axum::Router;
+
+use crate::infrastructure::config::{Config, ServerConfig};
+use crate::infrastructure::postgres::PostgresPool;
+use crate::infrastructure::rest::make_router;
+use crate::puzzle::PuzzleService;
+use crate::user::types::Credentials;
+use crate::user::UserService;
+use crate::{puzzle, user};
+
+pub struct TestServices {
+    pub puzzle_service: Arc<dyn PuzzleService + Send + Sync>,
+    pub user_service: Box<dyn UserService + Send + Sync>,
+    pub database: Option<PostgresPool>,
+}
+
+impl TestServices {
+    pub async fn new(database: Option<PostgresPool>) -> Self {
+        let config = Config::default();
+        TestServices {
+            puzzle_service: puzzle::make_service(&config, database.clone())
+                .await
+                .unwrap(),
+            user_service: user::make_service(&config, database.clone()),
+            database,
+        }
+    }
+
+    pub async fn login(&self, username: &str) -> String {
+        let credentials = Credentials {
+            username: username.to_string(),
+            password: "sample-password".to_string(),
+        };
+        self.user_service
+            .register(credentials.clone())
+            .await
+            .unwrap();
+        self.user_service.login(credentials).await.unwrap().token
+    }
+
+    pub fn router(self) -> Router {
+        self.router_with(&ServerConfig::default())
+    }
+
+    pub fn router_with(self, server: &ServerConfig) -> Router {
+        make_router(
+            self.puzzle_service,
+            self.user_service,
+            self.database,
+            server,
+        )
+    }
+}
+
+pub async fn test_router() -> Router {
+    TestServices::new(None).await.router()
+}
@@ -0,0 +1,269 @@
+use std::path::Path;
+
+use axum::http::header::CACHE_CONTROL;
+use axum::http::{HeaderValue, Response};
+use axum::Router;
+use tower::Layer;
+use tower_http::services::{ServeDir, ServeFile};
+use tower_http::set_header::SetResponseHeaderLayer;
+
+const HASHED_ASSETS_PATH: &str = "/static";
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+const REVALIDATE: &str = "no-cache";
+
+pub fn make_router<S>(path: &Path) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let hashed_assets = ServeDir::new(path.join(HASHED_ASSETS_PATH.trim_start_matches('/')))
+        .precompressed_br()
+        .precompressed_gzip();
+    let index = ServeFile::new(path.join("index.html"))
+        .precompressed_br()
+        .precompressed_gzip();
+    let files = ServeDir::new(path)
+        .precompressed_br()
+        .precompressed_gzip()
+        .fallback(index);
+
+    Router::new()
+        .nest_service(
+            HASHED_ASSETS_PATH,
+            cache_control(IMMUTABLE).layer(hashed_assets),
+        )
+        .fallback_service(cache_control(REVALIDATE).layer(files))
+}
+
+#[cfg(feature = "embed-ui")]
+pub fn make_embedded_router<S>() -> Option<Router<S>>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Some(Router::new().fallback(embedded::serve))
+}
+
+#[cfg(not(feature = "embed-ui"))]
+pub fn make_embedded_router<S>() -> Option<Router<S>>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    None
+}
+
+fn cache_control<B>(
+    policy: &'static str,
+) -> SetResponseHeaderLayer<impl Fn(&Response<B>) -> Option<HeaderValue> + Clone> {
+    SetResponseHeaderLayer::overriding(CACHE_CONTROL, move |response: &Response<B>| {
+        response
+            .status()
+            .is_success()
+            .then(|| HeaderValue::from_static(policy))
+    })
+}
+
+#[cfg(feature = "embed-ui")]
+mod embedded {
+    use axum::http::header::{
+        ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_TYPE, ETAG, IF_NONE_MATCH,
+    };
+    use axum::http::{HeaderMap, HeaderValue, StatusCode, Uri};
+    use axum::response::{IntoResponse, Response};
+    use rust_embed::{EmbeddedFile, RustEmbed};
+
+    use crate::infrastructure::web_ui::{HASHED_ASSETS_PATH, IMMUTABLE, REVALIDATE};
+
+    #[derive(RustEmbed)]
+    #[folder = "$EMBED_UI_FOLDER"]
+    struct Assets;
+
+    pub async fn serve(uri: Uri, headers: HeaderMap) -> Response {
+        let path = uri.path().trim_start_matches('/');
+        let path = if path.is_empty() { "index.html" } else { path };
+        match Assets::get(path) {
+            Some(file) => respond(path, file, cache_policy(uri.path()), &headers),
+            None if is_hashed_asset(uri.path()) => StatusCode::NOT_FOUND.into_response(),
+            None => match Assets::get("index.html") {
+                Some(file) => respond("index.html", file, REVALIDATE, &headers),
+                None => StatusCode::NOT_FOUND.into_response(),
+            },
+        }
+    }
+
+    fn respond(
+        path: &str,
+        file: EmbeddedFile,
+        policy: &'static str,
+        headers: &HeaderMap,
+    ) -> Response {
+        let etag = format!("\"{}\"", hex(&file.metadata.sha256_hash()));
+        let cache_headers = [
+            (CACHE_CONTROL, HeaderValue::from_static(policy)),
+            (ETAG, HeaderValue::from_str(&etag).unwrap()),
+        ];
+        if headers
+            .get(IF_NONE_MATCH)
+            .is_some_and(|if_none_match| if_none_match.as_bytes() == etag.as_bytes())
+        {
+            return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+        }
+
+        let content_type = HeaderValue::from_str(file.metadata.mimetype()).unwrap();
+        let accepts_gzip = headers
+            .get(ACCEPT_ENCODING)
+            .and_then(|accept_encoding| accept_encoding.to_str().ok())
+            .is_some_and(|accept_encoding| accept_encoding.contains("gzip"));
+        if accepts_gzip {
+            if let Some(compressed) = Assets::get(&format!("{}.gz", path)) {
+                return (
+                    cache_headers,
+                    [
+                        (CONTENT_TYPE, content_type),
+                        (CONTENT_ENCODING, HeaderValue::from_static("gzip")),
+                    ],
+                    compressed.data.into_owned(),
+                )
+                    .into_response();
+            }
+        }
+        (
+            cache_headers,
+            [(CONTENT_TYPE, content_type)],
+            file.data.into_owned(),
+        )
+            .into_response()
+    }
+
+    fn is_hashed_asset(path: &str) -> bool {
+        path.strip_prefix(HASHED_ASSETS_PATH)
+            .is_some_and(|rest| rest.starts_with('/'))
+    }
+
+    fn cache_policy(path: &str) -> &'static str {
+        if is_hashed_asset(path) {
+            IMMUTABLE
+        } else {
+            REVALIDATE
+        }
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use axum::body::Body;
+    use axum::http::header::{ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_ENCODING};
+    use axum::http::{Request, Response, StatusCode};
+    use axum::Router;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use crate::infrastructure::config::ServerConfig;
+    use crate::infrastructure::test_support::TestServices;
+
+    fn sample_build() -> PathBuf {
+        let path = std::env::temp_dir().join(format!("web-ui-{}", Uuid::new_v4()));
+        fs::create_dir_all(path.join("static/js")).unwrap();
+        fs::write(path.join("index.html"), "<html>index</html>").unwrap();
+        fs::write(path.join("static/js/main.1234.js"), "main").unwrap();
+        fs::write(path.join("static/js/main.1234.js.gz"), "main-gzip").unwrap();
+        path
+    }
+
+    async fn make_test_router() -> Router {
+        let server = ServerConfig {
+            static_path: Some(sample_build()),
+            ..ServerConfig::default()
+        };
+        TestServices::new(None).await.router_with(&server)
+    }
+
+    async fn get(request: Request<Body>) -> (Response<()>, String) {
+        let response = make_test_router().await.oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let body = hyper::body::to_bytes(body).await.unwrap();
+        (
+            Response::from_parts(parts, ()),
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn should_fall_back_to_index() {
+        // when client-side route is requested:
+        let (response, body) = get(Request::get("/sets/42").body(Body::empty()).unwrap()).await;
+
+        // then index is served and revalidated:
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body, "<html>index</html>");
+        assert_eq!(response.headers()[CACHE_CONTROL], "no-cache");
+    }
+
+    #[tokio::test]
+    async fn should_serve_precompressed_hashed_assets() {
+        // when hashed asset is requested with gzip accepted:
+        let request = Request::get("/static/js/main.1234.js")
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let (response, body) = get(request).await;
+
+        // then precompressed file is served and cached forever:
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body, "main-gzip");
+        assert_eq!(response.headers()[CONTENT_ENCODING], "gzip");
+        assert_eq!(
+            response.headers()[CACHE_CONTROL],
+            "public, max-age=31536000, immutable"
+        );
+    }
+
+    #[tokio::test]
+    async fn should_not_fall_back_for_missing_hashed_assets() {
+        // when missing hashed asset is requested:
+        let (response, _) = get(Request::get("/static/js/main.5678.js")
+            .body(Body::empty())
+            .unwrap())
+        .await;
+
+        // then it's not found instead of index being cached forever:
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(response.headers().get(CACHE_CONTROL).is_none());
+    }
+
+    #[tokio::test]
+    async fn should_prefer_api_routes() {
+        // when API route is requested:
+        let (response, body) = get(Request::get("/healthz").body(Body::empty()).unwrap()).await;
+
+        // then it's not shadowed by assets:
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body, "ok");
+    }
+
+    #[cfg(feature = "embed-ui")]
+    #[tokio::test]
+    async fn should_serve_embedded_index_without_static_path() {
+        // given router without static path:
+        let router = TestServices::new(None).await.router();
+
+        // when client-side route is requested:
+        let response = router
+            .oneshot(Request::get("/sets/42").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        // then embedded index is served:
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CACHE_CONTROL], "no-cache");
+        assert!(response.headers()[axum::http::header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html"));
+    }
+}
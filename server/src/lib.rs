@@ -1,2 +1,3 @@
 pub mod infrastructure;
 pub mod puzzle;
+pub mod user;
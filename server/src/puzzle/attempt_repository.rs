@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::puzzle::glicko;
+use crate::puzzle::review_repository::{InMemoryReviewRepository, SaveReview};
+use crate::puzzle::training_set_repository::{InMemoryTrainingSetRepository, Progress};
+use crate::puzzle::types::{Attempt, PuzzleId, Rating, RatingHistoryEntry, Review, TrainingSetId};
+use crate::user::types::UserId;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait AttemptRepository: Send + Sync {
+    async fn record(&self, attempt: RecordAttempt) -> anyhow::Result<Option<RecordedAttempt>>;
+    async fn find_recent(&self, user_id: UserId, limit: usize) -> anyhow::Result<Vec<Attempt>>;
+    async fn find_attempted_puzzle_ids(
+        &self,
+        user_id: UserId,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<HashSet<PuzzleId>>;
+    async fn find_by_set(
+        &self,
+        user_id: UserId,
+        training_set_id: TrainingSetId,
+    ) -> anyhow::Result<Vec<Attempt>>;
+    async fn find_rating(&self, user_id: UserId) -> anyhow::Result<Option<Rating>>;
+    async fn find_rating_history(&self, user_id: UserId)
+        -> anyhow::Result<Vec<RatingHistoryEntry>>;
+}
+
+/// Advances the set from `from` to `to`, rates the user against `outcome` and saves `review`,
+/// all or nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordAttempt {
+    pub user_id: UserId,
+    pub training_set_id: TrainingSetId,
+    pub puzzle_id: PuzzleId,
+    pub from: Progress,
+    pub to: Progress,
+    pub solved: bool,
+    pub solve_time_ms: u32,
+    pub attempted_at: DateTime<Utc>,
+    pub outcome: glicko::Outcome,
+    pub review: Option<SaveReview>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedAttempt {
+    pub attempt: Attempt,
+    pub rating: Rating,
+    pub review: Option<Review>,
+}
+
+pub struct InMemoryAttemptRepository {
+    training_sets: InMemoryTrainingSetRepository,
+    reviews: InMemoryReviewRepository,
+    attempts: RwLock<Vec<(Attempt, Rating)>>,
+}
+
+impl InMemoryAttemptRepository {
+    pub fn new(
+        training_sets: InMemoryTrainingSetRepository,
+        reviews: InMemoryReviewRepository,
+    ) -> InMemoryAttemptRepository {
+        InMemoryAttemptRepository {
+            training_sets,
+            reviews,
+            attempts: RwLock::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl AttemptRepository for InMemoryAttemptRepository {
+    #[instrument(level = "debug", skip_all, fields(user_id = %attempt.user_id, puzzle_id = attempt.puzzle_id))]
+    async fn record(&self, attempt: RecordAttempt) -> anyhow::Result<Option<RecordedAttempt>> {
+        let mut attempts = self.attempts.write();
+        if !self.training_sets.compare_and_set_progress(
+            attempt.user_id,
+            attempt.training_set_id,
+            attempt.from,
+            attempt.to,
+        ) {
+            return Ok(None);
+        }
+        let current = attempts
+            .iter()
+            .rev()
+            .find(|(recorded, _)| recorded.user_id == attempt.user_id)
+            .map(|(_, rating)| *rating)
+            .unwrap_or_default();
+        let rating = glicko::rate(&current, &[attempt.outcome]);
+        let recorded = Attempt {
+            id: Uuid::new_v4(),
+            user_id: attempt.user_id,
+            training_set_id: attempt.training_set_id,
+            puzzle_id: attempt.puzzle_id,
+            cycle: attempt.from.cycles_done,
+            solved: attempt.solved,
+            solve_time_ms: attempt.solve_time_ms,
+            attempted_at: attempt.attempted_at,
+        };
+        attempts.push((recorded.clone(), rating));
+        Ok(Some(RecordedAttempt {
+            attempt: recorded,
+            rating,
+            review: attempt.review.map(|review| self.reviews.upsert(review)),
+        }))
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_recent(&self, user_id: UserId, limit: usize) -> anyhow::Result<Vec<Attempt>> {
+        Ok(self
+            .attempts
+            .read()
+            .iter()
+            .rev()
+            .filter(|(attempt, _)| attempt.user_id == user_id)
+            .take(limit)
+            .map(|(attempt, _)| attempt.clone())
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_attempted_puzzle_ids(
+        &self,
+        user_id: UserId,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<HashSet<PuzzleId>> {
+        Ok(self
+            .attempts
+            .read()
+            .iter()
+            .filter(|(attempt, _)| attempt.user_id == user_id && attempt.attempted_at >= since)
+            .map(|(attempt, _)| attempt.puzzle_id)
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_set(
+        &self,
+        user_id: UserId,
+        training_set_id: TrainingSetId,
+    ) -> anyhow::Result<Vec<Attempt>> {
+        Ok(self
+            .attempts
+            .read()
+            .iter()
+            .filter(|(attempt, _)| {
+                attempt.user_id == user_id && attempt.training_set_id == training_set_id
+            })
+            .map(|(attempt, _)| attempt.clone())
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_rating(&self, user_id: UserId) -> anyhow::Result<Option<Rating>> {
+        Ok(self
+            .attempts
+            .read()
+            .iter()
+            .rev()
+            .find(|(attempt, _)| attempt.user_id == user_id)
+            .map(|(_, rating)| *rating))
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_rating_history(
+        &self,
+        user_id: UserId,
+    ) -> anyhow::Result<Vec<RatingHistoryEntry>> {
+        Ok(self
+            .attempts
+            .read()
+            .iter()
+            .filter(|(attempt, _)| attempt.user_id == user_id)
+            .map(|(attempt, rating)| RatingHistoryEntry {
+                rating: *rating,
+                attempt_id: attempt.id,
+                rated_at: attempt.attempted_at,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::puzzle_repository::InMemoryPuzzleRepository;
+    use crate::puzzle::repository_contract::attempt_repository_contract_tests;
+    use crate::puzzle::review_repository::InMemoryReviewRepository;
+    use crate::puzzle::training_set_repository::InMemoryTrainingSetRepository;
+    use crate::user::user_repository::InMemoryUserRepository;
+
+    use super::InMemoryAttemptRepository;
+
+    attempt_repository_contract_tests!({
+        let training_sets = InMemoryTrainingSetRepository::new();
+        (
+            InMemoryPuzzleRepository::new(),
+            training_sets.clone(),
+            InMemoryUserRepository::new(),
+            InMemoryAttemptRepository::new(training_sets, InMemoryReviewRepository::new()),
+        )
+    });
+}
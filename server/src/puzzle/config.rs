@@ -1,10 +1,152 @@
-use crate::puzzle::puzzle_repository::DummyPuzzleRepository;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{ensure, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::infrastructure::config::Config;
+use crate::infrastructure::postgres::PostgresPool;
+use crate::puzzle::attempt_repository::{AttemptRepository, InMemoryAttemptRepository};
+use crate::puzzle::import::import_csv;
+use crate::puzzle::index::IndexedPuzzleRepository;
+use crate::puzzle::postgres::{
+    PostgresAttemptRepository, PostgresPuzzleRepository, PostgresReviewRepository,
+    PostgresTrainingSetRepository,
+};
+use crate::puzzle::puzzle_repository::{InMemoryPuzzleRepository, PuzzleRepository};
+use crate::puzzle::review_repository::{InMemoryReviewRepository, ReviewRepository};
 use crate::puzzle::service::PuzzleServiceImpl;
-use crate::puzzle::training_set_repository::DummyTrainingSetRepository;
+use crate::puzzle::training_set_repository::{
+    InMemoryTrainingSetRepository, TrainingSetRepository,
+};
 use crate::puzzle::PuzzleService;
 
-pub fn make_service() -> impl PuzzleService {
-    let puzzle_repository = DummyPuzzleRepository;
-    let training_set_repository = DummyTrainingSetRepository;
-    PuzzleServiceImpl::new(puzzle_repository, training_set_repository)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SetLimits {
+    pub max_set_name_length: usize,
+    pub min_set_size: usize,
+    pub max_set_size: usize,
+}
+
+impl Default for SetLimits {
+    fn default() -> Self {
+        SetLimits {
+            max_set_name_length: 100,
+            min_set_size: 5,
+            max_set_size: 1000,
+        }
+    }
+}
+
+impl SetLimits {
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.max_set_name_length > 0,
+            "limits.max_set_name_length must be positive."
+        );
+        ensure!(
+            self.min_set_size > 0,
+            "limits.min_set_size must be positive."
+        );
+        ensure!(
+            self.min_set_size <= self.max_set_size,
+            "limits.min_set_size ({}) can't exceed limits.max_set_size ({}).",
+            self.min_set_size,
+            self.max_set_size
+        );
+        Ok(())
+    }
+}
+
+pub async fn make_service(
+    config: &Config,
+    database: Option<PostgresPool>,
+) -> anyhow::Result<Arc<dyn PuzzleService + Send + Sync>> {
+    match database {
+        None => {
+            let puzzle_repository = InMemoryPuzzleRepository::new();
+            let training_set_repository = InMemoryTrainingSetRepository::new();
+            let review_repository = InMemoryReviewRepository::new();
+            let attempt_repository = InMemoryAttemptRepository::new(
+                training_set_repository.clone(),
+                review_repository.clone(),
+            );
+            let service = make_indexed_service(
+                config,
+                puzzle_repository,
+                training_set_repository,
+                attempt_repository,
+                review_repository,
+                None,
+            )
+            .await?;
+            if let Some(fixture) = &config.database.fixture {
+                import_csv(&service, fixture)
+                    .await
+                    .with_context(|| format!("failed to load fixture {}", fixture.display()))?;
+            }
+            Ok(service)
+        }
+        Some(pool) => {
+            let puzzle_repository = PostgresPuzzleRepository::new(pool.clone());
+            let training_set_repository = PostgresTrainingSetRepository::new(pool.clone());
+            let attempt_repository = PostgresAttemptRepository::new(pool.clone());
+            let review_repository = PostgresReviewRepository::new(pool);
+            let refresh = Duration::from_secs(config.database.index_refresh_seconds);
+            make_indexed_service(
+                config,
+                puzzle_repository,
+                training_set_repository,
+                attempt_repository,
+                review_repository,
+                Some(refresh),
+            )
+            .await
+        }
+    }
+}
+
+async fn make_indexed_service<P, T, A, R>(
+    config: &Config,
+    puzzle_repository: P,
+    training_set_repository: T,
+    attempt_repository: A,
+    review_repository: R,
+    refresh: Option<Duration>,
+) -> anyhow::Result<Arc<dyn PuzzleService + Send + Sync>>
+where
+    P: PuzzleRepository + 'static,
+    T: TrainingSetRepository + 'static,
+    A: AttemptRepository + 'static,
+    R: ReviewRepository + 'static,
+{
+    let limits = config.limits.clone();
+    if config.database.index {
+        let puzzle_repository = IndexedPuzzleRepository::load(puzzle_repository)
+            .await
+            .context("failed to load the puzzle index")?;
+        if let Some(period) = refresh {
+            tokio::spawn(puzzle_repository.clone().refresh_every(period));
+        }
+        Ok(Arc::new(
+            PuzzleServiceImpl::new(
+                puzzle_repository,
+                training_set_repository,
+                attempt_repository,
+                review_repository,
+            )
+            .with_limits(limits),
+        ))
+    } else {
+        Ok(Arc::new(
+            PuzzleServiceImpl::new(
+                puzzle_repository,
+                training_set_repository,
+                attempt_repository,
+                review_repository,
+            )
+            .with_limits(limits),
+        ))
+    }
 }
@@ -1,3 +0,0 @@
-pub const MAX_SET_NAME_LENGTH: usize = 100;
-pub const MIN_SET_SIZE: usize = 5;
-pub const MAX_SET_SIZE: usize = 1000;
@@ -0,0 +1,112 @@
+use std::ops::RangeInclusive;
+
+use crate::puzzle::glicko::{self, Outcome};
+use crate::puzzle::types::{Puzzle, Rating};
+
+pub const RECENT_ATTEMPTS: usize = 50;
+pub const TARGET_SUCCESS_PERCENT: RangeInclusive<u8> = 50..=95;
+pub const SLOW_SOLVE_MS: u32 = 60_000;
+const SLOW_SOLVE_SCORE: f64 = 0.5;
+const MIN_FORM_DEVIATION: f64 = 150.0;
+const HALF_WIDTH: f64 = 100.0;
+
+pub fn score(solved: bool, solve_time_ms: u32) -> f64 {
+    match solved {
+        false => 0.0,
+        true if solve_time_ms > SLOW_SOLVE_MS => SLOW_SOLVE_SCORE,
+        true => 1.0,
+    }
+}
+
+pub fn outcome(puzzle: &Puzzle, solved: bool, solve_time_ms: u32) -> Outcome {
+    Outcome {
+        rating: f64::from(puzzle.lichess_rating),
+        deviation: f64::from(puzzle.lichess_rating_deviation),
+        score: score(solved, solve_time_ms),
+    }
+}
+
+pub fn adaptive_range(
+    rating: &Rating,
+    recent: &[Outcome],
+    target_success_percent: u8,
+) -> RangeInclusive<u16> {
+    let prior = Rating {
+        deviation: rating.deviation.max(MIN_FORM_DEVIATION),
+        ..*rating
+    };
+    let form = if recent.is_empty() {
+        *rating
+    } else {
+        glicko::rate(&prior, recent)
+    };
+    let centre =
+        glicko::rating_for_expected_score(&form, f64::from(target_success_percent) / 100.0);
+    glicko::range_around(centre, HALF_WIDTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::difficulty::{adaptive_range, score};
+    use crate::puzzle::glicko::Outcome;
+    use crate::puzzle::types::Rating;
+
+    fn settled(rating: f64) -> Rating {
+        Rating {
+            rating,
+            deviation: 60.0,
+            volatility: 0.06,
+        }
+    }
+
+    fn outcomes(score: f64) -> Vec<Outcome> {
+        (0..10)
+            .map(|_| Outcome {
+                rating: 1600.0,
+                deviation: 75.0,
+                score,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_score_slow_solves_as_half() {
+        assert_eq!(score(false, 5_000), 0.0);
+        assert_eq!(score(true, 5_000), 1.0);
+        assert_eq!(score(true, 90_000), 0.5);
+    }
+
+    #[test]
+    fn should_centre_on_rating_for_even_chances() {
+        // when range for 50% success without recent attempts is computed:
+        let range = adaptive_range(&settled(1600.0), &[], 50);
+
+        // then it's centred on the rating:
+        assert_eq!(range, 1500..=1700);
+    }
+
+    #[test]
+    fn should_lower_range_for_higher_target() {
+        // when ranges for different targets are computed:
+        let easy = adaptive_range(&settled(1600.0), &[], 80);
+        let hard = adaptive_range(&settled(1600.0), &[], 60);
+
+        // then higher target gives easier puzzles:
+        assert!(easy.start() < hard.start());
+        assert!(*easy.end() < 1600);
+    }
+
+    #[test]
+    fn should_follow_recent_form() {
+        // when ranges after recent fast solves, slow solves and failures are computed:
+        let fast = adaptive_range(&settled(1600.0), &outcomes(1.0), 75);
+        let slow = adaptive_range(&settled(1600.0), &outcomes(0.5), 75);
+        let failed = adaptive_range(&settled(1600.0), &outcomes(0.0), 75);
+        let baseline = adaptive_range(&settled(1600.0), &[], 75);
+
+        // then range moves with form:
+        assert!(fast.start() > baseline.start());
+        assert!(failed.start() < baseline.start());
+        assert!(fast.start() > slow.start() && slow.start() > failed.start());
+    }
+}
@@ -1,17 +1,73 @@
-use crate::puzzle::consts::{MAX_SET_NAME_LENGTH, MAX_SET_SIZE, MIN_SET_SIZE};
+use chrono::{DateTime, Utc};
+use strum::IntoStaticStr;
 
-#[derive(Debug, thiserror::Error)]
+use crate::puzzle::types::{CriteriaFilter, PuzzleId};
+
+#[derive(Debug, thiserror::Error, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
 pub enum CreateTrainingSetError {
     #[error("Set name can't be blank.")]
     EmptyName,
-    #[error("Set name length can't exceed {}.", MAX_SET_NAME_LENGTH)]
-    NameLengthLimitExceeded,
-    #[error("Set size must be at least {}.", MIN_SET_SIZE)]
-    SizeTooSmall,
-    #[error("Set size can't exceed {}.", MAX_SET_SIZE)]
-    SizeLimitExceeded,
-    #[error("Not enough puzzles meet the criteria given.")]
-    CriteriaUnmet,
+    #[error("Set name length can't exceed {}.", max)]
+    NameLengthLimitExceeded { max: usize },
+    #[error("Set size must be at least {}.", min)]
+    SizeTooSmall { min: usize },
+    #[error("Set size can't exceed {}.", max)]
+    SizeLimitExceeded { max: usize },
+    #[error("Rating range can't be combined with adaptive difficulty.")]
+    ConflictingDifficulty,
+    #[error("Target success must be between {}% and {}%.", min, max)]
+    TargetSuccessOutOfRange { min: u8, max: u8 },
+    #[error(
+        "Not enough puzzles meet the criteria given ({} requested, {} available, {} is the most restrictive).",
+        requested,
+        available,
+        most_restrictive
+    )]
+    CriteriaUnmet {
+        requested: usize,
+        available: usize,
+        most_restrictive: CriteriaFilter,
+    },
+    #[error("Repository error.")]
+    RepositoryError { source: anyhow::Error },
+}
+
+#[derive(Debug, thiserror::Error, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum RecordAttemptError {
+    #[error("Training set not found.")]
+    SetNotFound,
+    #[error("Expected an attempt at puzzle {}, the next one in the set.", expected)]
+    NotCurrentPuzzle { expected: PuzzleId },
+    #[error("Set progress changed while recording the attempt.")]
+    ProgressConflict,
+    #[error("Repository error.")]
+    RepositoryError { source: anyhow::Error },
+}
+
+#[derive(Debug, thiserror::Error, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum RecordReviewError {
+    #[error("Review not found.")]
+    ReviewNotFound,
+    #[error("Review isn't due until {}.", due_at.to_rfc3339())]
+    NotDue { due_at: DateTime<Utc> },
+    #[error("Repository error.")]
+    RepositoryError { source: anyhow::Error },
+}
+
+#[derive(Debug, thiserror::Error, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum CyclePlanError {
+    #[error("Training set not found.")]
+    SetNotFound,
+    #[error("Training set has no cycle plan.")]
+    PlanNotFound,
+    #[error("Plan must have between 1 and {} cycles.", max)]
+    CycleCountOutOfRange { max: usize },
+    #[error("Cycles must take between 1 and {} days.", max)]
+    CycleDaysOutOfRange { max: u32 },
     #[error("Repository error.")]
     RepositoryError { source: anyhow::Error },
 }
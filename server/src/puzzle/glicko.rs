@@ -0,0 +1,229 @@
+use std::f64::consts::PI;
+use std::ops::RangeInclusive;
+
+use crate::puzzle::types::Rating;
+
+const SCALE: f64 = 173.7178;
+const BASE_RATING: f64 = 1500.0;
+const TAU: f64 = 0.5;
+const CONVERGENCE_TOLERANCE: f64 = 0.000001;
+const MIN_SUGGESTED_HALF_WIDTH: f64 = 100.0;
+const MAX_SUGGESTED_HALF_WIDTH: f64 = 300.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome {
+    pub rating: f64,
+    pub deviation: f64,
+    pub score: f64,
+}
+
+pub fn rate(player: &Rating, outcomes: &[Outcome]) -> Rating {
+    let mu = (player.rating - BASE_RATING) / SCALE;
+    let phi = player.deviation / SCALE;
+    if outcomes.is_empty() {
+        return Rating {
+            deviation: (phi.powi(2) + player.volatility.powi(2)).sqrt() * SCALE,
+            ..*player
+        };
+    }
+
+    let games: Vec<(f64, f64, f64)> = outcomes
+        .iter()
+        .map(|outcome| {
+            let mu_j = (outcome.rating - BASE_RATING) / SCALE;
+            let g = g(outcome.deviation / SCALE);
+            let expected = 1.0 / (1.0 + (-g * (mu - mu_j)).exp());
+            (g, expected, outcome.score)
+        })
+        .collect();
+    let variance = 1.0
+        / games
+            .iter()
+            .map(|(g, expected, _)| g.powi(2) * expected * (1.0 - expected))
+            .sum::<f64>();
+    let improvement: f64 = games
+        .iter()
+        .map(|(g, expected, score)| g * (score - expected))
+        .sum();
+    let delta = variance * improvement;
+
+    let volatility = volatility(phi, player.volatility, variance, delta);
+    let phi_star = (phi.powi(2) + volatility.powi(2)).sqrt();
+    let phi = 1.0 / (1.0 / phi_star.powi(2) + 1.0 / variance).sqrt();
+    let mu = mu + phi.powi(2) * improvement;
+    Rating {
+        rating: mu * SCALE + BASE_RATING,
+        deviation: phi * SCALE,
+        volatility,
+    }
+}
+
+pub fn rating_for_expected_score(player: &Rating, expected: f64) -> f64 {
+    player.rating + SCALE * (1.0 / expected - 1.0).ln() / g(player.deviation / SCALE)
+}
+
+pub fn expected_score(player: &Rating, rating: f64) -> f64 {
+    1.0 / (1.0 + (-g(player.deviation / SCALE) * (player.rating - rating) / SCALE).exp())
+}
+
+pub fn suggested_range(rating: &Rating) -> RangeInclusive<u16> {
+    let half_width = rating
+        .deviation
+        .clamp(MIN_SUGGESTED_HALF_WIDTH, MAX_SUGGESTED_HALF_WIDTH);
+    range_around(rating.rating, half_width)
+}
+
+pub fn range_around(centre: f64, half_width: f64) -> RangeInclusive<u16> {
+    let bound = |value: f64| value.round().clamp(0.0, u16::MAX as f64) as u16;
+    bound(centre - half_width)..=bound(centre + half_width)
+}
+
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi.powi(2) / PI.powi(2)).sqrt()
+}
+
+fn volatility(phi: f64, sigma: f64, variance: f64, delta: f64) -> f64 {
+    let a = sigma.powi(2).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        ex * (delta.powi(2) - phi.powi(2) - variance - ex)
+            / (2.0 * (phi.powi(2) + variance + ex).powi(2))
+            - (x - a) / TAU.powi(2)
+    };
+
+    let mut lower = a;
+    let mut upper = if delta.powi(2) > phi.powi(2) + variance {
+        (delta.powi(2) - phi.powi(2) - variance).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * TAU) < 0.0 {
+            k += 1.0;
+        }
+        a - k * TAU
+    };
+    let mut f_lower = f(lower);
+    let mut f_upper = f(upper);
+    while (upper - lower).abs() > CONVERGENCE_TOLERANCE {
+        let candidate = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+        let f_candidate = f(candidate);
+        if f_candidate * f_upper <= 0.0 {
+            lower = upper;
+            f_lower = f_upper;
+        } else {
+            f_lower /= 2.0;
+        }
+        upper = candidate;
+        f_upper = f_candidate;
+    }
+    (lower / 2.0).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::glicko::{
+        expected_score, rate, rating_for_expected_score, suggested_range, Outcome,
+    };
+    use crate::puzzle::types::Rating;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn should_rate_example_from_glicko2_paper() {
+        // given player and results from Glickman's example:
+        let player = Rating {
+            rating: 1500.0,
+            deviation: 200.0,
+            volatility: 0.06,
+        };
+        let outcomes = [
+            Outcome {
+                rating: 1400.0,
+                deviation: 30.0,
+                score: 1.0,
+            },
+            Outcome {
+                rating: 1550.0,
+                deviation: 100.0,
+                score: 0.0,
+            },
+            Outcome {
+                rating: 1700.0,
+                deviation: 300.0,
+                score: 0.0,
+            },
+        ];
+
+        // when player is rated:
+        let rated = rate(&player, &outcomes);
+
+        // then rating matches the paper:
+        assert_close(rated.rating, 1464.06, 0.01);
+        assert_close(rated.deviation, 151.52, 0.01);
+        assert_close(rated.volatility, 0.05999, 0.00001);
+    }
+
+    #[test]
+    fn should_move_towards_result() {
+        // given new player:
+        let player = Rating::default();
+        let puzzle = |score| Outcome {
+            rating: 1500.0,
+            deviation: 75.0,
+            score,
+        };
+
+        // when they solve or fail an even puzzle:
+        let solved = rate(&player, &[puzzle(1.0)]);
+        let failed = rate(&player, &[puzzle(0.0)]);
+
+        // then rating moves symmetrically and gets more certain:
+        assert!(solved.rating > 1500.0);
+        assert_close(solved.rating - 1500.0, 1500.0 - failed.rating, 0.001);
+        assert!(solved.deviation < player.deviation);
+    }
+
+    #[test]
+    fn should_suggest_range_around_rating() {
+        // given uncertain, settled and extreme ratings:
+        let rating = |rating, deviation| Rating {
+            rating,
+            deviation,
+            volatility: 0.06,
+        };
+
+        // then ranges are centred on them, within bounds:
+        assert_eq!(suggested_range(&Rating::default()), 1200..=1800);
+        assert_eq!(suggested_range(&rating(1834.6, 45.0)), 1735..=1935);
+        assert_eq!(suggested_range(&rating(50.0, 150.0)), 0..=200);
+    }
+
+    #[test]
+    fn should_find_rating_for_expected_score() {
+        // given player:
+        let player = Rating {
+            rating: 1600.0,
+            deviation: 80.0,
+            volatility: 0.06,
+        };
+
+        // when rating for even, likely and unlikely success is found:
+        let even = rating_for_expected_score(&player, 0.5);
+        let likely = rating_for_expected_score(&player, 0.75);
+        let unlikely = rating_for_expected_score(&player, 0.25);
+
+        // then it's symmetric around the player and inverse to expected score:
+        assert_close(even, 1600.0, 0.001);
+        assert!(likely < 1450.0);
+        assert_close(likely - 1600.0, 1600.0 - unlikely, 0.001);
+        assert_close(expected_score(&player, likely), 0.75, 0.000001);
+        assert_close(expected_score(&player, unlikely), 0.25, 0.000001);
+    }
+}
@@ -0,0 +1,39 @@
+use std::path::Path;
+
+use crate::infrastructure::metrics;
+use crate::puzzle::types::LichessPuzzleImport;
+use crate::puzzle::PuzzleService;
+
+pub async fn import_csv(
+    puzzle_service: &impl PuzzleService,
+    path: impl AsRef<Path>,
+) -> anyhow::Result<usize> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)?;
+
+    let mut count = 0;
+    for record in reader.deserialize::<LichessPuzzleImport>() {
+        let result = match record {
+            Ok(lichess_puzzle) => puzzle_service.import_puzzle(lichess_puzzle).await,
+            Err(error) => Err(error.into()),
+        };
+        match result {
+            Ok(_) => {
+                metrics::PUZZLES_IMPORTED
+                    .with_label_values(&["imported"])
+                    .inc();
+                count += 1;
+            }
+            Err(error) => {
+                metrics::PUZZLES_IMPORTED
+                    .with_label_values(&["failed"])
+                    .inc();
+                tracing::warn!(%error, "skipped puzzle");
+            }
+        }
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,498 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use strum::EnumCount;
+use tracing::instrument;
+
+use crate::puzzle::puzzle_repository::{CreatePuzzle, PuzzleRepository, PuzzleSummary};
+use crate::puzzle::types::{Puzzle, PuzzleId, Theme, ThemeChoice};
+
+const BUCKET_WIDTH: u16 = 25;
+// Ids are assigned before commit, so a puzzle committed late by a concurrent import can have a
+// lower id than the last one indexed. Refreshes look that far back to pick it up.
+const REFRESH_OVERLAP: PuzzleId = 1000;
+
+#[derive(Debug, Default, Clone)]
+struct Bitset {
+    words: Vec<u64>,
+}
+
+impl Bitset {
+    fn insert(&mut self, position: usize) {
+        let word = position / 64;
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= 1 << (position % 64);
+    }
+
+    fn word(&self, word: usize) -> u64 {
+        self.words.get(word).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    ids: Vec<PuzzleId>,
+    ratings: Vec<u16>,
+    themes: Vec<Bitset>,
+}
+
+impl Default for Bucket {
+    fn default() -> Self {
+        Bucket {
+            ids: Vec::new(),
+            ratings: Vec::new(),
+            themes: vec![Bitset::default(); Theme::COUNT],
+        }
+    }
+}
+
+impl Bucket {
+    fn lowest_rating(index: usize) -> u32 {
+        index as u32 * u32::from(BUCKET_WIDTH)
+    }
+
+    fn highest_rating(index: usize) -> u32 {
+        Bucket::lowest_rating(index + 1) - 1
+    }
+
+    fn mask(&self, index: usize, rating: &RangeInclusive<u16>, themes: &ThemeChoice) -> Vec<u64> {
+        let len = self.ids.len();
+        let mut mask: Vec<u64> = match themes {
+            ThemeChoice::HealthyMix => {
+                let mut mask = vec![u64::MAX; len.div_ceil(64)];
+                if !len.is_multiple_of(64) {
+                    *mask.last_mut().unwrap() = (1 << (len % 64)) - 1;
+                }
+                mask
+            }
+            ThemeChoice::Themes(themes) => (0..len.div_ceil(64))
+                .map(|word| {
+                    themes.iter().fold(0, |bits, &theme| {
+                        bits | self.themes[theme as usize].word(word)
+                    })
+                })
+                .collect(),
+        };
+
+        let fully_in_range = u32::from(*rating.start()) <= Bucket::lowest_rating(index)
+            && Bucket::highest_rating(index) <= u32::from(*rating.end());
+        if !fully_in_range {
+            for (position, puzzle_rating) in self.ratings.iter().enumerate() {
+                if !rating.contains(puzzle_rating) {
+                    mask[position / 64] &= !(1 << (position % 64));
+                }
+            }
+        }
+        mask
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PuzzleIndex {
+    buckets: Vec<Bucket>,
+    positions: HashMap<PuzzleId, (usize, usize)>,
+    last_id: Option<PuzzleId>,
+}
+
+impl PuzzleIndex {
+    pub fn new() -> PuzzleIndex {
+        PuzzleIndex::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn last_id(&self) -> Option<PuzzleId> {
+        self.last_id
+    }
+
+    pub fn insert(&mut self, id: PuzzleId, rating: u16, themes: &[Theme]) {
+        if self.positions.contains_key(&id) {
+            return;
+        }
+        self.last_id = self.last_id.max(Some(id));
+        let index = usize::from(rating / BUCKET_WIDTH);
+        if index >= self.buckets.len() {
+            self.buckets.resize_with(index + 1, Bucket::default);
+        }
+        let bucket = &mut self.buckets[index];
+        let position = bucket.ids.len();
+        bucket.ids.push(id);
+        bucket.ratings.push(rating);
+        for &theme in themes {
+            bucket.themes[theme as usize].insert(position);
+        }
+        self.positions.insert(id, (index, position));
+    }
+
+    fn candidates(
+        &self,
+        rating: &RangeInclusive<u16>,
+        themes: &ThemeChoice,
+        excluded: &HashSet<PuzzleId>,
+    ) -> Vec<(usize, Vec<u64>)> {
+        if rating.is_empty() || self.buckets.is_empty() {
+            return Vec::new();
+        }
+        let first = usize::from(rating.start() / BUCKET_WIDTH);
+        let last = usize::from(rating.end() / BUCKET_WIDTH).min(self.buckets.len() - 1);
+        let mut candidates: Vec<_> = (first..=last)
+            .map(|index| (index, self.buckets[index].mask(index, rating, themes)))
+            .collect();
+
+        for id in excluded {
+            if let Some(&(index, position)) = self.positions.get(id) {
+                if (first..=last).contains(&index) {
+                    candidates[index - first].1[position / 64] &= !(1 << (position % 64));
+                }
+            }
+        }
+        candidates
+    }
+
+    pub fn count(
+        &self,
+        rating: &RangeInclusive<u16>,
+        themes: &ThemeChoice,
+        excluded: &HashSet<PuzzleId>,
+    ) -> usize {
+        self.candidates(rating, themes, excluded)
+            .iter()
+            .flat_map(|(_, mask)| mask)
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn sample(
+        &self,
+        rng: &mut impl Rng,
+        count: usize,
+        rating: &RangeInclusive<u16>,
+        themes: &ThemeChoice,
+        excluded: &HashSet<PuzzleId>,
+    ) -> Vec<PuzzleId> {
+        let candidates = self.candidates(rating, themes, excluded);
+        let total: usize = candidates
+            .iter()
+            .flat_map(|(_, mask)| mask)
+            .map(|word| word.count_ones() as usize)
+            .sum();
+
+        let mut ranks = rand::seq::index::sample(rng, total, count.min(total)).into_vec();
+        ranks.sort_unstable();
+
+        let mut ids = Vec::with_capacity(ranks.len());
+        let mut ranks = ranks.into_iter().peekable();
+        let mut seen = 0;
+        'buckets: for (index, mask) in &candidates {
+            for (word_index, &word) in mask.iter().enumerate() {
+                let ones = word.count_ones() as usize;
+                while let Some(&rank) = ranks.peek() {
+                    if rank >= seen + ones {
+                        break;
+                    }
+                    let position = word_index * 64 + nth_set_bit(word, rank - seen);
+                    ids.push(self.buckets[*index].ids[position]);
+                    ranks.next();
+                }
+                seen += ones;
+                if ranks.peek().is_none() {
+                    break 'buckets;
+                }
+            }
+        }
+        ids.shuffle(rng);
+        ids
+    }
+}
+
+fn nth_set_bit(mut word: u64, n: usize) -> usize {
+    for _ in 0..n {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+pub struct IndexedPuzzleRepository<R>
+where
+    R: PuzzleRepository,
+{
+    inner: Arc<R>,
+    index: Arc<RwLock<PuzzleIndex>>,
+}
+
+impl<R> Clone for IndexedPuzzleRepository<R>
+where
+    R: PuzzleRepository,
+{
+    fn clone(&self) -> Self {
+        IndexedPuzzleRepository {
+            inner: self.inner.clone(),
+            index: self.index.clone(),
+        }
+    }
+}
+
+impl<R> IndexedPuzzleRepository<R>
+where
+    R: PuzzleRepository,
+{
+    pub async fn load(inner: R) -> anyhow::Result<IndexedPuzzleRepository<R>> {
+        let repository = IndexedPuzzleRepository {
+            inner: Arc::new(inner),
+            index: Arc::new(RwLock::new(PuzzleIndex::new())),
+        };
+        repository.refresh().await?;
+        Ok(repository)
+    }
+
+    /// Indexes puzzles created since the last refresh without going through this repository,
+    /// e.g. by the importer. Returns how many were added.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn refresh(&self) -> anyhow::Result<usize> {
+        let after = self
+            .index
+            .read()
+            .last_id()
+            .map(|id| id.saturating_sub(REFRESH_OVERLAP));
+        let summaries = self.inner.find_summaries(after).await?;
+        let mut index = self.index.write();
+        let indexed = index.len();
+        for summary in &summaries {
+            index.insert(summary.id, summary.lichess_rating, &summary.themes);
+        }
+        Ok(index.len() - indexed)
+    }
+
+    pub async fn refresh_every(self, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match self.refresh().await {
+                Ok(0) => {}
+                Ok(added) => tracing::info!(added, "indexed new puzzles"),
+                Err(error) => tracing::warn!(error = ?error, "failed to refresh the puzzle index"),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<R> PuzzleRepository for IndexedPuzzleRepository<R>
+where
+    R: PuzzleRepository,
+{
+    #[instrument(level = "debug", skip_all, fields(lichess_id = %puzzle.lichess_id))]
+    async fn create(&self, puzzle: CreatePuzzle) -> anyhow::Result<Puzzle> {
+        let puzzle = self.inner.create(puzzle).await?;
+        self.index
+            .write()
+            .insert(puzzle.id, puzzle.lichess_rating, &puzzle.themes);
+        Ok(puzzle)
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn find(&self) -> anyhow::Result<Vec<Puzzle>> {
+        self.inner.find().await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(ids = ids.len()))]
+    async fn find_by_ids(&self, ids: &[PuzzleId]) -> anyhow::Result<Vec<Puzzle>> {
+        self.inner.find_by_ids(ids).await
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn has_puzzles(&self) -> anyhow::Result<bool> {
+        self.inner.has_puzzles().await
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn estimate_count(&self) -> anyhow::Result<usize> {
+        Ok(self.index.read().len())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_summaries(&self, after: Option<PuzzleId>) -> anyhow::Result<Vec<PuzzleSummary>> {
+        self.inner.find_summaries(after).await
+    }
+
+    #[instrument(level = "debug", skip(self, excluded), fields(excluded = excluded.len()))]
+    async fn find_random(
+        &self,
+        count: usize,
+        rating: &RangeInclusive<u16>,
+        themes: &ThemeChoice,
+        excluded: &HashSet<PuzzleId>,
+    ) -> anyhow::Result<Vec<Puzzle>> {
+        let ids =
+            self.index
+                .read()
+                .sample(&mut rand::thread_rng(), count, rating, themes, excluded);
+        self.inner.find_by_ids(&ids).await
+    }
+
+    #[instrument(level = "debug", skip(self, excluded), fields(excluded = excluded.len()))]
+    async fn count_matching(
+        &self,
+        rating: &RangeInclusive<u16>,
+        themes: &ThemeChoice,
+        excluded: &HashSet<PuzzleId>,
+    ) -> anyhow::Result<usize> {
+        Ok(self.index.read().count(rating, themes, excluded))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::puzzle::index::{IndexedPuzzleRepository, PuzzleIndex};
+    use crate::puzzle::puzzle_repository::{
+        CreatePuzzle, InMemoryPuzzleRepository, PuzzleRepository,
+    };
+    use crate::puzzle::repository_contract::puzzle_repository_contract_tests;
+    use crate::puzzle::types::{PuzzleId, Theme, ThemeChoice};
+
+    puzzle_repository_contract_tests!(IndexedPuzzleRepository::load(
+        InMemoryPuzzleRepository::new()
+    )
+    .await
+    .unwrap());
+
+    const ALL_THEMES: [Theme; 4] = [Theme::Fork, Theme::Pin, Theme::Skewer, Theme::Mate];
+
+    #[test]
+    fn should_agree_with_linear_scan() {
+        // given many puzzles spread across buckets:
+        let mut rng = StdRng::seed_from_u64(7);
+        let puzzles: Vec<(PuzzleId, u16, Vec<Theme>)> = (0..5000)
+            .map(|id| {
+                let themes = ALL_THEMES
+                    .iter()
+                    .copied()
+                    .filter(|_| rng.gen_bool(0.3))
+                    .collect();
+                (id, rng.gen_range(600..=2800), themes)
+            })
+            .collect();
+        let mut index = PuzzleIndex::new();
+        for (id, rating, themes) in &puzzles {
+            index.insert(*id, *rating, themes);
+        }
+        let excluded: HashSet<PuzzleId> = (0..5000).step_by(7).collect();
+
+        for (rating, themes) in [
+            (1000..=1999, ThemeChoice::HealthyMix),
+            (1013..=1587, ThemeChoice::Themes(vec![Theme::Fork])),
+            (
+                0..=u16::MAX,
+                ThemeChoice::Themes(vec![Theme::Pin, Theme::Mate]),
+            ),
+            (2790..=2790, ThemeChoice::HealthyMix),
+            (3000..=4000, ThemeChoice::HealthyMix),
+        ] {
+            // when puzzles are counted and sampled:
+            let count = index.count(&rating, &themes, &excluded);
+            let sample = index.sample(&mut rng, count + 10, &rating, &themes, &excluded);
+
+            // then the same puzzles as in a linear scan are found:
+            let mut expected: Vec<PuzzleId> = puzzles
+                .iter()
+                .filter(|(id, puzzle_rating, puzzle_themes)| {
+                    rating.contains(puzzle_rating)
+                        && themes.matches(puzzle_themes)
+                        && !excluded.contains(id)
+                })
+                .map(|(id, _, _)| *id)
+                .collect();
+            expected.sort_unstable();
+            let mut sample_sorted = sample.clone();
+            sample_sorted.sort_unstable();
+            assert_eq!(count, expected.len());
+            assert_eq!(sample_sorted, expected);
+        }
+    }
+
+    fn sample_puzzle(lichess_id: &str) -> CreatePuzzle {
+        CreatePuzzle {
+            fen: "fen".to_string(),
+            moves: "e2e4".to_string(),
+            lichess_id: lichess_id.to_string(),
+            lichess_rating: 1500,
+            lichess_rating_deviation: 80,
+            lichess_popularity: 90,
+            lichess_play_count: 100,
+            themes: vec![Theme::Fork],
+            lichess_game_url: "https://lichess.org/game".to_string(),
+        }
+    }
+
+    async fn count(repository: &impl PuzzleRepository) -> usize {
+        repository
+            .count_matching(&(0..=3000), &ThemeChoice::HealthyMix, &HashSet::new())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_index_puzzles_created_elsewhere_on_refresh() {
+        // given indexed repository:
+        let inner = InMemoryPuzzleRepository::new();
+        inner.create(sample_puzzle("before")).await.unwrap();
+        let repository = IndexedPuzzleRepository::load(inner.clone()).await.unwrap();
+
+        // and puzzles created behind its back, like by the importer:
+        inner.create(sample_puzzle("after")).await.unwrap();
+        inner.create(sample_puzzle("later")).await.unwrap();
+        assert_eq!(count(&repository).await, 1);
+
+        // when index is refreshed:
+        let added = repository.refresh().await.unwrap();
+
+        // then the new puzzles are indexed once:
+        assert_eq!(added, 2);
+        assert_eq!(count(&repository).await, 3);
+        assert_eq!(repository.refresh().await.unwrap(), 0);
+    }
+
+    #[test]
+    fn should_sample_without_repetitions() {
+        // given puzzles in a single bucket spanning several words:
+        let mut index = PuzzleIndex::new();
+        for id in 0..200 {
+            index.insert(id, 1500, &[Theme::Fork]);
+        }
+
+        // when a few are sampled:
+        let sample = index.sample(
+            &mut StdRng::seed_from_u64(1),
+            50,
+            &(1500..=1500),
+            &ThemeChoice::Themes(vec![Theme::Fork]),
+            &HashSet::new(),
+        );
+
+        // then they are distinct:
+        let distinct: HashSet<_> = sample.iter().collect();
+        assert_eq!(sample.len(), 50);
+        assert_eq!(distinct.len(), 50);
+    }
+}
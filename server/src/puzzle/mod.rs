@@ -1,12 +1,27 @@
-pub use config::make_service;
-pub use rest::make_router;
+pub use config::{make_service, SetLimits};
+pub use import::import_csv;
+pub use rest::{make_legacy_router, make_router};
 pub use service::PuzzleService;
 
+mod attempt_repository;
 mod config;
-pub(self) mod consts;
+mod difficulty;
 pub mod errors;
-mod puzzle_repository;
-mod rest;
+mod glicko;
+mod import;
+pub mod index;
+pub mod postgres;
+pub mod puzzle_repository;
+#[cfg(test)]
+mod repository_contract;
+pub(crate) mod rest;
+mod review_repository;
 mod service;
+mod srs;
+mod stats;
+#[cfg(test)]
+mod test_fixtures;
 mod training_set_repository;
 pub mod types;
+mod weakness;
+mod woodpecker;
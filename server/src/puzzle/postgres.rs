@@ -0,0 +1,802 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::GenericClient;
+use tokio_postgres::Row;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::infrastructure::postgres::PostgresPool;
+use crate::puzzle::attempt_repository::{AttemptRepository, RecordAttempt, RecordedAttempt};
+use crate::puzzle::glicko;
+use crate::puzzle::puzzle_repository::{CreatePuzzle, PuzzleRepository, PuzzleSummary};
+use crate::puzzle::review_repository::{ReviewRepository, SaveReview};
+use crate::puzzle::training_set_repository::{CreateTrainingSet, TrainingSetRepository};
+use crate::puzzle::types::{
+    AdaptiveDifficulty, Attempt, CyclePlan, Puzzle, PuzzleId, Rating, RatingHistoryEntry, Review,
+    ReviewId, Schedule, Theme, ThemeChoice, TrainingSet, TrainingSetId,
+};
+use crate::user::types::UserId;
+
+const PUZZLE_COLUMNS: &str = "id, fen, moves, lichess_id, lichess_rating, \
+    lichess_rating_deviation, lichess_popularity, lichess_play_count, themes, lichess_game_url";
+
+const TRAINING_SET_QUERY: &str = "SELECT s.id, s.user_id, s.name, s.rating_min, s.rating_max, \
+    s.themes, s.target_success_percent, s.cycle_days, s.current_progress, s.cycles_done, \
+    array_agg(p.puzzle_id ORDER BY p.position) AS puzzle_ids \
+    FROM training_sets s JOIN training_set_puzzles p ON p.training_set_id = s.id";
+
+const PUZZLE_MATCHES: &str = "lichess_rating BETWEEN $1 AND $2 \
+    AND ($3::TEXT[] IS NULL OR themes && $3) \
+    AND NOT (id = ANY($4))";
+
+fn theme_names(themes: &[Theme]) -> Vec<String> {
+    themes.iter().map(Theme::to_string).collect()
+}
+
+fn parse_themes(names: Vec<String>) -> anyhow::Result<Vec<Theme>> {
+    names
+        .iter()
+        .map(|name| Theme::from_str(name).with_context(|| format!("unknown theme {}", name)))
+        .collect()
+}
+
+fn theme_choice_names(themes: &ThemeChoice) -> Option<Vec<String>> {
+    match themes {
+        ThemeChoice::Themes(themes) => Some(theme_names(themes)),
+        ThemeChoice::HealthyMix => None,
+    }
+}
+
+fn puzzle_ids(ids: &HashSet<PuzzleId>) -> Vec<i64> {
+    ids.iter().map(|&id| id as i64).collect()
+}
+
+fn puzzle_from_row(row: &Row) -> anyhow::Result<Puzzle> {
+    Ok(Puzzle {
+        id: row.try_get::<_, i64>("id")? as PuzzleId,
+        fen: row.try_get("fen")?,
+        moves: row.try_get("moves")?,
+        lichess_id: row.try_get("lichess_id")?,
+        lichess_rating: row.try_get::<_, i32>("lichess_rating")?.try_into()?,
+        lichess_rating_deviation: row
+            .try_get::<_, i32>("lichess_rating_deviation")?
+            .try_into()?,
+        lichess_popularity: row.try_get::<_, i16>("lichess_popularity")?.try_into()?,
+        lichess_play_count: row.try_get::<_, i64>("lichess_play_count")?.try_into()?,
+        themes: parse_themes(row.try_get("themes")?)?,
+        lichess_game_url: row.try_get("lichess_game_url")?,
+    })
+}
+
+fn training_set_from_row(row: &Row) -> anyhow::Result<TrainingSet> {
+    let rating_min: i32 = row.try_get("rating_min")?;
+    let rating_max: i32 = row.try_get("rating_max")?;
+    let themes: Option<Vec<String>> = row.try_get("themes")?;
+    let target_success_percent: Option<i16> = row.try_get("target_success_percent")?;
+    let cycle_days: Option<Vec<i64>> = row.try_get("cycle_days")?;
+    Ok(TrainingSet {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        puzzle_ids: row
+            .try_get::<_, Vec<i64>>("puzzle_ids")?
+            .into_iter()
+            .map(|id| id as PuzzleId)
+            .collect(),
+        name: row.try_get("name")?,
+        rating: rating_min.try_into()?..=rating_max.try_into()?,
+        themes: match themes {
+            Some(themes) => ThemeChoice::Themes(parse_themes(themes)?),
+            None => ThemeChoice::HealthyMix,
+        },
+        adaptive: target_success_percent
+            .map(|percent| {
+                Ok::<_, anyhow::Error>(AdaptiveDifficulty {
+                    target_success_percent: percent.try_into()?,
+                })
+            })
+            .transpose()?,
+        plan: cycle_days
+            .map(|cycle_days| {
+                Ok::<_, anyhow::Error>(CyclePlan {
+                    cycle_days: cycle_days
+                        .into_iter()
+                        .map(u32::try_from)
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .transpose()?,
+        current_progress: row.try_get::<_, i64>("current_progress")?.try_into()?,
+        cycles_done: row.try_get::<_, i64>("cycles_done")?.try_into()?,
+    })
+}
+
+pub struct PostgresPuzzleRepository {
+    pool: PostgresPool,
+}
+
+impl PostgresPuzzleRepository {
+    pub fn new(pool: PostgresPool) -> PostgresPuzzleRepository {
+        PostgresPuzzleRepository { pool }
+    }
+}
+
+#[async_trait]
+impl PuzzleRepository for PostgresPuzzleRepository {
+    #[instrument(level = "debug", skip_all, fields(lichess_id = %puzzle.lichess_id))]
+    async fn create(&self, puzzle: CreatePuzzle) -> anyhow::Result<Puzzle> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO puzzles (fen, moves, lichess_id, lichess_rating, \
+                     lichess_rating_deviation, lichess_popularity, lichess_play_count, themes, \
+                     lichess_game_url) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+                 RETURNING {}",
+                    PUZZLE_COLUMNS
+                ),
+                &[
+                    &puzzle.fen,
+                    &puzzle.moves,
+                    &puzzle.lichess_id,
+                    &i32::from(puzzle.lichess_rating),
+                    &i32::from(puzzle.lichess_rating_deviation),
+                    &i16::from(puzzle.lichess_popularity),
+                    &i64::from(puzzle.lichess_play_count),
+                    &theme_names(&puzzle.themes),
+                    &puzzle.lichess_game_url,
+                ],
+            )
+            .await?;
+        puzzle_from_row(&row)
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn find(&self) -> anyhow::Result<Vec<Puzzle>> {
+        let client = self.pool.get().await?;
+        client
+            .query(
+                &format!("SELECT {} FROM puzzles ORDER BY id", PUZZLE_COLUMNS),
+                &[],
+            )
+            .await?
+            .iter()
+            .map(puzzle_from_row)
+            .collect()
+    }
+
+    #[instrument(level = "debug", skip_all, fields(ids = ids.len()))]
+    async fn find_by_ids(&self, ids: &[PuzzleId]) -> anyhow::Result<Vec<Puzzle>> {
+        let client = self.pool.get().await?;
+        let ids_param: Vec<i64> = ids.iter().map(|&id| id as i64).collect();
+        let mut found = client
+            .query(
+                &format!("SELECT {} FROM puzzles WHERE id = ANY($1)", PUZZLE_COLUMNS),
+                &[&ids_param],
+            )
+            .await?
+            .iter()
+            .map(|row| puzzle_from_row(row).map(|puzzle| (puzzle.id, puzzle)))
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
+        Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn has_puzzles(&self) -> anyhow::Result<bool> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_one("SELECT EXISTS (SELECT 1 FROM puzzles)", &[])
+            .await?;
+        Ok(row.try_get(0)?)
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn estimate_count(&self) -> anyhow::Result<usize> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_one(
+                "SELECT greatest(reltuples, 0)::BIGINT FROM pg_class \
+                 WHERE oid = 'puzzles'::regclass",
+                &[],
+            )
+            .await?;
+        Ok(row.try_get::<_, i64>(0)?.try_into()?)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_summaries(&self, after: Option<PuzzleId>) -> anyhow::Result<Vec<PuzzleSummary>> {
+        let client = self.pool.get().await?;
+        client
+            .query(
+                "SELECT id, lichess_rating, themes FROM puzzles WHERE id > $1 ORDER BY id",
+                &[&after.map_or(-1, |id| id as i64)],
+            )
+            .await?
+            .iter()
+            .map(|row| {
+                Ok(PuzzleSummary {
+                    id: row.try_get::<_, i64>("id")? as PuzzleId,
+                    lichess_rating: row.try_get::<_, i32>("lichess_rating")?.try_into()?,
+                    themes: parse_themes(row.try_get("themes")?)?,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(level = "debug", skip(self, excluded), fields(excluded = excluded.len()))]
+    async fn find_random(
+        &self,
+        count: usize,
+        rating: &RangeInclusive<u16>,
+        themes: &ThemeChoice,
+        excluded: &HashSet<PuzzleId>,
+    ) -> anyhow::Result<Vec<Puzzle>> {
+        let client = self.pool.get().await?;
+        client
+            .query(
+                &format!(
+                    "SELECT {} FROM puzzles WHERE {} ORDER BY random() LIMIT $5",
+                    PUZZLE_COLUMNS, PUZZLE_MATCHES
+                ),
+                &[
+                    &i32::from(*rating.start()),
+                    &i32::from(*rating.end()),
+                    &theme_choice_names(themes),
+                    &puzzle_ids(excluded),
+                    &i64::try_from(count)?,
+                ],
+            )
+            .await?
+            .iter()
+            .map(puzzle_from_row)
+            .collect()
+    }
+
+    #[instrument(level = "debug", skip(self, excluded), fields(excluded = excluded.len()))]
+    async fn count_matching(
+        &self,
+        rating: &RangeInclusive<u16>,
+        themes: &ThemeChoice,
+        excluded: &HashSet<PuzzleId>,
+    ) -> anyhow::Result<usize> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_one(
+                &format!("SELECT count(*) FROM puzzles WHERE {}", PUZZLE_MATCHES),
+                &[
+                    &i32::from(*rating.start()),
+                    &i32::from(*rating.end()),
+                    &theme_choice_names(themes),
+                    &puzzle_ids(excluded),
+                ],
+            )
+            .await?;
+        Ok(row.try_get::<_, i64>(0)?.try_into()?)
+    }
+}
+
+pub struct PostgresTrainingSetRepository {
+    pool: PostgresPool,
+}
+
+impl PostgresTrainingSetRepository {
+    pub fn new(pool: PostgresPool) -> PostgresTrainingSetRepository {
+        PostgresTrainingSetRepository { pool }
+    }
+}
+
+#[async_trait]
+impl TrainingSetRepository for PostgresTrainingSetRepository {
+    #[instrument(level = "debug", skip_all, fields(size = training_set.puzzle_ids.len()))]
+    async fn create(&self, training_set: CreateTrainingSet) -> anyhow::Result<TrainingSet> {
+        let id = Uuid::new_v4();
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        transaction
+            .execute(
+                "INSERT INTO training_sets (id, user_id, name, rating_min, rating_max, themes, \
+                 target_success_percent, current_progress, cycles_done) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                &[
+                    &id,
+                    &training_set.user_id,
+                    &training_set.name,
+                    &i32::from(*training_set.rating.start()),
+                    &i32::from(*training_set.rating.end()),
+                    &theme_choice_names(&training_set.themes),
+                    &training_set
+                        .adaptive
+                        .map(|adaptive| i16::from(adaptive.target_success_percent)),
+                    &i64::from(training_set.current_progress),
+                    &i64::from(training_set.cycles_done),
+                ],
+            )
+            .await?;
+        let puzzle_ids: Vec<i64> = training_set
+            .puzzle_ids
+            .iter()
+            .map(|&id| id as i64)
+            .collect();
+        transaction
+            .execute(
+                "INSERT INTO training_set_puzzles (training_set_id, position, puzzle_id) \
+                 SELECT $1, position - 1, puzzle_id \
+                 FROM unnest($2::BIGINT[]) WITH ORDINALITY AS p (puzzle_id, position)",
+                &[&id, &puzzle_ids],
+            )
+            .await?;
+        transaction.commit().await?;
+
+        Ok(TrainingSet {
+            id,
+            user_id: training_set.user_id,
+            puzzle_ids: training_set.puzzle_ids,
+            name: training_set.name,
+            rating: training_set.rating,
+            themes: training_set.themes,
+            adaptive: training_set.adaptive,
+            plan: None,
+            current_progress: training_set.current_progress,
+            cycles_done: training_set.cycles_done,
+        })
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_puzzle_ids(&self, user_id: UserId) -> anyhow::Result<HashSet<PuzzleId>> {
+        let client = self.pool.get().await?;
+        client
+            .query(
+                "SELECT DISTINCT p.puzzle_id FROM training_set_puzzles p \
+                 JOIN training_sets s ON s.id = p.training_set_id \
+                 WHERE s.user_id = $1",
+                &[&user_id],
+            )
+            .await?
+            .iter()
+            .map(|row| Ok(row.try_get::<_, i64>(0)? as PuzzleId))
+            .collect()
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_user(&self, user_id: UserId) -> anyhow::Result<Vec<TrainingSet>> {
+        let client = self.pool.get().await?;
+        client
+            .query(
+                &format!(
+                    "{} WHERE s.user_id = $1 GROUP BY s.id ORDER BY s.name, s.id",
+                    TRAINING_SET_QUERY
+                ),
+                &[&user_id],
+            )
+            .await?
+            .iter()
+            .map(training_set_from_row)
+            .collect()
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+    ) -> anyhow::Result<Option<TrainingSet>> {
+        let client = self.pool.get().await?;
+        client
+            .query_opt(
+                &format!(
+                    "{} WHERE s.user_id = $1 AND s.id = $2 GROUP BY s.id",
+                    TRAINING_SET_QUERY
+                ),
+                &[&user_id, &id],
+            )
+            .await?
+            .as_ref()
+            .map(training_set_from_row)
+            .transpose()
+    }
+
+    #[instrument(level = "debug", skip(self, plan))]
+    async fn update_plan(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+        plan: CyclePlan,
+    ) -> anyhow::Result<bool> {
+        let client = self.pool.get().await?;
+        let cycle_days: Vec<i64> = plan
+            .cycle_days
+            .iter()
+            .map(|&days| i64::from(days))
+            .collect();
+        let updated = client
+            .execute(
+                "UPDATE training_sets SET cycle_days = $3 WHERE user_id = $1 AND id = $2",
+                &[&user_id, &id, &cycle_days],
+            )
+            .await?;
+        Ok(updated > 0)
+    }
+}
+
+const ATTEMPT_COLUMNS: &str =
+    "id, user_id, training_set_id, puzzle_id, cycle, solved, solve_time_ms, attempted_at";
+
+fn attempt_from_row(row: &Row) -> anyhow::Result<Attempt> {
+    Ok(Attempt {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        training_set_id: row.try_get("training_set_id")?,
+        puzzle_id: row.try_get::<_, i64>("puzzle_id")? as PuzzleId,
+        cycle: row.try_get::<_, i64>("cycle")?.try_into()?,
+        solved: row.try_get("solved")?,
+        solve_time_ms: row.try_get::<_, i64>("solve_time_ms")?.try_into()?,
+        attempted_at: row.try_get("attempted_at")?,
+    })
+}
+
+fn rating_from_row(row: &Row) -> anyhow::Result<Rating> {
+    Ok(Rating {
+        rating: row.try_get("rating")?,
+        deviation: row.try_get("deviation")?,
+        volatility: row.try_get("volatility")?,
+    })
+}
+
+pub struct PostgresAttemptRepository {
+    pool: PostgresPool,
+}
+
+impl PostgresAttemptRepository {
+    pub fn new(pool: PostgresPool) -> PostgresAttemptRepository {
+        PostgresAttemptRepository { pool }
+    }
+}
+
+#[async_trait]
+impl AttemptRepository for PostgresAttemptRepository {
+    #[instrument(level = "debug", skip_all, fields(user_id = %attempt.user_id, puzzle_id = attempt.puzzle_id))]
+    async fn record(&self, attempt: RecordAttempt) -> anyhow::Result<Option<RecordedAttempt>> {
+        let id = Uuid::new_v4();
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        // Ratings of a user are rated one attempt at a time, even before the first one exists.
+        transaction
+            .execute(
+                "SELECT id FROM users WHERE id = $1 FOR NO KEY UPDATE",
+                &[&attempt.user_id],
+            )
+            .await?;
+        let updated = transaction
+            .execute(
+                "UPDATE training_sets SET current_progress = $5, cycles_done = $6 \
+                 WHERE user_id = $1 AND id = $2 AND current_progress = $3 AND cycles_done = $4",
+                &[
+                    &attempt.user_id,
+                    &attempt.training_set_id,
+                    &i64::from(attempt.from.current_progress),
+                    &i64::from(attempt.from.cycles_done),
+                    &i64::from(attempt.to.current_progress),
+                    &i64::from(attempt.to.cycles_done),
+                ],
+            )
+            .await?;
+        if updated == 0 {
+            return Ok(None);
+        }
+        let current = transaction
+            .query_opt(
+                "SELECT rating, deviation, volatility FROM rating_history WHERE user_id = $1 \
+                 ORDER BY rated_at DESC, sequence DESC LIMIT 1",
+                &[&attempt.user_id],
+            )
+            .await?
+            .as_ref()
+            .map(rating_from_row)
+            .transpose()?
+            .unwrap_or_default();
+        let rating = glicko::rate(&current, &[attempt.outcome]);
+        let row = transaction
+            .query_one(
+                &format!(
+                    "INSERT INTO attempts ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+                     RETURNING {}",
+                    ATTEMPT_COLUMNS, ATTEMPT_COLUMNS
+                ),
+                &[
+                    &id,
+                    &attempt.user_id,
+                    &attempt.training_set_id,
+                    &(attempt.puzzle_id as i64),
+                    &i64::from(attempt.from.cycles_done),
+                    &attempt.solved,
+                    &i64::from(attempt.solve_time_ms),
+                    &attempt.attempted_at,
+                ],
+            )
+            .await?;
+        transaction
+            .execute(
+                "INSERT INTO rating_history (attempt_id, user_id, rating, deviation, volatility, \
+                 rated_at) VALUES ($1, $2, $3, $4, $5, $6)",
+                &[
+                    &id,
+                    &attempt.user_id,
+                    &rating.rating,
+                    &rating.deviation,
+                    &rating.volatility,
+                    &attempt.attempted_at,
+                ],
+            )
+            .await?;
+        let review = match &attempt.review {
+            Some(review) => Some(save_review(&transaction, review).await?),
+            None => None,
+        };
+        transaction.commit().await?;
+        Ok(Some(RecordedAttempt {
+            attempt: attempt_from_row(&row)?,
+            rating,
+            review,
+        }))
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_recent(&self, user_id: UserId, limit: usize) -> anyhow::Result<Vec<Attempt>> {
+        let client = self.pool.get().await?;
+        client
+            .query(
+                &format!(
+                    "SELECT {} FROM attempts WHERE user_id = $1 \
+                     ORDER BY attempted_at DESC LIMIT $2",
+                    ATTEMPT_COLUMNS
+                ),
+                &[&user_id, &i64::try_from(limit)?],
+            )
+            .await?
+            .iter()
+            .map(attempt_from_row)
+            .collect()
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_attempted_puzzle_ids(
+        &self,
+        user_id: UserId,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<HashSet<PuzzleId>> {
+        let client = self.pool.get().await?;
+        client
+            .query(
+                "SELECT DISTINCT puzzle_id FROM attempts \
+                 WHERE user_id = $1 AND attempted_at >= $2",
+                &[&user_id, &since],
+            )
+            .await?
+            .iter()
+            .map(|row| Ok(row.try_get::<_, i64>(0)? as PuzzleId))
+            .collect()
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_set(
+        &self,
+        user_id: UserId,
+        training_set_id: TrainingSetId,
+    ) -> anyhow::Result<Vec<Attempt>> {
+        let client = self.pool.get().await?;
+        client
+            .query(
+                &format!(
+                    "SELECT {} FROM attempts WHERE user_id = $1 AND training_set_id = $2 \
+                     ORDER BY attempted_at, cycle",
+                    ATTEMPT_COLUMNS
+                ),
+                &[&user_id, &training_set_id],
+            )
+            .await?
+            .iter()
+            .map(attempt_from_row)
+            .collect()
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_rating(&self, user_id: UserId) -> anyhow::Result<Option<Rating>> {
+        let client = self.pool.get().await?;
+        client
+            .query_opt(
+                "SELECT rating, deviation, volatility FROM rating_history WHERE user_id = $1 \
+                 ORDER BY rated_at DESC, sequence DESC LIMIT 1",
+                &[&user_id],
+            )
+            .await?
+            .as_ref()
+            .map(rating_from_row)
+            .transpose()
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_rating_history(
+        &self,
+        user_id: UserId,
+    ) -> anyhow::Result<Vec<RatingHistoryEntry>> {
+        let client = self.pool.get().await?;
+        client
+            .query(
+                "SELECT attempt_id, rating, deviation, volatility, rated_at FROM rating_history \
+                 WHERE user_id = $1 ORDER BY rated_at, sequence",
+                &[&user_id],
+            )
+            .await?
+            .iter()
+            .map(|row| {
+                Ok(RatingHistoryEntry {
+                    rating: rating_from_row(row)?,
+                    attempt_id: row.try_get("attempt_id")?,
+                    rated_at: row.try_get("rated_at")?,
+                })
+            })
+            .collect()
+    }
+}
+
+const REVIEW_COLUMNS: &str =
+    "id, user_id, puzzle_id, repetitions, interval_days, ease, due_at, reviewed_at";
+
+fn review_from_row(row: &Row) -> anyhow::Result<Review> {
+    Ok(Review {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        puzzle_id: row.try_get::<_, i64>("puzzle_id")? as PuzzleId,
+        schedule: Schedule {
+            repetitions: row.try_get::<_, i64>("repetitions")?.try_into()?,
+            interval_days: row.try_get::<_, i64>("interval_days")?.try_into()?,
+            ease: row.try_get("ease")?,
+        },
+        due_at: row.try_get("due_at")?,
+        reviewed_at: row.try_get("reviewed_at")?,
+    })
+}
+
+async fn save_review(client: &impl GenericClient, review: &SaveReview) -> anyhow::Result<Review> {
+    let row = client
+        .query_one(
+            &format!(
+                "INSERT INTO reviews ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+                 ON CONFLICT (user_id, puzzle_id) DO UPDATE SET \
+                 repetitions = EXCLUDED.repetitions, interval_days = EXCLUDED.interval_days, \
+                 ease = EXCLUDED.ease, due_at = EXCLUDED.due_at, \
+                 reviewed_at = EXCLUDED.reviewed_at \
+                 RETURNING {}",
+                REVIEW_COLUMNS, REVIEW_COLUMNS
+            ),
+            &[
+                &Uuid::new_v4(),
+                &review.user_id,
+                &(review.puzzle_id as i64),
+                &i64::from(review.schedule.repetitions),
+                &i64::from(review.schedule.interval_days),
+                &review.schedule.ease,
+                &review.due_at,
+                &review.reviewed_at,
+            ],
+        )
+        .await?;
+    review_from_row(&row)
+}
+
+pub struct PostgresReviewRepository {
+    pool: PostgresPool,
+}
+
+impl PostgresReviewRepository {
+    pub fn new(pool: PostgresPool) -> PostgresReviewRepository {
+        PostgresReviewRepository { pool }
+    }
+}
+
+#[async_trait]
+impl ReviewRepository for PostgresReviewRepository {
+    #[instrument(level = "debug", skip_all, fields(user_id = %review.user_id, puzzle_id = review.puzzle_id))]
+    async fn save(&self, review: SaveReview) -> anyhow::Result<Review> {
+        let client = self.pool.get().await?;
+        save_review(&client, &review).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find(&self, user_id: UserId, id: ReviewId) -> anyhow::Result<Option<Review>> {
+        let client = self.pool.get().await?;
+        client
+            .query_opt(
+                &format!(
+                    "SELECT {} FROM reviews WHERE user_id = $1 AND id = $2",
+                    REVIEW_COLUMNS
+                ),
+                &[&user_id, &id],
+            )
+            .await?
+            .as_ref()
+            .map(review_from_row)
+            .transpose()
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_due(&self, user_id: UserId, now: DateTime<Utc>) -> anyhow::Result<Vec<Review>> {
+        let client = self.pool.get().await?;
+        client
+            .query(
+                &format!(
+                    "SELECT {} FROM reviews WHERE user_id = $1 AND due_at <= $2 \
+                     ORDER BY due_at, puzzle_id",
+                    REVIEW_COLUMNS
+                ),
+                &[&user_id, &now],
+            )
+            .await?
+            .iter()
+            .map(review_from_row)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::postgres::testing::test_pool;
+    use crate::puzzle::repository_contract::{
+        attempt_repository_contract_tests, puzzle_repository_contract_tests,
+        review_repository_contract_tests, training_set_repository_contract_tests,
+    };
+
+    use crate::user::postgres::PostgresUserRepository;
+
+    use super::{
+        PostgresAttemptRepository, PostgresPuzzleRepository, PostgresReviewRepository,
+        PostgresTrainingSetRepository,
+    };
+
+    puzzle_repository_contract_tests!(
+        PostgresPuzzleRepository::new(test_pool().await),
+        #[ignore = "requires TEST_DATABASE_URL"]
+    );
+
+    training_set_repository_contract_tests!(
+        {
+            let pool = test_pool().await;
+            (
+                PostgresPuzzleRepository::new(pool.clone()),
+                PostgresTrainingSetRepository::new(pool.clone()),
+                PostgresUserRepository::new(pool),
+            )
+        },
+        #[ignore = "requires TEST_DATABASE_URL"]
+    );
+
+    attempt_repository_contract_tests!(
+        {
+            let pool = test_pool().await;
+            (
+                PostgresPuzzleRepository::new(pool.clone()),
+                PostgresTrainingSetRepository::new(pool.clone()),
+                PostgresUserRepository::new(pool.clone()),
+                PostgresAttemptRepository::new(pool),
+            )
+        },
+        #[ignore = "requires TEST_DATABASE_URL"]
+    );
+
+    review_repository_contract_tests!(
+        {
+            let pool = test_pool().await;
+            (
+                PostgresPuzzleRepository::new(pool.clone()),
+                PostgresUserRepository::new(pool.clone()),
+                PostgresReviewRepository::new(pool),
+            )
+        },
+        #[ignore = "requires TEST_DATABASE_URL"]
+    );
+}
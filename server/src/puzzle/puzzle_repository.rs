@@ -1,17 +1,37 @@
+use std::collections::{HashMap, HashSet};
 use std::ops::RangeInclusive;
+use std::sync::Arc;
 
-use crate::puzzle::types::{Puzzle, Theme, ThemeChoice};
+use anyhow::ensure;
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use rand::seq::{IteratorRandom, SliceRandom};
+use tracing::instrument;
+
+use crate::puzzle::types::{Puzzle, PuzzleId, Theme, ThemeChoice};
 
 #[cfg_attr(test, mockall::automock)]
-pub trait PuzzleRepository {
-    fn create(&self, puzzle: CreatePuzzle) -> anyhow::Result<Puzzle>;
-    fn find(&self) -> Vec<Puzzle>;
-    fn find_random(
+#[async_trait]
+pub trait PuzzleRepository: Send + Sync {
+    async fn create(&self, puzzle: CreatePuzzle) -> anyhow::Result<Puzzle>;
+    async fn find(&self) -> anyhow::Result<Vec<Puzzle>>;
+    async fn find_by_ids(&self, ids: &[PuzzleId]) -> anyhow::Result<Vec<Puzzle>>;
+    async fn has_puzzles(&self) -> anyhow::Result<bool>;
+    async fn estimate_count(&self) -> anyhow::Result<usize>;
+    async fn find_summaries(&self, after: Option<PuzzleId>) -> anyhow::Result<Vec<PuzzleSummary>>;
+    async fn find_random(
         &self,
         count: usize,
         rating: &RangeInclusive<u16>,
         themes: &ThemeChoice,
+        excluded: &HashSet<PuzzleId>,
     ) -> anyhow::Result<Vec<Puzzle>>;
+    async fn count_matching(
+        &self,
+        rating: &RangeInclusive<u16>,
+        themes: &ThemeChoice,
+        excluded: &HashSet<PuzzleId>,
+    ) -> anyhow::Result<usize>;
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -27,23 +47,153 @@ pub struct CreatePuzzle {
     pub lichess_game_url: String,
 }
 
-pub struct DummyPuzzleRepository;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PuzzleSummary {
+    pub id: PuzzleId,
+    pub lichess_rating: u16,
+    pub themes: Vec<Theme>,
+}
+
+#[derive(Default, Clone)]
+pub struct InMemoryPuzzleRepository {
+    puzzles: Arc<RwLock<Puzzles>>,
+}
+
+#[derive(Default)]
+struct Puzzles {
+    all: Vec<Puzzle>,
+    by_lichess_id: HashMap<String, PuzzleId>,
+}
+
+impl InMemoryPuzzleRepository {
+    pub fn new() -> InMemoryPuzzleRepository {
+        InMemoryPuzzleRepository::default()
+    }
+}
+
+fn matches(
+    puzzle: &Puzzle,
+    rating: &RangeInclusive<u16>,
+    themes: &ThemeChoice,
+    excluded: &HashSet<PuzzleId>,
+) -> bool {
+    rating.contains(&puzzle.lichess_rating)
+        && themes.matches(&puzzle.themes)
+        && !excluded.contains(&puzzle.id)
+}
+
+#[async_trait]
+impl PuzzleRepository for InMemoryPuzzleRepository {
+    #[instrument(level = "debug", skip_all, fields(lichess_id = %puzzle.lichess_id))]
+    async fn create(&self, puzzle: CreatePuzzle) -> anyhow::Result<Puzzle> {
+        let mut puzzles = self.puzzles.write();
+        ensure!(
+            !puzzles.by_lichess_id.contains_key(&puzzle.lichess_id),
+            "puzzle {} already exists.",
+            puzzle.lichess_id
+        );
+        let puzzle = Puzzle {
+            id: puzzles.all.len() as PuzzleId,
+            fen: puzzle.fen,
+            moves: puzzle.moves,
+            lichess_id: puzzle.lichess_id,
+            lichess_rating: puzzle.lichess_rating,
+            lichess_rating_deviation: puzzle.lichess_rating_deviation,
+            lichess_popularity: puzzle.lichess_popularity,
+            lichess_play_count: puzzle.lichess_play_count,
+            themes: puzzle.themes,
+            lichess_game_url: puzzle.lichess_game_url,
+        };
+        puzzles
+            .by_lichess_id
+            .insert(puzzle.lichess_id.clone(), puzzle.id);
+        puzzles.all.push(puzzle.clone());
+        Ok(puzzle)
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn find(&self) -> anyhow::Result<Vec<Puzzle>> {
+        Ok(self.puzzles.read().all.clone())
+    }
 
-impl PuzzleRepository for DummyPuzzleRepository {
-    fn create(&self, _puzzle: CreatePuzzle) -> anyhow::Result<Puzzle> {
-        unimplemented!()
+    #[instrument(level = "debug", skip_all, fields(ids = ids.len()))]
+    async fn find_by_ids(&self, ids: &[PuzzleId]) -> anyhow::Result<Vec<Puzzle>> {
+        let puzzles = self.puzzles.read();
+        Ok(ids
+            .iter()
+            .filter_map(|&id| puzzles.all.get(id as usize).cloned())
+            .collect())
     }
 
-    fn find(&self) -> Vec<Puzzle> {
-        unimplemented!()
+    #[instrument(level = "debug", skip_all)]
+    async fn has_puzzles(&self) -> anyhow::Result<bool> {
+        Ok(!self.puzzles.read().all.is_empty())
     }
 
-    fn find_random(
+    #[instrument(level = "debug", skip_all)]
+    async fn estimate_count(&self) -> anyhow::Result<usize> {
+        Ok(self.puzzles.read().all.len())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_summaries(&self, after: Option<PuzzleId>) -> anyhow::Result<Vec<PuzzleSummary>> {
+        Ok(self
+            .puzzles
+            .read()
+            .all
+            .iter()
+            .filter(|puzzle| after.is_none_or(|after| puzzle.id > after))
+            .map(|puzzle| PuzzleSummary {
+                id: puzzle.id,
+                lichess_rating: puzzle.lichess_rating,
+                themes: puzzle.themes.clone(),
+            })
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self, excluded), fields(excluded = excluded.len()))]
+    async fn find_random(
         &self,
-        _count: usize,
-        _rating: &RangeInclusive<u16>,
-        _themes: &ThemeChoice,
+        count: usize,
+        rating: &RangeInclusive<u16>,
+        themes: &ThemeChoice,
+        excluded: &HashSet<PuzzleId>,
     ) -> anyhow::Result<Vec<Puzzle>> {
-        unimplemented!()
+        let mut rng = rand::thread_rng();
+        let mut puzzles: Vec<Puzzle> = self
+            .puzzles
+            .read()
+            .all
+            .iter()
+            .filter(|puzzle| matches(puzzle, rating, themes, excluded))
+            .cloned()
+            .choose_multiple(&mut rng, count);
+        puzzles.shuffle(&mut rng);
+        Ok(puzzles)
     }
+
+    #[instrument(level = "debug", skip(self, excluded), fields(excluded = excluded.len()))]
+    async fn count_matching(
+        &self,
+        rating: &RangeInclusive<u16>,
+        themes: &ThemeChoice,
+        excluded: &HashSet<PuzzleId>,
+    ) -> anyhow::Result<usize> {
+        Ok(self
+            .puzzles
+            .read()
+            .all
+            .iter()
+            .filter(|puzzle| matches(puzzle, rating, themes, excluded))
+            .count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::repository_contract::puzzle_repository_contract_tests;
+
+    use super::InMemoryPuzzleRepository;
+
+    puzzle_repository_contract_tests!(InMemoryPuzzleRepository::new());
 }
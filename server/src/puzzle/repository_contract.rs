@@ -0,0 +1,1201 @@
+//! Behavior every `PuzzleRepository`, `TrainingSetRepository`, `AttemptRepository` and
+//! `ReviewRepository` implementation must share.
+//!
+//! Backends instantiate the suite with `puzzle_repository_contract_tests!`, passing an
+//! expression that yields an empty repository, `training_set_repository_contract_tests!`,
+//! passing one that yields empty puzzle, training set and user repositories sharing storage,
+//! `attempt_repository_contract_tests!`, passing those followed by an empty attempt repository
+//! sharing the training set storage,
+//! and `review_repository_contract_tests!`, passing empty puzzle, user and review repositories.
+//! Each may be followed by attributes applied to every test.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use crate::puzzle::attempt_repository::{AttemptRepository, RecordAttempt, RecordedAttempt};
+use crate::puzzle::glicko;
+use crate::puzzle::puzzle_repository::{CreatePuzzle, PuzzleRepository, PuzzleSummary};
+use crate::puzzle::review_repository::{ReviewRepository, SaveReview};
+use crate::puzzle::training_set_repository::{CreateTrainingSet, Progress, TrainingSetRepository};
+use crate::puzzle::types::{
+    AdaptiveDifficulty, CyclePlan, Puzzle, PuzzleId, Rating, Schedule, Theme, ThemeChoice,
+    TrainingSet,
+};
+use crate::user::repository_contract::create_user;
+use crate::user::types::UserId;
+use crate::user::user_repository::UserRepository;
+
+macro_rules! puzzle_repository_contract_tests {
+    ($make_repository:expr $(, #[$attribute:meta])*) => {
+        mod puzzle_repository_contract {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::puzzle::repository_contract as contract;
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_create_and_find_puzzles() {
+                contract::should_create_and_find_puzzles($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_by_ids_in_given_order() {
+                contract::should_find_by_ids_in_given_order($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_tell_whether_it_has_puzzles() {
+                contract::should_tell_whether_it_has_puzzles($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_estimate_no_puzzles_when_empty() {
+                contract::should_estimate_no_puzzles_when_empty($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_summaries_after_id() {
+                contract::should_find_summaries_after_id($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_reject_duplicate_lichess_ids() {
+                contract::should_reject_duplicate_lichess_ids($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_random_within_inclusive_rating_bounds() {
+                contract::should_find_random_within_inclusive_rating_bounds($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_random_matching_any_theme() {
+                contract::should_find_random_matching_any_theme($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_random_from_healthy_mix() {
+                contract::should_find_random_from_healthy_mix($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_random_without_excluded() {
+                contract::should_find_random_without_excluded($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_random_at_most_count() {
+                contract::should_find_random_at_most_count($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_nothing_when_empty() {
+                contract::should_find_nothing_when_empty($make_repository).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_count_matching() {
+                contract::should_count_matching($make_repository).await;
+            }
+        }
+    };
+}
+
+macro_rules! training_set_repository_contract_tests {
+    ($make_repositories:expr $(, #[$attribute:meta])*) => {
+        mod training_set_repository_contract {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::puzzle::repository_contract as contract;
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_create_sets() {
+                contract::should_create_sets($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_puzzle_ids_of_all_sets() {
+                contract::should_find_puzzle_ids_of_all_sets($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_sets_of_user() {
+                contract::should_find_sets_of_user($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_no_puzzle_ids_when_empty() {
+                contract::should_find_no_puzzle_ids_when_empty($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_update_plan_of_own_set() {
+                contract::should_update_plan_of_own_set($make_repositories).await;
+            }
+        }
+    };
+}
+
+macro_rules! attempt_repository_contract_tests {
+    ($make_repositories:expr $(, #[$attribute:meta])*) => {
+        mod attempt_repository_contract {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::puzzle::repository_contract as contract;
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_record_attempts_only_from_expected_progress() {
+                contract::should_record_attempts_only_from_expected_progress($make_repositories)
+                    .await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_save_review_with_attempt() {
+                contract::should_save_review_with_attempt($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_not_lose_rating_updates_of_concurrent_attempts() {
+                contract::should_not_lose_rating_updates_of_concurrent_attempts(
+                    $make_repositories,
+                )
+                .await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_recent_attempts_newest_first() {
+                contract::should_find_recent_attempts_newest_first($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_puzzles_attempted_since() {
+                contract::should_find_puzzles_attempted_since($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_attempts_of_set_in_order() {
+                contract::should_find_attempts_of_set_in_order($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_latest_rating_of_user() {
+                contract::should_find_latest_rating_of_user($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_rating_history_in_order() {
+                contract::should_find_rating_history_in_order($make_repositories).await;
+            }
+        }
+    };
+}
+
+pub(crate) use attempt_repository_contract_tests;
+
+macro_rules! review_repository_contract_tests {
+    ($make_repositories:expr $(, #[$attribute:meta])*) => {
+        mod review_repository_contract {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::puzzle::repository_contract as contract;
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_save_one_review_per_puzzle() {
+                contract::should_save_one_review_per_puzzle($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_due_reviews_in_order() {
+                contract::should_find_due_reviews_in_order($make_repositories).await;
+            }
+        }
+    };
+}
+
+pub(crate) use review_repository_contract_tests;
+
+pub(crate) use puzzle_repository_contract_tests;
+pub(crate) use training_set_repository_contract_tests;
+
+fn sample_puzzle(lichess_id: &str, rating: u16, themes: Vec<Theme>) -> CreatePuzzle {
+    CreatePuzzle {
+        fen: format!("{}-fen", lichess_id),
+        moves: format!("{}-moves", lichess_id),
+        lichess_id: lichess_id.to_string(),
+        lichess_rating: rating,
+        lichess_rating_deviation: 75,
+        lichess_popularity: 90,
+        lichess_play_count: 1000,
+        themes,
+        lichess_game_url: format!("https://lichess.org/{}", lichess_id),
+    }
+}
+
+fn sample_set(user_id: UserId, name: &str, puzzle_ids: Vec<PuzzleId>) -> CreateTrainingSet {
+    CreateTrainingSet {
+        user_id,
+        puzzle_ids,
+        name: name.to_string(),
+        rating: 1500..=1600,
+        themes: ThemeChoice::HealthyMix,
+        adaptive: None,
+        current_progress: 0,
+        cycles_done: 0,
+    }
+}
+
+async fn create_all(repository: &impl PuzzleRepository, puzzles: Vec<CreatePuzzle>) -> Vec<Puzzle> {
+    let mut created = Vec::new();
+    for puzzle in puzzles {
+        created.push(repository.create(puzzle).await.unwrap());
+    }
+    created
+}
+
+fn ratings(puzzles: &[Puzzle]) -> Vec<u16> {
+    let mut ratings: Vec<_> = puzzles.iter().map(|puzzle| puzzle.lichess_rating).collect();
+    ratings.sort_unstable();
+    ratings
+}
+
+fn lichess_ids(puzzles: &[Puzzle]) -> Vec<&str> {
+    let mut ids: Vec<_> = puzzles
+        .iter()
+        .map(|puzzle| puzzle.lichess_id.as_str())
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+pub async fn should_create_and_find_puzzles(repository: impl PuzzleRepository) {
+    // when puzzles are created:
+    let first = repository
+        .create(sample_puzzle("first", 1500, vec![Theme::Fork]))
+        .await
+        .unwrap();
+    let second = repository
+        .create(sample_puzzle("second", 1600, vec![Theme::Pin]))
+        .await
+        .unwrap();
+
+    // then they have the data given and distinct ids:
+    assert_eq!(first.lichess_id, "first");
+    assert_eq!(first.fen, "first-fen");
+    assert_eq!(first.moves, "first-moves");
+    assert_eq!(first.lichess_rating, 1500);
+    assert_eq!(first.lichess_rating_deviation, 75);
+    assert_eq!(first.lichess_popularity, 90);
+    assert_eq!(first.lichess_play_count, 1000);
+    assert_eq!(first.themes, vec![Theme::Fork]);
+    assert_eq!(first.lichess_game_url, "https://lichess.org/first");
+    assert_ne!(first.id, second.id);
+
+    // and they can be found:
+    let mut found = repository.find().await.unwrap();
+    found.sort_by_key(|puzzle| puzzle.id);
+    let mut expected = vec![first, second];
+    expected.sort_by_key(|puzzle| puzzle.id);
+    assert_eq!(found, expected);
+}
+
+pub async fn should_find_by_ids_in_given_order(repository: impl PuzzleRepository) {
+    // given puzzles:
+    let puzzles = create_all(
+        &repository,
+        vec![
+            sample_puzzle("first", 1500, vec![]),
+            sample_puzzle("second", 1500, vec![]),
+            sample_puzzle("third", 1500, vec![]),
+        ],
+    )
+    .await;
+
+    // when some are found by ids, including an unknown one:
+    let unknown = puzzles.iter().map(|puzzle| puzzle.id).max().unwrap() + 1;
+    let found = repository
+        .find_by_ids(&[puzzles[2].id, unknown, puzzles[0].id])
+        .await
+        .unwrap();
+
+    // then known puzzles are found in the order given:
+    assert_eq!(found, vec![puzzles[2].clone(), puzzles[0].clone()]);
+}
+
+pub async fn should_tell_whether_it_has_puzzles(repository: impl PuzzleRepository) {
+    // given empty repository:
+    assert!(!repository.has_puzzles().await.unwrap());
+
+    // when puzzle is created:
+    repository
+        .create(sample_puzzle("first", 1500, vec![]))
+        .await
+        .unwrap();
+
+    // then it has puzzles:
+    assert!(repository.has_puzzles().await.unwrap());
+}
+
+pub async fn should_estimate_no_puzzles_when_empty(repository: impl PuzzleRepository) {
+    // when empty repository's puzzles are estimated:
+    let estimate = repository.estimate_count().await.unwrap();
+
+    // then there are none:
+    assert_eq!(estimate, 0);
+}
+
+pub async fn should_find_summaries_after_id(repository: impl PuzzleRepository) {
+    // given puzzles:
+    let puzzles = create_all(
+        &repository,
+        vec![
+            sample_puzzle("first", 1400, vec![Theme::Fork]),
+            sample_puzzle("second", 1500, vec![]),
+            sample_puzzle("third", 1600, vec![Theme::Pin, Theme::Mate]),
+        ],
+    )
+    .await;
+
+    // when all summaries and those after the first puzzle are found:
+    let all = repository.find_summaries(None).await.unwrap();
+    let after_first = repository
+        .find_summaries(Some(puzzles[0].id))
+        .await
+        .unwrap();
+
+    // then they carry ratings and themes in id order:
+    let summaries: Vec<_> = puzzles
+        .iter()
+        .map(|puzzle| PuzzleSummary {
+            id: puzzle.id,
+            lichess_rating: puzzle.lichess_rating,
+            themes: puzzle.themes.clone(),
+        })
+        .collect();
+    assert_eq!(all, summaries);
+    assert_eq!(after_first, summaries[1..]);
+}
+
+pub async fn should_reject_duplicate_lichess_ids(repository: impl PuzzleRepository) {
+    // given existing puzzle:
+    repository
+        .create(sample_puzzle("duplicate", 1500, vec![]))
+        .await
+        .unwrap();
+
+    // when puzzle with the same Lichess id is created:
+    let result = repository
+        .create(sample_puzzle("duplicate", 1600, vec![]))
+        .await;
+
+    // then it's rejected:
+    assert!(result.is_err());
+    assert_eq!(repository.find().await.unwrap().len(), 1);
+}
+
+pub async fn should_find_random_within_inclusive_rating_bounds(repository: impl PuzzleRepository) {
+    // given puzzles on and around rating bounds:
+    create_all(
+        &repository,
+        vec![
+            sample_puzzle("below", 1499, vec![]),
+            sample_puzzle("lower", 1500, vec![]),
+            sample_puzzle("inside", 1550, vec![]),
+            sample_puzzle("upper", 1600, vec![]),
+            sample_puzzle("above", 1601, vec![]),
+        ],
+    )
+    .await;
+
+    // when random puzzles are found:
+    let found = repository
+        .find_random(
+            10,
+            &(1500..=1600),
+            &ThemeChoice::HealthyMix,
+            &HashSet::new(),
+        )
+        .await
+        .unwrap();
+
+    // then both bounds are included:
+    assert_eq!(ratings(&found), vec![1500, 1550, 1600]);
+}
+
+pub async fn should_find_random_matching_any_theme(repository: impl PuzzleRepository) {
+    // given puzzles with various themes:
+    create_all(
+        &repository,
+        vec![
+            sample_puzzle("fork", 1500, vec![Theme::Fork, Theme::Middlegame]),
+            sample_puzzle("pin", 1500, vec![Theme::Pin]),
+            sample_puzzle("both", 1500, vec![Theme::Pin, Theme::Fork]),
+            sample_puzzle("skewer", 1500, vec![Theme::Skewer]),
+            sample_puzzle("none", 1500, vec![]),
+        ],
+    )
+    .await;
+
+    // when random puzzles with chosen themes are found:
+    let themes = ThemeChoice::Themes(vec![Theme::Fork, Theme::Pin]);
+    let found = repository
+        .find_random(10, &(0..=3000), &themes, &HashSet::new())
+        .await
+        .unwrap();
+
+    // then puzzles with any of the themes are found:
+    assert_eq!(lichess_ids(&found), vec!["both", "fork", "pin"]);
+}
+
+pub async fn should_find_random_from_healthy_mix(repository: impl PuzzleRepository) {
+    // given puzzles with various themes:
+    create_all(
+        &repository,
+        vec![
+            sample_puzzle("fork", 1500, vec![Theme::Fork]),
+            sample_puzzle("pin", 1500, vec![Theme::Pin]),
+            sample_puzzle("none", 1500, vec![]),
+        ],
+    )
+    .await;
+
+    // when random puzzles from a healthy mix are found:
+    let found = repository
+        .find_random(10, &(0..=3000), &ThemeChoice::HealthyMix, &HashSet::new())
+        .await
+        .unwrap();
+
+    // then all puzzles are found:
+    assert_eq!(lichess_ids(&found), vec!["fork", "none", "pin"]);
+}
+
+pub async fn should_find_random_without_excluded(repository: impl PuzzleRepository) {
+    // given puzzles:
+    let puzzles = create_all(
+        &repository,
+        vec![
+            sample_puzzle("first", 1500, vec![]),
+            sample_puzzle("second", 1500, vec![]),
+            sample_puzzle("third", 1500, vec![]),
+        ],
+    )
+    .await;
+
+    // when random puzzles are found excluding some:
+    let excluded = [puzzles[0].id, puzzles[2].id].into();
+    let found = repository
+        .find_random(10, &(0..=3000), &ThemeChoice::HealthyMix, &excluded)
+        .await
+        .unwrap();
+
+    // then excluded puzzles are left out:
+    assert_eq!(lichess_ids(&found), vec!["second"]);
+}
+
+pub async fn should_find_random_at_most_count(repository: impl PuzzleRepository) {
+    // given puzzles:
+    create_all(
+        &repository,
+        (0..10)
+            .map(|i| sample_puzzle(&format!("puzzle-{}", i), 1500, vec![]))
+            .collect(),
+    )
+    .await;
+
+    // when fewer random puzzles are requested:
+    let found = repository
+        .find_random(4, &(0..=3000), &ThemeChoice::HealthyMix, &HashSet::new())
+        .await
+        .unwrap();
+
+    // then no more than requested are found, without repetitions:
+    let ids: HashSet<_> = found.iter().map(|puzzle| puzzle.id).collect();
+    assert_eq!(found.len(), 4);
+    assert_eq!(ids.len(), 4);
+}
+
+pub async fn should_find_nothing_when_empty(repository: impl PuzzleRepository) {
+    // when nothing was created:
+    let found = repository
+        .find_random(10, &(0..=3000), &ThemeChoice::HealthyMix, &HashSet::new())
+        .await
+        .unwrap();
+    let count = repository
+        .count_matching(&(0..=3000), &ThemeChoice::HealthyMix, &HashSet::new())
+        .await
+        .unwrap();
+
+    // then nothing is found:
+    assert!(repository.find().await.unwrap().is_empty());
+    assert!(found.is_empty());
+    assert_eq!(count, 0);
+}
+
+pub async fn should_count_matching(repository: impl PuzzleRepository) {
+    // given puzzles:
+    let puzzles = create_all(
+        &repository,
+        vec![
+            sample_puzzle("below", 1499, vec![Theme::Fork]),
+            sample_puzzle("lower", 1500, vec![Theme::Fork]),
+            sample_puzzle("upper", 1600, vec![Theme::Pin]),
+            sample_puzzle("excluded", 1550, vec![Theme::Fork]),
+            sample_puzzle("above", 1601, vec![Theme::Fork]),
+        ],
+    )
+    .await;
+    let excluded = [puzzles[3].id].into();
+    let forks = ThemeChoice::Themes(vec![Theme::Fork]);
+
+    // when puzzles are counted:
+    let count = |themes: ThemeChoice, excluded: HashSet<PuzzleId>| {
+        let repository = &repository;
+        async move {
+            repository
+                .count_matching(&(1500..=1600), &themes, &excluded)
+                .await
+                .unwrap()
+        }
+    };
+
+    // then all criteria are applied:
+    assert_eq!(count(ThemeChoice::HealthyMix, HashSet::new()).await, 3);
+    assert_eq!(count(forks.clone(), HashSet::new()).await, 2);
+    assert_eq!(count(forks, excluded).await, 1);
+}
+
+async fn create_puzzle_ids(repository: &impl PuzzleRepository, count: usize) -> Vec<PuzzleId> {
+    create_all(
+        repository,
+        (0..count)
+            .map(|i| sample_puzzle(&format!("puzzle-{}", i), 1500, vec![]))
+            .collect(),
+    )
+    .await
+    .iter()
+    .map(|puzzle| puzzle.id)
+    .collect()
+}
+
+pub async fn should_create_sets<P, T, U>((puzzle_repository, repository, users): (P, T, U))
+where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+{
+    // given puzzles and user:
+    let ids = create_puzzle_ids(&puzzle_repository, 4).await;
+    let user = create_user(&users, "user").await;
+
+    // when sets are created:
+    let first = repository
+        .create(sample_set(user.id, "first", ids[..3].to_vec()))
+        .await
+        .unwrap();
+    let second = repository
+        .create(CreateTrainingSet {
+            adaptive: Some(AdaptiveDifficulty {
+                target_success_percent: 75,
+            }),
+            ..sample_set(user.id, "second", ids[3..].to_vec())
+        })
+        .await
+        .unwrap();
+
+    // then they have the data given and distinct ids:
+    assert_eq!(first.user_id, user.id);
+    assert_eq!(first.name, "first");
+    assert_eq!(first.puzzle_ids, ids[..3].to_vec());
+    assert_eq!(first.rating, 1500..=1600);
+    assert_eq!(first.themes, ThemeChoice::HealthyMix);
+    assert_eq!(first.current_progress, 0);
+    assert_eq!(first.cycles_done, 0);
+    assert_eq!(first.adaptive, None);
+    assert_ne!(first.id, second.id);
+
+    // and adaptive difficulty is kept:
+    let found = repository.find(user.id, second.id).await.unwrap().unwrap();
+    assert_eq!(
+        found.adaptive,
+        Some(AdaptiveDifficulty {
+            target_success_percent: 75
+        })
+    );
+}
+
+pub async fn should_find_puzzle_ids_of_all_sets<P, T, U>(
+    (puzzle_repository, repository, users): (P, T, U),
+) where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+{
+    // given user's sets sharing some puzzles:
+    let ids = create_puzzle_ids(&puzzle_repository, 5).await;
+    let user = create_user(&users, "user").await;
+    repository
+        .create(sample_set(user.id, "first", ids[0..3].to_vec()))
+        .await
+        .unwrap();
+    repository
+        .create(sample_set(user.id, "second", ids[2..4].to_vec()))
+        .await
+        .unwrap();
+
+    // and other user's set:
+    let other = create_user(&users, "other").await;
+    repository
+        .create(sample_set(other.id, "other", ids[4..].to_vec()))
+        .await
+        .unwrap();
+
+    // when user's puzzle ids are found:
+    let found = repository.find_puzzle_ids(user.id).await.unwrap();
+
+    // then puzzles of every set of theirs are included:
+    assert_eq!(found, ids[0..4].iter().copied().collect());
+}
+
+pub async fn should_find_sets_of_user<P, T, U>((puzzle_repository, repository, users): (P, T, U))
+where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+{
+    // given sets of different users:
+    let ids = create_puzzle_ids(&puzzle_repository, 3).await;
+    let user = create_user(&users, "user").await;
+    let other = create_user(&users, "other").await;
+    let first = repository
+        .create(sample_set(user.id, "first", ids.clone()))
+        .await
+        .unwrap();
+    let second = repository
+        .create(sample_set(user.id, "second", ids[1..].to_vec()))
+        .await
+        .unwrap();
+    let others = repository
+        .create(sample_set(other.id, "other", ids.clone()))
+        .await
+        .unwrap();
+
+    // when user's sets are found:
+    let mut found = repository.find_by_user(user.id).await.unwrap();
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // then only theirs are, with puzzles in order:
+    assert_eq!(found, vec![first.clone(), second]);
+    assert_eq!(
+        repository.find(user.id, first.id).await.unwrap(),
+        Some(first)
+    );
+    assert_eq!(repository.find(user.id, others.id).await.unwrap(), None);
+}
+
+pub async fn should_find_no_puzzle_ids_when_empty<P, T, U>((_, repository, users): (P, T, U))
+where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+{
+    // when nothing was created:
+    let user = create_user(&users, "user").await;
+    let ids = repository.find_puzzle_ids(user.id).await.unwrap();
+
+    // then nothing is found:
+    assert!(ids.is_empty());
+}
+
+pub async fn should_update_plan_of_own_set<P, T, U>(
+    (puzzle_repository, repository, users): (P, T, U),
+) where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+{
+    // given set without plan:
+    let ids = create_puzzle_ids(&puzzle_repository, 3).await;
+    let user = create_user(&users, "user").await;
+    let other = create_user(&users, "other").await;
+    let set = repository
+        .create(sample_set(user.id, "set", ids))
+        .await
+        .unwrap();
+    assert_eq!(set.plan, None);
+    let plan = CyclePlan {
+        cycle_days: vec![28, 14, 7],
+    };
+
+    // when plan is updated by other user and by owner:
+    let by_other = repository
+        .update_plan(other.id, set.id, plan.clone())
+        .await
+        .unwrap();
+    let updated = repository
+        .update_plan(user.id, set.id, plan.clone())
+        .await
+        .unwrap();
+
+    // then only the owner's update applies:
+    assert!(!by_other);
+    assert!(updated);
+    let found = repository.find(user.id, set.id).await.unwrap().unwrap();
+    assert_eq!(found.plan, Some(plan));
+}
+
+fn sample_time(minutes: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+fn sample_outcome(solved: bool) -> glicko::Outcome {
+    glicko::Outcome {
+        rating: 1500.0,
+        deviation: 75.0,
+        score: if solved { 1.0 } else { 0.0 },
+    }
+}
+
+fn sample_attempt(set: &TrainingSet, position: usize, minutes: i64) -> RecordAttempt {
+    let solved = position.is_multiple_of(2);
+    let next = position as u32 + 1;
+    RecordAttempt {
+        user_id: set.user_id,
+        training_set_id: set.id,
+        puzzle_id: set.puzzle_ids[position],
+        from: Progress {
+            current_progress: position as u32,
+            cycles_done: 0,
+        },
+        to: if (next as usize) < set.puzzle_ids.len() {
+            Progress {
+                current_progress: next,
+                cycles_done: 0,
+            }
+        } else {
+            Progress {
+                current_progress: 0,
+                cycles_done: 1,
+            }
+        },
+        solved,
+        solve_time_ms: 30_000,
+        attempted_at: sample_time(minutes),
+        outcome: sample_outcome(solved),
+        review: None,
+    }
+}
+
+async fn record(attempts: &impl AttemptRepository, attempt: RecordAttempt) -> RecordedAttempt {
+    attempts.record(attempt).await.unwrap().unwrap()
+}
+
+async fn create_attempt_set<P, T, U>(
+    (puzzle_repository, repository, users): (&P, &T, &U),
+    username: &str,
+) -> TrainingSet
+where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+{
+    let puzzles = create_all(
+        puzzle_repository,
+        (0..3)
+            .map(|i| sample_puzzle(&format!("{}-{}", username, i), 1500, vec![]))
+            .collect(),
+    )
+    .await;
+    let user = create_user(users, username).await;
+    repository
+        .create(sample_set(
+            user.id,
+            username,
+            puzzles.iter().map(|puzzle| puzzle.id).collect(),
+        ))
+        .await
+        .unwrap()
+}
+
+pub async fn should_record_attempts_only_from_expected_progress<P, T, U, A>(
+    (puzzles, sets, users, attempts): (P, T, U, A),
+) where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+    A: AttemptRepository,
+{
+    // given set:
+    let set = create_attempt_set((&puzzles, &sets, &users), "user").await;
+    let other = create_user(&users, "other").await;
+
+    // when attempt is recorded by other user, as expected and again from stale progress:
+    let by_other = attempts
+        .record(RecordAttempt {
+            user_id: other.id,
+            ..sample_attempt(&set, 0, 0)
+        })
+        .await
+        .unwrap();
+    let recorded = attempts.record(sample_attempt(&set, 0, 0)).await.unwrap();
+    let stale = attempts.record(sample_attempt(&set, 0, 1)).await.unwrap();
+
+    // then only the expected one is recorded, with the data given:
+    assert_eq!(by_other, None);
+    assert_eq!(stale, None);
+    let RecordedAttempt {
+        attempt,
+        rating,
+        review,
+    } = recorded.unwrap();
+    assert_eq!(review, None);
+    assert_eq!(attempt.user_id, set.user_id);
+    assert_eq!(attempt.training_set_id, set.id);
+    assert_eq!(attempt.puzzle_id, set.puzzle_ids[0]);
+    assert_eq!(attempt.cycle, 0);
+    assert!(attempt.solved);
+    assert_eq!(attempt.solve_time_ms, 30_000);
+    assert_eq!(attempt.attempted_at, sample_time(0));
+    assert!(attempts.find_recent(other.id, 10).await.unwrap().is_empty());
+    assert_eq!(
+        attempts.find_recent(set.user_id, 10).await.unwrap(),
+        vec![attempt]
+    );
+
+    // and the user is rated against the puzzle:
+    assert_eq!(
+        rating,
+        glicko::rate(&Rating::default(), &[sample_outcome(true)])
+    );
+    assert_eq!(
+        attempts.find_rating(set.user_id).await.unwrap(),
+        Some(rating)
+    );
+
+    // and the set advances:
+    let found = sets.find(set.user_id, set.id).await.unwrap().unwrap();
+    assert_eq!(Progress::of(&found), sample_attempt(&set, 0, 0).to);
+}
+
+pub async fn should_save_review_with_attempt<P, T, U, A>(
+    (puzzles, sets, users, attempts): (P, T, U, A),
+) where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+    A: AttemptRepository,
+{
+    // given set:
+    let set = create_attempt_set((&puzzles, &sets, &users), "user").await;
+    let review = sample_review(set.user_id, set.puzzle_ids[0], 1);
+
+    // when attempt is recorded with a review, and again from stale progress:
+    let recorded = attempts
+        .record(RecordAttempt {
+            review: Some(review.clone()),
+            ..sample_attempt(&set, 0, 0)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    let stale = attempts
+        .record(RecordAttempt {
+            review: Some(sample_review(set.user_id, set.puzzle_ids[0], 6)),
+            ..sample_attempt(&set, 0, 1)
+        })
+        .await
+        .unwrap();
+
+    // then the review is saved along with the attempt only:
+    let saved = recorded.review.unwrap();
+    assert_eq!(saved.user_id, review.user_id);
+    assert_eq!(saved.puzzle_id, review.puzzle_id);
+    assert_eq!(saved.schedule, review.schedule);
+    assert_eq!(saved.due_at, review.due_at);
+    assert_eq!(stale, None);
+}
+
+pub async fn should_not_lose_rating_updates_of_concurrent_attempts<P, T, U, A>(
+    (puzzles, sets, users, attempts): (P, T, U, A),
+) where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+    A: AttemptRepository,
+{
+    // given two sets of the same user:
+    let set = create_attempt_set((&puzzles, &sets, &users), "user").await;
+    let other_set = sets
+        .create(sample_set(set.user_id, "other", set.puzzle_ids.clone()))
+        .await
+        .unwrap();
+
+    // when attempts at both are recorded concurrently:
+    let mut expected = Rating::default();
+    for position in 0..set.puzzle_ids.len() {
+        let (first, second) = tokio::join!(
+            attempts.record(sample_attempt(&set, position, position as i64)),
+            attempts.record(sample_attempt(&other_set, position, position as i64)),
+        );
+        assert!(first.unwrap().is_some());
+        assert!(second.unwrap().is_some());
+        let outcome = sample_outcome(position.is_multiple_of(2));
+        expected = glicko::rate(&glicko::rate(&expected, &[outcome]), &[outcome]);
+    }
+
+    // then every attempt is rated on top of the previous one:
+    let history = attempts.find_rating_history(set.user_id).await.unwrap();
+    assert_eq!(history.len(), 2 * set.puzzle_ids.len());
+    assert_eq!(
+        attempts.find_rating(set.user_id).await.unwrap(),
+        Some(expected)
+    );
+}
+
+pub async fn should_find_recent_attempts_newest_first<P, T, U, A>(
+    (puzzles, sets, users, attempts): (P, T, U, A),
+) where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+    A: AttemptRepository,
+{
+    // given attempts of different users:
+    let set = create_attempt_set((&puzzles, &sets, &users), "user").await;
+    let others = create_attempt_set((&puzzles, &sets, &users), "other").await;
+    let mut created = Vec::new();
+    for position in 0..3 {
+        created.push(
+            record(&attempts, sample_attempt(&set, position, position as i64))
+                .await
+                .attempt,
+        );
+    }
+    record(&attempts, sample_attempt(&others, 0, 5)).await;
+
+    // when recent attempts are found:
+    let recent = attempts.find_recent(set.user_id, 2).await.unwrap();
+
+    // then the latest ones of the user are, newest first:
+    let ids: Vec<_> = recent.iter().map(|attempt| attempt.id).collect();
+    assert_eq!(ids, vec![created[2].id, created[1].id]);
+}
+
+pub async fn should_find_puzzles_attempted_since<P, T, U, A>(
+    (puzzles, sets, users, attempts): (P, T, U, A),
+) where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+    A: AttemptRepository,
+{
+    // given attempts of different users over time:
+    let set = create_attempt_set((&puzzles, &sets, &users), "user").await;
+    let others = create_attempt_set((&puzzles, &sets, &users), "other").await;
+    for position in 0..3 {
+        record(
+            &attempts,
+            sample_attempt(&set, position, position as i64 * 10),
+        )
+        .await;
+    }
+    record(&attempts, sample_attempt(&others, 0, 30)).await;
+
+    // when puzzles attempted since the second attempt are found:
+    let found = attempts
+        .find_attempted_puzzle_ids(set.user_id, sample_time(10))
+        .await
+        .unwrap();
+
+    // then they're the user's puzzles attempted at or after it:
+    assert_eq!(found, HashSet::from([set.puzzle_ids[1], set.puzzle_ids[2]]));
+}
+
+pub async fn should_find_attempts_of_set_in_order<P, T, U, A>(
+    (puzzles, sets, users, attempts): (P, T, U, A),
+) where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+    A: AttemptRepository,
+{
+    // given attempts at two sets:
+    let set = create_attempt_set((&puzzles, &sets, &users), "user").await;
+    let other_set = sets
+        .create(sample_set(set.user_id, "other", set.puzzle_ids.clone()))
+        .await
+        .unwrap();
+    let mut created = Vec::new();
+    for position in 0..3 {
+        created.push(
+            record(&attempts, sample_attempt(&set, position, position as i64))
+                .await
+                .attempt,
+        );
+    }
+    record(&attempts, sample_attempt(&other_set, 0, 1)).await;
+
+    // when attempts of the set are found, by owner and other user:
+    let found = attempts.find_by_set(set.user_id, set.id).await.unwrap();
+    let other = create_user(&users, "other").await;
+    let by_other = attempts.find_by_set(other.id, set.id).await.unwrap();
+
+    // then they're the owner's attempts at the set, oldest first:
+    assert_eq!(found, created);
+    assert!(by_other.is_empty());
+}
+
+pub async fn should_find_latest_rating_of_user<P, T, U, A>(
+    (puzzles, sets, users, attempts): (P, T, U, A),
+) where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+    A: AttemptRepository,
+{
+    // given attempts of different users:
+    let set = create_attempt_set((&puzzles, &sets, &users), "user").await;
+    let others = create_attempt_set((&puzzles, &sets, &users), "other").await;
+    let unrated = create_user(&users, "unrated").await;
+    let mut latest = None;
+    for position in 0..2 {
+        latest = Some(record(&attempts, sample_attempt(&set, position, position as i64)).await);
+    }
+    record(&attempts, sample_attempt(&others, 0, 5)).await;
+
+    // when ratings are found:
+    let rating = attempts.find_rating(set.user_id).await.unwrap();
+
+    // then the latest one of the user is:
+    assert_eq!(rating, latest.map(|recorded| recorded.rating));
+    assert_eq!(attempts.find_rating(unrated.id).await.unwrap(), None);
+}
+
+pub async fn should_find_rating_history_in_order<P, T, U, A>(
+    (puzzles, sets, users, attempts): (P, T, U, A),
+) where
+    P: PuzzleRepository,
+    T: TrainingSetRepository,
+    U: UserRepository,
+    A: AttemptRepository,
+{
+    // given attempts:
+    let set = create_attempt_set((&puzzles, &sets, &users), "user").await;
+    let mut recorded = Vec::new();
+    for position in 0..3 {
+        recorded.push(record(&attempts, sample_attempt(&set, position, position as i64)).await);
+    }
+
+    // when rating history is found:
+    let history = attempts.find_rating_history(set.user_id).await.unwrap();
+
+    // then it follows the attempts:
+    let ratings: Vec<_> = history.iter().map(|entry| entry.rating).collect();
+    let expected: Vec<_> = recorded.iter().map(|recorded| recorded.rating).collect();
+    assert_eq!(ratings, expected);
+    assert_eq!(history[2].attempt_id, recorded[2].attempt.id);
+    assert_eq!(history[2].rated_at, sample_time(2));
+}
+
+fn sample_review(user_id: UserId, puzzle_id: PuzzleId, interval_days: u32) -> SaveReview {
+    SaveReview {
+        user_id,
+        puzzle_id,
+        schedule: Schedule {
+            repetitions: 1,
+            interval_days,
+            ease: 2.5,
+        },
+        due_at: sample_time(i64::from(interval_days) * 24 * 60),
+        reviewed_at: sample_time(0),
+    }
+}
+
+pub async fn should_save_one_review_per_puzzle<P, U, R>((puzzles, users, reviews): (P, U, R))
+where
+    P: PuzzleRepository,
+    U: UserRepository,
+    R: ReviewRepository,
+{
+    // given puzzle and users:
+    let puzzle_id = create_puzzle_ids(&puzzles, 1).await[0];
+    let user = create_user(&users, "user").await;
+    let other = create_user(&users, "other").await;
+
+    // when review of the puzzle is saved twice:
+    let first = reviews
+        .save(sample_review(user.id, puzzle_id, 1))
+        .await
+        .unwrap();
+    let second = reviews
+        .save(sample_review(user.id, puzzle_id, 6))
+        .await
+        .unwrap();
+
+    // then it's updated in place:
+    assert_eq!(second.id, first.id);
+    assert_eq!(second.schedule.interval_days, 6);
+    assert_eq!(second.due_at, sample_time(6 * 24 * 60));
+    assert_eq!(reviews.find(user.id, first.id).await.unwrap(), Some(second));
+
+    // and it's not visible to other users:
+    assert_eq!(reviews.find(other.id, first.id).await.unwrap(), None);
+}
+
+pub async fn should_find_due_reviews_in_order<P, U, R>((puzzles, users, reviews): (P, U, R))
+where
+    P: PuzzleRepository,
+    U: UserRepository,
+    R: ReviewRepository,
+{
+    // given reviews of different users due on different days:
+    let ids = create_puzzle_ids(&puzzles, 3).await;
+    let user = create_user(&users, "user").await;
+    let other = create_user(&users, "other").await;
+    for (puzzle_id, interval_days) in [(ids[0], 6), (ids[1], 1), (ids[2], 3)] {
+        reviews
+            .save(sample_review(user.id, puzzle_id, interval_days))
+            .await
+            .unwrap();
+    }
+    reviews
+        .save(sample_review(other.id, ids[0], 1))
+        .await
+        .unwrap();
+
+    // when reviews due after three days are found:
+    let due = reviews
+        .find_due(user.id, sample_time(3 * 24 * 60))
+        .await
+        .unwrap();
+
+    // then they're the user's reviews due by then, earliest first:
+    let due: Vec<_> = due.iter().map(|review| review.puzzle_id).collect();
+    assert_eq!(due, vec![ids[1], ids[2]]);
+}
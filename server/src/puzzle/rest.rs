@@ -1,23 +1,1190 @@
+use std::str::FromStr;
 use std::sync::Arc;
 
-use axum::extract::State;
-use axum::routing::get;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post, MethodRouter};
 use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::IntoParams;
 
+use crate::infrastructure::api_error::{ApiError, ApiJson, ApiQuery};
+use crate::infrastructure::metrics;
 use crate::infrastructure::rest::Context;
-use crate::puzzle::types::Puzzle;
+use crate::puzzle::errors::{
+    CreateTrainingSetError, CyclePlanError, RecordAttemptError, RecordReviewError,
+};
+use crate::puzzle::types::{
+    AttemptResult, CreateTrainingSetOptions, CyclePlan, DailySession, PlanStatus,
+    PreviewTrainingSetOptions, Puzzle, RatingHistoryEntry, RatingSummary, RecordAttemptOptions,
+    RecordReviewOptions, Review, ReviewId, SetSuggestion, Statistics, Theme, ThemeChoice,
+    TrainingSet, TrainingSetId, TrainingSetPreview,
+};
 use crate::puzzle::PuzzleService;
+use crate::user::types::Scope;
+use crate::user::AuthenticatedUser;
 
 pub fn make_router<T>() -> Router<Arc<Context<T>>>
 where
     T: PuzzleService + Send + Sync + 'static,
 {
-    Router::new().route("/puzzles", get(list_puzzles))
+    routes()
+        .into_iter()
+        .fold(Router::new(), |router, (path, method_router)| {
+            router.route(path, method_router)
+        })
 }
 
-pub async fn list_puzzles<T>(State(ctx): State<Arc<Context<T>>>) -> Json<Vec<Puzzle>>
+pub(crate) fn routes<T>() -> Vec<(&'static str, MethodRouter<Arc<Context<T>>>)>
 where
     T: PuzzleService + Send + Sync + 'static,
 {
-    Json(ctx.puzzle_service.list_puzzles())
+    vec![
+        ("/puzzles", get(list_puzzles)),
+        ("/sets", get(list_sets).post(create_set)),
+        ("/sets/preview", get(preview_set)),
+        ("/sets/:id", get(get_set)),
+        ("/sets/:id/attempts", post(record_attempt)),
+        ("/sets/:id/plan", get(plan_status).put(set_plan)),
+        ("/sets/:id/stats", get(set_stats)),
+        ("/me/rating", get(get_rating)),
+        ("/me/rating/history", get(rating_history)),
+        ("/me/session", get(daily_session)),
+        ("/me/stats", get(user_stats)),
+        ("/me/weaknesses", get(suggest_set)),
+        ("/reviews/due", get(due_reviews)),
+        ("/reviews/:id", post(record_review)),
+    ]
+}
+
+const MAX_SESSION_SIZE: usize = 200;
+
+pub fn make_legacy_router<T>() -> Router<Arc<Context<T>>>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/puzzles", get(list_puzzles))
+        .route("/sets", post(create_set))
+        .route("/sets/preview", get(preview_set))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/puzzles",
+    tag = "puzzles",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "All puzzles.", body = [Puzzle]),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn list_puzzles<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<Vec<Puzzle>>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    auth.require(Scope::ReadPuzzles)?;
+    Ok(Json(ctx.puzzle_service.list_puzzles().await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sets",
+    tag = "sets",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Training sets of the user.", body = [TrainingSet]),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn list_sets<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<Vec<TrainingSet>>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ManageSets)?;
+    Ok(Json(ctx.puzzle_service.list_sets(user.id).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sets/{id}",
+    tag = "sets",
+    security(("bearer" = [])),
+    params(("id" = Uuid, Path, description = "Training set id.")),
+    responses(
+        (status = 200, description = "Training set.", body = TrainingSet),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 404, description = "No such set of the user.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn get_set<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    Path(id): Path<TrainingSetId>,
+) -> Result<Json<TrainingSet>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ManageSets)?;
+    ctx.puzzle_service
+        .get_set(user.id, id)
+        .await?
+        .map(Json)
+        .ok_or_else(ApiError::not_found)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/sets",
+    tag = "sets",
+    security(("bearer" = [])),
+    request_body = CreateTrainingSetOptions,
+    responses(
+        (status = 201, description = "Training set created.", body = TrainingSet),
+        (status = 400, description = "Invalid options.", body = ApiError),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 415, description = "Body is not JSON.", body = ApiError),
+        (status = 422, description = "Malformed body or not enough puzzles meet the criteria.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn create_set<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    ApiJson(options): ApiJson<CreateTrainingSetOptions>,
+) -> Result<(StatusCode, Json<TrainingSet>), ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ManageSets)?;
+    let result = ctx.puzzle_service.create_set(user.id, options).await;
+    let outcome = match &result {
+        Ok(_) => "created",
+        Err(error) => error.into(),
+    };
+    metrics::TRAINING_SETS_CREATED
+        .with_label_values(&[outcome])
+        .inc();
+    Ok((StatusCode::CREATED, Json(result?)))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PreviewSetQuery {
+    size: usize,
+    /// Lower bound of the rating range, `rating.start` of the set options.
+    min_rating: u16,
+    /// Upper bound of the rating range, `rating.end` of the set options.
+    max_rating: u16,
+    #[param(example = "fork,pin")]
+    themes: Option<String>,
+    #[serde(default)]
+    exclude_seen: bool,
+    /// Leaves out puzzles attempted within this many days.
+    exclude_attempted_days: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sets/preview",
+    tag = "sets",
+    security(("bearer" = [])),
+    params(PreviewSetQuery),
+    responses(
+        (status = 200, description = "Puzzles available for the criteria.", body = TrainingSetPreview),
+        (status = 400, description = "Invalid options.", body = ApiError),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 422, description = "Not enough puzzles meet the criteria.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn preview_set<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    ApiQuery(query): ApiQuery<PreviewSetQuery>,
+) -> Result<Json<TrainingSetPreview>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ReadPuzzles)?;
+    if query.min_rating > query.max_rating {
+        return Err(ApiError::bad_request(
+            "invalid_rating_range",
+            format!(
+                "Minimum rating {} exceeds maximum rating {}.",
+                query.min_rating, query.max_rating
+            ),
+        )
+        .with_field("min_rating"));
+    }
+    let themes = match query.themes {
+        Some(themes) => ThemeChoice::Themes(
+            themes
+                .split(',')
+                .map(Theme::from_str)
+                .collect::<Result<_, _>>()
+                .map_err(|_| {
+                    ApiError::bad_request("unknown_theme", format!("Unknown theme in {}.", themes))
+                        .with_field("themes")
+                })?,
+        ),
+        None => ThemeChoice::HealthyMix,
+    };
+    let options = PreviewTrainingSetOptions {
+        size: query.size,
+        rating: query.min_rating..=query.max_rating,
+        themes,
+        exclude_seen: query.exclude_seen,
+        exclude_attempted_days: query.exclude_attempted_days,
+    };
+
+    Ok(Json(
+        ctx.puzzle_service.preview_set(user.id, options).await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/sets/{id}/attempts",
+    tag = "sets",
+    security(("bearer" = [])),
+    params(("id" = Uuid, Path, description = "Training set id.")),
+    request_body = RecordAttemptOptions,
+    responses(
+        (status = 201, description = "Attempt recorded, set advanced and rating updated.", body = AttemptResult),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 404, description = "No such set of the user.", body = ApiError),
+        (status = 409, description = "Puzzle is not the next one in the set, or the set advanced concurrently.", body = ApiError),
+        (status = 415, description = "Body is not JSON.", body = ApiError),
+        (status = 422, description = "Malformed body.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn record_attempt<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    Path(id): Path<TrainingSetId>,
+    ApiJson(options): ApiJson<RecordAttemptOptions>,
+) -> Result<(StatusCode, Json<AttemptResult>), ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::RecordAttempts)?;
+    let result = ctx
+        .puzzle_service
+        .record_attempt(user.id, id, options)
+        .await;
+    let outcome = match &result {
+        Ok(result) if result.attempt.solved => "solved",
+        Ok(_) => "failed",
+        Err(error) => error.into(),
+    };
+    metrics::PUZZLE_ATTEMPTS.with_label_values(&[outcome]).inc();
+    Ok((StatusCode::CREATED, Json(result?)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/sets/{id}/plan",
+    tag = "sets",
+    security(("bearer" = [])),
+    params(("id" = Uuid, Path, description = "Training set id.")),
+    request_body = CyclePlan,
+    responses(
+        (status = 200, description = "Plan saved. Returns the set's schedule under it.", body = PlanStatus),
+        (status = 400, description = "Invalid plan.", body = ApiError),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 404, description = "No such set of the user.", body = ApiError),
+        (status = 415, description = "Body is not JSON.", body = ApiError),
+        (status = 422, description = "Malformed body.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn set_plan<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    Path(id): Path<TrainingSetId>,
+    ApiJson(plan): ApiJson<CyclePlan>,
+) -> Result<Json<PlanStatus>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ManageSets)?;
+    Ok(Json(ctx.puzzle_service.set_plan(user.id, id, plan).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sets/{id}/plan",
+    tag = "sets",
+    security(("bearer" = [])),
+    params(("id" = Uuid, Path, description = "Training set id.")),
+    responses(
+        (status = 200, description = "Cycle deadlines and whether the user is ahead of or behind the plan.", body = PlanStatus),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 404, description = "No such set of the user, or the set has no plan.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn plan_status<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    Path(id): Path<TrainingSetId>,
+) -> Result<Json<PlanStatus>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ManageSets)?;
+    Ok(Json(ctx.puzzle_service.plan_status(user.id, id).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sets/{id}/stats",
+    tag = "stats",
+    security(("bearer" = [])),
+    params(("id" = Uuid, Path, description = "Training set id.")),
+    responses(
+        (status = 200, description = "Accuracy and solve time of attempts at the set, by cycle, theme and rating band, with the worst puzzles.", body = Statistics),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 404, description = "No such set of the user.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn set_stats<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    Path(id): Path<TrainingSetId>,
+) -> Result<Json<Statistics>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ManageSets)?;
+    ctx.puzzle_service
+        .set_stats(user.id, id)
+        .await?
+        .map(Json)
+        .ok_or_else(ApiError::not_found)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/stats",
+    tag = "stats",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Accuracy and solve time of the latest 1000 attempts of the user, by cycle, theme and rating band, with the worst puzzles.", body = Statistics),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn user_stats<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<Statistics>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ReadPuzzles)?;
+    Ok(Json(ctx.puzzle_service.user_stats(user.id).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/weaknesses",
+    tag = "stats",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Themes where the user scores below what puzzle ratings predict in the latest 1000 attempts, worst first, with options of a set targeting them when enough unseen puzzles match. Post the options to /api/v1/sets to accept the suggestion.", body = SetSuggestion),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn suggest_set<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<SetSuggestion>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ReadPuzzles)?;
+    Ok(Json(ctx.puzzle_service.suggest_set(user.id).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/rating",
+    tag = "rating",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Current Glicko-2 rating of the user and the set rating range suggested for it.", body = RatingSummary),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn get_rating<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<RatingSummary>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ReadPuzzles)?;
+    Ok(Json(ctx.puzzle_service.get_rating(user.id).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/rating/history",
+    tag = "rating",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Rating of the user after each attempt, oldest first.", body = [RatingHistoryEntry]),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn rating_history<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<Vec<RatingHistoryEntry>>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ReadPuzzles)?;
+    Ok(Json(ctx.puzzle_service.rating_history(user.id).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/reviews/due",
+    tag = "reviews",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Reviews of failed or slowly solved puzzles that are due, earliest first.", body = [Review]),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn due_reviews<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<Vec<Review>>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ReadPuzzles)?;
+    Ok(Json(ctx.puzzle_service.due_reviews(user.id).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/reviews/{id}",
+    tag = "reviews",
+    security(("bearer" = [])),
+    params(("id" = Uuid, Path, description = "Review id.")),
+    request_body = RecordReviewOptions,
+    responses(
+        (status = 200, description = "Review recorded and rescheduled.", body = Review),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 404, description = "No such review of the user.", body = ApiError),
+        (status = 409, description = "Review is not due yet.", body = ApiError),
+        (status = 415, description = "Body is not JSON.", body = ApiError),
+        (status = 422, description = "Malformed body.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn record_review<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    Path(id): Path<ReviewId>,
+    ApiJson(options): ApiJson<RecordReviewOptions>,
+) -> Result<Json<Review>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::RecordAttempts)?;
+    let solved = options.solved;
+    let result = ctx.puzzle_service.record_review(user.id, id, options).await;
+    let outcome = match &result {
+        Ok(_) if solved => "solved",
+        Ok(_) => "failed",
+        Err(error) => error.into(),
+    };
+    metrics::PUZZLE_REVIEWS.with_label_values(&[outcome]).inc();
+    Ok(Json(result?))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DailySessionQuery {
+    #[param(minimum = 1, maximum = 200, default = 20)]
+    #[serde(default = "default_session_size")]
+    size: usize,
+}
+
+fn default_session_size() -> usize {
+    20
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/me/session",
+    tag = "reviews",
+    security(("bearer" = [])),
+    params(DailySessionQuery),
+    responses(
+        (status = 200, description = "Due reviews followed by the next puzzles of each set, taken in turns.", body = DailySession),
+        (status = 400, description = "Invalid size.", body = ApiError),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Token lacks the required scope.", body = ApiError),
+        (status = 500, description = "Repository failed.", body = ApiError),
+    )
+)]
+pub async fn daily_session<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    ApiQuery(query): ApiQuery<DailySessionQuery>,
+) -> Result<Json<DailySession>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require(Scope::ReadPuzzles)?;
+    if !(1..=MAX_SESSION_SIZE).contains(&query.size) {
+        return Err(ApiError::bad_request(
+            "invalid_session_size",
+            format!("Session size must be between 1 and {}.", MAX_SESSION_SIZE),
+        )
+        .with_field("size")
+        .with_details(json!({ "min": 1, "max": MAX_SESSION_SIZE })));
+    }
+    Ok(Json(
+        ctx.puzzle_service
+            .daily_session(user.id, query.size)
+            .await?,
+    ))
+}
+
+impl From<CreateTrainingSetError> for ApiError {
+    fn from(error: CreateTrainingSetError) -> Self {
+        let code = (&error).into();
+        let message = error.to_string();
+        match error {
+            CreateTrainingSetError::EmptyName => {
+                ApiError::bad_request(code, message).with_field("name")
+            }
+            CreateTrainingSetError::NameLengthLimitExceeded { max } => {
+                ApiError::bad_request(code, message)
+                    .with_field("name")
+                    .with_details(json!({ "max": max }))
+            }
+            CreateTrainingSetError::SizeTooSmall { min } => ApiError::bad_request(code, message)
+                .with_field("size")
+                .with_details(json!({ "min": min })),
+            CreateTrainingSetError::SizeLimitExceeded { max } => {
+                ApiError::bad_request(code, message)
+                    .with_field("size")
+                    .with_details(json!({ "max": max }))
+            }
+            CreateTrainingSetError::ConflictingDifficulty => {
+                ApiError::bad_request(code, message).with_field("adaptive")
+            }
+            CreateTrainingSetError::TargetSuccessOutOfRange { min, max } => {
+                ApiError::bad_request(code, message)
+                    .with_field("adaptive")
+                    .with_details(json!({ "min": min, "max": max }))
+            }
+            CreateTrainingSetError::CriteriaUnmet {
+                requested,
+                available,
+                most_restrictive,
+            } => {
+                ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, code, message).with_details(json!({
+                    "requested": requested,
+                    "available": available,
+                    "most_restrictive": most_restrictive,
+                }))
+            }
+            CreateTrainingSetError::RepositoryError { source } => ApiError::internal(source),
+        }
+    }
+}
+
+impl From<RecordAttemptError> for ApiError {
+    fn from(error: RecordAttemptError) -> Self {
+        let code = (&error).into();
+        let message = error.to_string();
+        match error {
+            RecordAttemptError::SetNotFound => ApiError::new(StatusCode::NOT_FOUND, code, message),
+            RecordAttemptError::NotCurrentPuzzle { expected } => {
+                ApiError::new(StatusCode::CONFLICT, code, message)
+                    .with_field("puzzle_id")
+                    .with_details(json!({ "expected": expected }))
+            }
+            RecordAttemptError::ProgressConflict => {
+                ApiError::new(StatusCode::CONFLICT, code, message)
+            }
+            RecordAttemptError::RepositoryError { source } => ApiError::internal(source),
+        }
+    }
+}
+
+impl From<CyclePlanError> for ApiError {
+    fn from(error: CyclePlanError) -> Self {
+        let code = (&error).into();
+        let message = error.to_string();
+        match error {
+            CyclePlanError::SetNotFound | CyclePlanError::PlanNotFound => {
+                ApiError::new(StatusCode::NOT_FOUND, code, message)
+            }
+            CyclePlanError::CycleCountOutOfRange { max } => ApiError::bad_request(code, message)
+                .with_field("cycle_days")
+                .with_details(json!({ "min": 1, "max": max })),
+            CyclePlanError::CycleDaysOutOfRange { max } => ApiError::bad_request(code, message)
+                .with_field("cycle_days")
+                .with_details(json!({ "min": 1, "max": max })),
+            CyclePlanError::RepositoryError { source } => ApiError::internal(source),
+        }
+    }
+}
+
+impl From<RecordReviewError> for ApiError {
+    fn from(error: RecordReviewError) -> Self {
+        let code = (&error).into();
+        let message = error.to_string();
+        match error {
+            RecordReviewError::ReviewNotFound => {
+                ApiError::new(StatusCode::NOT_FOUND, code, message)
+            }
+            RecordReviewError::NotDue { due_at } => {
+                ApiError::new(StatusCode::CONFLICT, code, message)
+                    .with_details(json!({ "due_at": due_at }))
+            }
+            RecordReviewError::RepositoryError { source } => ApiError::internal(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    use crate::infrastructure::test_support::TestServices;
+    use crate::puzzle::types::{LichessPuzzleImport, Theme};
+
+    struct TestApp {
+        router: Router,
+        tokens: Vec<String>,
+    }
+
+    fn sample_lichess_puzzle(i: usize) -> LichessPuzzleImport {
+        LichessPuzzleImport {
+            puzzle_id: format!("sample-lichess-id-{}", i),
+            fen: "sample-fen".to_string(),
+            moves: "sample-moves".to_string(),
+            rating: 1500,
+            rating_deviation: 50,
+            popularity: 50,
+            play_count: 1000,
+            themes: vec![Theme::Fork],
+            game_url: "sample-lichess-game-url".to_string(),
+        }
+    }
+
+    async fn make_test_app(puzzles: usize) -> TestApp {
+        make_test_app_with((0..puzzles).map(sample_lichess_puzzle).collect()).await
+    }
+
+    async fn make_test_app_with(puzzles: Vec<LichessPuzzleImport>) -> TestApp {
+        let services = TestServices::new(None).await;
+        for lichess_puzzle in puzzles {
+            services
+                .puzzle_service
+                .import_puzzle(lichess_puzzle)
+                .await
+                .unwrap();
+        }
+        let mut tokens = Vec::new();
+        for username in ["magnus", "hikaru"] {
+            tokens.push(services.login(username).await);
+        }
+        TestApp {
+            router: services.router(),
+            tokens,
+        }
+    }
+
+    impl TestApp {
+        async fn send(
+            &self,
+            token: &str,
+            mut request: Request<Body>,
+        ) -> (StatusCode, serde_json::Value) {
+            request
+                .headers_mut()
+                .insert(AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+            let response = self.router.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            (status, serde_json::from_slice(&body).unwrap())
+        }
+    }
+
+    async fn send(request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let app = make_test_app(0).await;
+        app.send(&app.tokens[0], request).await
+    }
+
+    fn create_set_request(body: serde_json::Value) -> Request<Body> {
+        Request::post("/api/v1/sets")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn get(uri: &str) -> Request<Body> {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_scope_sets_to_user() {
+        // given set created by one user:
+        let app = make_test_app(5).await;
+        let (status, set) = app
+            .send(
+                &app.tokens[0],
+                create_set_request(json!({
+                    "name": "sample-name",
+                    "size": 5,
+                    "rating": { "start": 1000, "end": 2000 },
+                    "themes": "HealthyMix",
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let set_uri = format!("/api/v1/sets/{}", set["id"].as_str().unwrap());
+
+        // when sets are requested by both users:
+        let (_, own_sets) = app.send(&app.tokens[0], get("/api/v1/sets")).await;
+        let (_, other_sets) = app.send(&app.tokens[1], get("/api/v1/sets")).await;
+        let (own_status, own_set) = app.send(&app.tokens[0], get(&set_uri)).await;
+        let (other_status, _) = app.send(&app.tokens[1], get(&set_uri)).await;
+
+        // then only the owner sees it:
+        assert_eq!(own_sets, json!([set]));
+        assert_eq!(other_sets, json!([]));
+        assert_eq!(own_status, StatusCode::OK);
+        assert_eq!(own_set, set);
+        assert_eq!(other_status, StatusCode::NOT_FOUND);
+    }
+
+    fn post(uri: &str, body: serde_json::Value) -> Request<Body> {
+        Request::post(uri)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn put(uri: &str, body: serde_json::Value) -> Request<Body> {
+        Request::put(uri)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_record_attempts_and_rate_user() {
+        // given set created without rating range by new user:
+        let app = make_test_app(5).await;
+        let token = &app.tokens[0];
+        let (status, set) = app
+            .send(
+                token,
+                create_set_request(json!({
+                    "name": "sample-name",
+                    "size": 5,
+                    "themes": "HealthyMix",
+                })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(set["rating"], json!({ "start": 1200, "end": 1800 }));
+        let attempts_uri = format!("/api/v1/sets/{}/attempts", set["id"].as_str().unwrap());
+
+        // when the next puzzle is solved, and then the same one again:
+        let puzzle_id = set["puzzle_ids"][0].clone();
+        let attempt = json!({ "puzzle_id": puzzle_id, "solved": true, "solve_time_ms": 15000 });
+        let (status, result) = app.send(token, post(&attempts_uri, attempt.clone())).await;
+        let (repeated_status, repeated) = app.send(token, post(&attempts_uri, attempt)).await;
+
+        // then set advances and rating rises:
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(result["attempt"]["puzzle_id"], puzzle_id);
+        assert_eq!(result["training_set"]["current_progress"], 1);
+        assert!(result["rating"]["rating"].as_f64().unwrap() > 1500.0);
+        let (_, rating) = app.send(token, get("/api/v1/me/rating")).await;
+        assert_eq!(rating["rating"], result["rating"]["rating"]);
+        let (_, history) = app.send(token, get("/api/v1/me/rating/history")).await;
+        assert_eq!(history[0]["attempt_id"], result["attempt"]["id"]);
+
+        // and the repeated one conflicts:
+        assert_eq!(repeated_status, StatusCode::CONFLICT);
+        assert_eq!(repeated["code"], "not_current_puzzle");
+        assert_eq!(repeated["details"]["expected"], set["puzzle_ids"][1]);
+    }
+
+    #[tokio::test]
+    async fn should_queue_failed_puzzles_for_review() {
+        // given set:
+        let app = make_test_app(5).await;
+        let token = &app.tokens[0];
+        let (_, set) = app
+            .send(
+                token,
+                create_set_request(json!({
+                    "name": "sample-name",
+                    "size": 5,
+                    "themes": "HealthyMix",
+                })),
+            )
+            .await;
+        let attempts_uri = format!("/api/v1/sets/{}/attempts", set["id"].as_str().unwrap());
+
+        // when the next puzzle is failed:
+        let puzzle_id = set["puzzle_ids"][0].clone();
+        let attempt = json!({ "puzzle_id": puzzle_id, "solved": false, "solve_time_ms": 15000 });
+        let (_, result) = app.send(token, post(&attempts_uri, attempt)).await;
+
+        // then it's queued for review tomorrow:
+        let review = &result["review"];
+        assert_eq!(review["puzzle_id"], puzzle_id);
+        assert_eq!(review["interval_days"], 1);
+        let (_, due) = app.send(token, get("/api/v1/reviews/due")).await;
+        assert_eq!(due, json!([]));
+        let review_uri = format!("/api/v1/reviews/{}", review["id"].as_str().unwrap());
+        let recall = json!({ "solved": true, "solve_time_ms": 5000 });
+        let (status, body) = app.send(token, post(&review_uri, recall)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "not_due");
+        assert_eq!(body["details"]["due_at"], review["due_at"]);
+
+        // and today's session continues the set:
+        let (_, session) = app.send(token, get("/api/v1/me/session?size=2")).await;
+        assert_eq!(session["reviews_due"], 0);
+        assert_eq!(
+            session["items"],
+            json!([
+                { "kind": "set", "training_set_id": set["id"], "puzzle_id": set["puzzle_ids"][1], "position": 1 },
+                { "kind": "set", "training_set_id": set["id"], "puzzle_id": set["puzzle_ids"][2], "position": 2 },
+            ])
+        );
+        let (status, body) = app.send(token, get("/api/v1/me/session?size=0")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_session_size");
+    }
+
+    #[tokio::test]
+    async fn should_plan_cycles_of_set() {
+        // given set:
+        let app = make_test_app(5).await;
+        let token = &app.tokens[0];
+        let (_, set) = app
+            .send(
+                token,
+                create_set_request(json!({
+                    "name": "sample-name",
+                    "size": 5,
+                    "themes": "HealthyMix",
+                })),
+            )
+            .await;
+        let plan_uri = format!("/api/v1/sets/{}/plan", set["id"].as_str().unwrap());
+
+        // when plan is read before and after it's set:
+        let (missing_status, missing) = app.send(token, get(&plan_uri)).await;
+        let plan = json!({ "cycle_days": [28, 14, 7] });
+        let (status, saved) = app.send(token, put(&plan_uri, plan.clone())).await;
+        let (_, found) = app.send(token, get(&plan_uri)).await;
+
+        // then the plan applies from then on:
+        assert_eq!(missing_status, StatusCode::NOT_FOUND);
+        assert_eq!(missing["code"], "plan_not_found");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(saved["state"], "not_started");
+        assert_eq!(saved["cycles"][0]["target_days"], 28);
+        assert_eq!(found, saved);
+        let (_, set) = app
+            .send(
+                token,
+                get(&format!("/api/v1/sets/{}", set["id"].as_str().unwrap())),
+            )
+            .await;
+        assert_eq!(set["plan"], plan);
+
+        // and invalid plans are reported:
+        let (status, body) = app
+            .send(token, put(&plan_uri, json!({ "cycle_days": [0] })))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "cycle_days_out_of_range");
+        assert_eq!(body["field"], "cycle_days");
+    }
+
+    #[tokio::test]
+    async fn should_report_statistics_of_attempts() {
+        // given set with the first puzzle failed:
+        let app = make_test_app(5).await;
+        let token = &app.tokens[0];
+        let (_, set) = app
+            .send(
+                token,
+                create_set_request(json!({
+                    "name": "sample-name",
+                    "size": 5,
+                    "themes": "HealthyMix",
+                })),
+            )
+            .await;
+        let set_uri = format!("/api/v1/sets/{}", set["id"].as_str().unwrap());
+        let puzzle_id = set["puzzle_ids"][0].clone();
+        let attempt = json!({ "puzzle_id": puzzle_id, "solved": false, "solve_time_ms": 15000 });
+        app.send(token, post(&format!("{}/attempts", set_uri), attempt))
+            .await;
+
+        // when statistics of the set and of the user are read, also by other user:
+        let (status, set_stats) = app.send(token, get(&format!("{}/stats", set_uri))).await;
+        let (_, user_stats) = app.send(token, get("/api/v1/me/stats")).await;
+        let (other_status, _) = app
+            .send(&app.tokens[1], get(&format!("{}/stats", set_uri)))
+            .await;
+
+        // then the failed attempt is reported:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            set_stats["overall"],
+            json!({ "attempts": 1, "solved": 0, "accuracy": 0.0, "average_solve_time_ms": 15000 })
+        );
+        assert_eq!(set_stats["themes"][0]["theme"], "fork");
+        assert_eq!(set_stats["rating_bands"][0]["rating_max"], 1599);
+        assert_eq!(set_stats["worst_puzzles"][0]["puzzle_id"], puzzle_id);
+        assert_eq!(user_stats, set_stats);
+        assert_eq!(other_status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn should_suggest_set_targeting_weaknesses() {
+        // given five failed puzzles, and easier ones of the same theme:
+        let puzzles = (0..105)
+            .map(|i| LichessPuzzleImport {
+                rating: if i < 5 { 1500 } else { 500 + i as u16 * 5 },
+                ..sample_lichess_puzzle(i)
+            })
+            .collect();
+        let app = make_test_app_with(puzzles).await;
+        let token = &app.tokens[0];
+        let (_, set) = app
+            .send(
+                token,
+                create_set_request(json!({
+                    "name": "sample-name",
+                    "size": 5,
+                    "rating": { "start": 1500, "end": 1500 },
+                    "themes": "HealthyMix",
+                })),
+            )
+            .await;
+        let attempts_uri = format!("/api/v1/sets/{}/attempts", set["id"].as_str().unwrap());
+        for puzzle_id in set["puzzle_ids"].as_array().unwrap() {
+            let attempt =
+                json!({ "puzzle_id": puzzle_id, "solved": false, "solve_time_ms": 15000 });
+            app.send(token, post(&attempts_uri, attempt)).await;
+        }
+
+        // when set is suggested and the suggestion is accepted:
+        let (status, suggestion) = app.send(token, get("/api/v1/me/weaknesses")).await;
+        let (accepted_status, accepted) = app
+            .send(token, create_set_request(suggestion["options"].clone()))
+            .await;
+
+        // then a set of easier puzzles of the weak theme is created:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(suggestion["weaknesses"][0]["theme"], "fork");
+        assert_eq!(suggestion["weaknesses"][0]["attempts"], 5);
+        assert_eq!(suggestion["weaknesses"][0]["score"], 0.0);
+        assert_eq!(
+            suggestion["options"]["themes"],
+            json!({ "Themes": ["fork"] })
+        );
+        assert_eq!(accepted_status, StatusCode::CREATED);
+        assert_eq!(accepted["name"], "Weaknesses: fork");
+        assert_eq!(accepted["rating"], suggestion["options"]["rating"]);
+        assert!(accepted["rating"]["end"].as_u64().unwrap() < 1500);
+    }
+
+    #[tokio::test]
+    async fn should_list_puzzles_for_authenticated_users() {
+        // given imported puzzles:
+        let app = make_test_app(3).await;
+
+        // when they're listed with and without a valid token:
+        let (status, puzzles) = app.send(&app.tokens[0], get("/api/v1/puzzles")).await;
+        let (unknown_status, _) = app.send("unknown-token", get("/api/v1/puzzles")).await;
+
+        // then only the authenticated user gets them:
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(puzzles.as_array().unwrap().len(), 3);
+        assert_eq!(unknown_status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn should_require_authentication_for_sets() {
+        // when sets are requested with unknown token:
+        let app = make_test_app(0).await;
+        let (status, body) = app.send("unknown-token", get("/api/v1/sets")).await;
+
+        // then it's unauthorized:
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "unauthorized");
+    }
+
+    #[tokio::test]
+    async fn should_report_invalid_field() {
+        // when set with too small size is requested:
+        let (status, body) = send(create_set_request(json!({
+            "name": "sample-name",
+            "size": 1,
+            "rating": { "start": 1000, "end": 2000 },
+            "themes": "HealthyMix",
+        })))
+        .await;
+
+        // then the field and limit are reported:
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body,
+            json!({
+                "code": "size_too_small",
+                "message": "Set size must be at least 5.",
+                "field": "size",
+                "details": { "min": 5 },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn should_report_conflicting_difficulty() {
+        // when set with both rating range and adaptive difficulty is requested:
+        let (status, body) = send(create_set_request(json!({
+            "name": "sample-name",
+            "size": 5,
+            "rating": { "start": 1000, "end": 2000 },
+            "adaptive": { "target_success_percent": 75 },
+            "themes": "HealthyMix",
+        })))
+        .await;
+
+        // then the field is reported:
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "conflicting_difficulty");
+        assert_eq!(body["field"], "adaptive");
+    }
+
+    #[tokio::test]
+    async fn should_report_unmet_criteria() {
+        // when set is requested without puzzles imported:
+        let (status, body) = send(create_set_request(json!({
+            "name": "sample-name",
+            "size": 5,
+            "rating": { "start": 1000, "end": 2000 },
+            "themes": "HealthyMix",
+        })))
+        .await;
+
+        // then availability is reported:
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "criteria_unmet");
+        assert_eq!(body["details"]["requested"], 5);
+        assert_eq!(body["details"]["available"], 0);
+    }
+
+    #[tokio::test]
+    async fn should_report_malformed_body() {
+        // when body is missing fields:
+        let (status, body) = send(create_set_request(json!({ "name": "sample-name" }))).await;
+
+        // then it's reported as error body:
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "invalid_body");
+    }
+
+    #[tokio::test]
+    async fn should_report_unknown_theme() {
+        // when preview with unknown theme is requested:
+        let request = Request::get(
+            "/api/v1/sets/preview?size=5&min_rating=0&max_rating=3000&themes=fork,nope",
+        )
+        .body(Body::empty())
+        .unwrap();
+        let (status, body) = send(request).await;
+
+        // then theme field is reported:
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "unknown_theme");
+        assert_eq!(body["field"], "themes");
+    }
+
+    #[tokio::test]
+    async fn should_report_inverted_rating_range() {
+        // when preview with minimum rating above maximum is requested:
+        let request = Request::get("/api/v1/sets/preview?size=5&min_rating=1800&max_rating=1200")
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = send(request).await;
+
+        // then minimum rating field is reported:
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_rating_range");
+        assert_eq!(body["field"], "min_rating");
+    }
+
+    #[tokio::test]
+    async fn should_report_unknown_api_route() {
+        // when unknown API route is requested:
+        let request = Request::get("/api/v1/nope").body(Body::empty()).unwrap();
+        let (status, body) = send(request).await;
+
+        // then it's not found:
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+    }
 }
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::puzzle::types::{PuzzleId, Review, ReviewId, Schedule};
+use crate::user::types::UserId;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait ReviewRepository: Send + Sync {
+    async fn save(&self, review: SaveReview) -> anyhow::Result<Review>;
+    async fn find(&self, user_id: UserId, id: ReviewId) -> anyhow::Result<Option<Review>>;
+    async fn find_due(&self, user_id: UserId, now: DateTime<Utc>) -> anyhow::Result<Vec<Review>>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveReview {
+    pub user_id: UserId,
+    pub puzzle_id: PuzzleId,
+    pub schedule: Schedule,
+    pub due_at: DateTime<Utc>,
+    pub reviewed_at: DateTime<Utc>,
+}
+
+#[derive(Default, Clone)]
+pub struct InMemoryReviewRepository {
+    reviews: Arc<RwLock<Vec<Review>>>,
+}
+
+impl InMemoryReviewRepository {
+    pub fn new() -> InMemoryReviewRepository {
+        InMemoryReviewRepository::default()
+    }
+
+    pub(crate) fn upsert(&self, review: SaveReview) -> Review {
+        let mut reviews = self.reviews.write();
+        let existing = reviews.iter_mut().find(|existing| {
+            existing.user_id == review.user_id && existing.puzzle_id == review.puzzle_id
+        });
+        let saved = Review {
+            id: existing
+                .as_ref()
+                .map_or_else(Uuid::new_v4, |existing| existing.id),
+            user_id: review.user_id,
+            puzzle_id: review.puzzle_id,
+            schedule: review.schedule,
+            due_at: review.due_at,
+            reviewed_at: review.reviewed_at,
+        };
+        match existing {
+            Some(existing) => *existing = saved.clone(),
+            None => reviews.push(saved.clone()),
+        }
+        saved
+    }
+}
+
+#[async_trait]
+impl ReviewRepository for InMemoryReviewRepository {
+    #[instrument(level = "debug", skip_all, fields(user_id = %review.user_id, puzzle_id = review.puzzle_id))]
+    async fn save(&self, review: SaveReview) -> anyhow::Result<Review> {
+        Ok(self.upsert(review))
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find(&self, user_id: UserId, id: ReviewId) -> anyhow::Result<Option<Review>> {
+        Ok(self
+            .reviews
+            .read()
+            .iter()
+            .find(|review| review.user_id == user_id && review.id == id)
+            .cloned())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_due(&self, user_id: UserId, now: DateTime<Utc>) -> anyhow::Result<Vec<Review>> {
+        let mut due: Vec<_> = self
+            .reviews
+            .read()
+            .iter()
+            .filter(|review| review.user_id == user_id && review.due_at <= now)
+            .cloned()
+            .collect();
+        due.sort_by_key(|review| (review.due_at, review.puzzle_id));
+        Ok(due)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::puzzle_repository::InMemoryPuzzleRepository;
+    use crate::puzzle::repository_contract::review_repository_contract_tests;
+    use crate::user::user_repository::InMemoryUserRepository;
+
+    use super::InMemoryReviewRepository;
+
+    review_repository_contract_tests!((
+        InMemoryPuzzleRepository::new(),
+        InMemoryUserRepository::new(),
+        InMemoryReviewRepository::new()
+    ));
+}
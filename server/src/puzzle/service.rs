@@ -1,122 +1,964 @@
-use anyhow::ensure;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::Instant;
 
-use crate::puzzle::consts::{MAX_SET_NAME_LENGTH, MAX_SET_SIZE, MIN_SET_SIZE};
-use crate::puzzle::errors::CreateTrainingSetError;
+use anyhow::{ensure, Context};
+use async_trait::async_trait;
+use chrono::{DateTime, SubsecRound, Utc};
+use tracing::instrument;
+
+use crate::puzzle::attempt_repository::{AttemptRepository, RecordAttempt, RecordedAttempt};
+use crate::puzzle::config::SetLimits;
+use crate::puzzle::difficulty;
+use crate::puzzle::errors::{
+    CreateTrainingSetError, CyclePlanError, RecordAttemptError, RecordReviewError,
+};
+use crate::puzzle::glicko;
 use crate::puzzle::puzzle_repository::PuzzleRepository;
+use crate::puzzle::review_repository::{ReviewRepository, SaveReview};
+use crate::puzzle::srs;
+use crate::puzzle::stats;
 use crate::puzzle::training_set_repository;
-use crate::puzzle::training_set_repository::TrainingSetRepository;
-use crate::puzzle::types::{CreateTrainingSetOptions, LichessPuzzleImport, Puzzle, TrainingSet};
+use crate::puzzle::training_set_repository::{Progress, TrainingSetRepository};
+use crate::puzzle::types::{
+    AdaptiveDifficulty, Attempt, AttemptResult, CreateTrainingSetOptions, CriteriaFilter,
+    CyclePlan, DailySession, LichessPuzzleImport, PlanStatus, PreviewTrainingSetOptions, Puzzle,
+    PuzzleId, Rating, RatingHistoryEntry, RatingSummary, RecordAttemptOptions, RecordReviewOptions,
+    Review, ReviewId, Schedule, SessionItem, SetSuggestion, Statistics, Theme, ThemeChoice,
+    TrainingSet, TrainingSetId, TrainingSetPreview,
+};
+use crate::puzzle::weakness;
+use crate::puzzle::woodpecker;
+use crate::user::types::UserId;
 
-pub trait PuzzleService {
-    fn import_puzzle(&self, lichess_puzzle: LichessPuzzleImport) -> anyhow::Result<Puzzle>;
-    fn list_puzzles(&self) -> Vec<Puzzle>;
-    fn create_set(
+#[async_trait]
+pub trait PuzzleService: Send + Sync {
+    async fn import_puzzle(&self, lichess_puzzle: LichessPuzzleImport) -> anyhow::Result<Puzzle>;
+    async fn list_puzzles(&self) -> anyhow::Result<Vec<Puzzle>>;
+    async fn estimate_puzzle_count(&self) -> anyhow::Result<usize>;
+    async fn has_puzzles(&self) -> anyhow::Result<bool>;
+    async fn create_set(
         &self,
+        user_id: UserId,
         options: CreateTrainingSetOptions,
     ) -> Result<TrainingSet, CreateTrainingSetError>;
+    async fn preview_set(
+        &self,
+        user_id: UserId,
+        options: PreviewTrainingSetOptions,
+    ) -> Result<TrainingSetPreview, CreateTrainingSetError>;
+    async fn list_sets(&self, user_id: UserId) -> anyhow::Result<Vec<TrainingSet>>;
+    async fn get_set(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+    ) -> anyhow::Result<Option<TrainingSet>>;
+    async fn record_attempt(
+        &self,
+        user_id: UserId,
+        set_id: TrainingSetId,
+        options: RecordAttemptOptions,
+    ) -> Result<AttemptResult, RecordAttemptError>;
+    async fn get_rating(&self, user_id: UserId) -> anyhow::Result<RatingSummary>;
+    async fn rating_history(&self, user_id: UserId) -> anyhow::Result<Vec<RatingHistoryEntry>>;
+    async fn due_reviews(&self, user_id: UserId) -> anyhow::Result<Vec<Review>>;
+    async fn record_review(
+        &self,
+        user_id: UserId,
+        id: ReviewId,
+        options: RecordReviewOptions,
+    ) -> Result<Review, RecordReviewError>;
+    async fn daily_session(&self, user_id: UserId, size: usize) -> anyhow::Result<DailySession>;
+    async fn set_plan(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+        plan: CyclePlan,
+    ) -> Result<PlanStatus, CyclePlanError>;
+    async fn plan_status(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+    ) -> Result<PlanStatus, CyclePlanError>;
+    async fn set_stats(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+    ) -> anyhow::Result<Option<Statistics>>;
+    async fn user_stats(&self, user_id: UserId) -> anyhow::Result<Statistics>;
+    async fn suggest_set(&self, user_id: UserId) -> anyhow::Result<SetSuggestion>;
+}
+
+#[async_trait]
+impl<S> PuzzleService for Arc<S>
+where
+    S: PuzzleService + ?Sized,
+{
+    async fn import_puzzle(&self, lichess_puzzle: LichessPuzzleImport) -> anyhow::Result<Puzzle> {
+        (**self).import_puzzle(lichess_puzzle).await
+    }
+
+    async fn list_puzzles(&self) -> anyhow::Result<Vec<Puzzle>> {
+        (**self).list_puzzles().await
+    }
+
+    async fn estimate_puzzle_count(&self) -> anyhow::Result<usize> {
+        (**self).estimate_puzzle_count().await
+    }
+
+    async fn has_puzzles(&self) -> anyhow::Result<bool> {
+        (**self).has_puzzles().await
+    }
+
+    async fn create_set(
+        &self,
+        user_id: UserId,
+        options: CreateTrainingSetOptions,
+    ) -> Result<TrainingSet, CreateTrainingSetError> {
+        (**self).create_set(user_id, options).await
+    }
+
+    async fn preview_set(
+        &self,
+        user_id: UserId,
+        options: PreviewTrainingSetOptions,
+    ) -> Result<TrainingSetPreview, CreateTrainingSetError> {
+        (**self).preview_set(user_id, options).await
+    }
+
+    async fn list_sets(&self, user_id: UserId) -> anyhow::Result<Vec<TrainingSet>> {
+        (**self).list_sets(user_id).await
+    }
+
+    async fn get_set(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+    ) -> anyhow::Result<Option<TrainingSet>> {
+        (**self).get_set(user_id, id).await
+    }
+
+    async fn record_attempt(
+        &self,
+        user_id: UserId,
+        set_id: TrainingSetId,
+        options: RecordAttemptOptions,
+    ) -> Result<AttemptResult, RecordAttemptError> {
+        (**self).record_attempt(user_id, set_id, options).await
+    }
+
+    async fn get_rating(&self, user_id: UserId) -> anyhow::Result<RatingSummary> {
+        (**self).get_rating(user_id).await
+    }
+
+    async fn rating_history(&self, user_id: UserId) -> anyhow::Result<Vec<RatingHistoryEntry>> {
+        (**self).rating_history(user_id).await
+    }
+
+    async fn due_reviews(&self, user_id: UserId) -> anyhow::Result<Vec<Review>> {
+        (**self).due_reviews(user_id).await
+    }
+
+    async fn record_review(
+        &self,
+        user_id: UserId,
+        id: ReviewId,
+        options: RecordReviewOptions,
+    ) -> Result<Review, RecordReviewError> {
+        (**self).record_review(user_id, id, options).await
+    }
+
+    async fn daily_session(&self, user_id: UserId, size: usize) -> anyhow::Result<DailySession> {
+        (**self).daily_session(user_id, size).await
+    }
+
+    async fn set_plan(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+        plan: CyclePlan,
+    ) -> Result<PlanStatus, CyclePlanError> {
+        (**self).set_plan(user_id, id, plan).await
+    }
+
+    async fn plan_status(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+    ) -> Result<PlanStatus, CyclePlanError> {
+        (**self).plan_status(user_id, id).await
+    }
+
+    async fn set_stats(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+    ) -> anyhow::Result<Option<Statistics>> {
+        (**self).set_stats(user_id, id).await
+    }
+
+    async fn user_stats(&self, user_id: UserId) -> anyhow::Result<Statistics> {
+        (**self).user_stats(user_id).await
+    }
+
+    async fn suggest_set(&self, user_id: UserId) -> anyhow::Result<SetSuggestion> {
+        (**self).suggest_set(user_id).await
+    }
 }
 
 #[cfg_attr(test, derive(derive_builder::Builder))]
 #[cfg_attr(test, builder(pattern = "owned"))]
-pub struct PuzzleServiceImpl<P, T>
+pub struct PuzzleServiceImpl<P, T, A, R>
 where
     P: PuzzleRepository,
     T: TrainingSetRepository,
+    A: AttemptRepository,
+    R: ReviewRepository,
 {
     puzzle_repository: P,
     training_set_repository: T,
+    attempt_repository: A,
+    review_repository: R,
+    #[cfg_attr(test, builder(default))]
+    limits: SetLimits,
 }
 
-impl<P, T> PuzzleServiceImpl<P, T>
+impl<P, T, A, R> PuzzleServiceImpl<P, T, A, R>
 where
     P: PuzzleRepository,
     T: TrainingSetRepository,
+    A: AttemptRepository,
+    R: ReviewRepository,
 {
-    pub fn new(puzzle_repository: P, training_set_repository: T) -> PuzzleServiceImpl<P, T> {
+    pub fn new(
+        puzzle_repository: P,
+        training_set_repository: T,
+        attempt_repository: A,
+        review_repository: R,
+    ) -> PuzzleServiceImpl<P, T, A, R> {
         PuzzleServiceImpl {
             puzzle_repository,
             training_set_repository,
+            attempt_repository,
+            review_repository,
+            limits: SetLimits::default(),
+        }
+    }
+
+    pub fn with_limits(self, limits: SetLimits) -> PuzzleServiceImpl<P, T, A, R> {
+        PuzzleServiceImpl { limits, ..self }
+    }
+
+    async fn schedule_review(
+        &self,
+        user_id: UserId,
+        puzzle_id: PuzzleId,
+        schedule: &Schedule,
+        quality: u8,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Review> {
+        let schedule = srs::next(schedule, quality);
+        self.review_repository
+            .save(SaveReview {
+                user_id,
+                puzzle_id,
+                schedule,
+                due_at: srs::due_at(now, &schedule),
+                reviewed_at: now,
+            })
+            .await
+    }
+
+    async fn statistics(&self, attempts: Vec<Attempt>) -> anyhow::Result<Statistics> {
+        let puzzle_ids: Vec<PuzzleId> = attempts
+            .iter()
+            .map(|attempt| attempt.puzzle_id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let puzzles = self.puzzle_repository.find_by_ids(&puzzle_ids).await?;
+        Ok(stats::aggregate(&attempts, &puzzles))
+    }
+
+    async fn find_rating(&self, user_id: UserId) -> anyhow::Result<Rating> {
+        Ok(self
+            .attempt_repository
+            .find_rating(user_id)
+            .await?
+            .unwrap_or_default())
+    }
+
+    async fn find_adaptive_range(
+        &self,
+        user_id: UserId,
+        adaptive: AdaptiveDifficulty,
+    ) -> anyhow::Result<RangeInclusive<u16>> {
+        let (rating, recent) = tokio::try_join!(
+            self.find_rating(user_id),
+            self.attempt_repository
+                .find_recent(user_id, difficulty::RECENT_ATTEMPTS),
+        )?;
+        let puzzle_ids: Vec<PuzzleId> = recent.iter().map(|attempt| attempt.puzzle_id).collect();
+        let puzzles: HashMap<PuzzleId, Puzzle> = self
+            .puzzle_repository
+            .find_by_ids(&puzzle_ids)
+            .await?
+            .into_iter()
+            .map(|puzzle| (puzzle.id, puzzle))
+            .collect();
+        let outcomes: Vec<glicko::Outcome> = recent
+            .iter()
+            .filter_map(|attempt| {
+                puzzles.get(&attempt.puzzle_id).map(|puzzle| {
+                    difficulty::outcome(puzzle, attempt.solved, attempt.solve_time_ms)
+                })
+            })
+            .collect();
+        Ok(difficulty::adaptive_range(
+            &rating,
+            &outcomes,
+            adaptive.target_success_percent,
+        ))
+    }
+
+    fn validate_size(&self, size: usize) -> Result<(), CreateTrainingSetError> {
+        if size < self.limits.min_set_size {
+            return Err(CreateTrainingSetError::SizeTooSmall {
+                min: self.limits.min_set_size,
+            });
+        }
+        if size > self.limits.max_set_size {
+            return Err(CreateTrainingSetError::SizeLimitExceeded {
+                max: self.limits.max_set_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Puzzles to leave out of a new set, or `None` when nothing is to be excluded.
+    async fn find_excluded(
+        &self,
+        user_id: UserId,
+        exclude_seen: bool,
+        exclude_attempted_days: Option<u32>,
+    ) -> anyhow::Result<Option<HashSet<PuzzleId>>> {
+        if !exclude_seen && exclude_attempted_days.is_none() {
+            return Ok(None);
         }
+        let seen = async {
+            match exclude_seen {
+                true => self.training_set_repository.find_puzzle_ids(user_id).await,
+                false => Ok(HashSet::new()),
+            }
+        };
+        let attempted = async {
+            match exclude_attempted_days {
+                Some(days) => {
+                    let since = Utc::now()
+                        .checked_sub_signed(chrono::Duration::days(days.into()))
+                        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+                    self.attempt_repository
+                        .find_attempted_puzzle_ids(user_id, since)
+                        .await
+                }
+                None => Ok(HashSet::new()),
+            }
+        };
+        let (mut seen, attempted) = tokio::try_join!(seen, attempted)?;
+        seen.extend(attempted);
+        Ok(Some(seen))
+    }
+
+    async fn count_matching(
+        &self,
+        size: usize,
+        rating: &RangeInclusive<u16>,
+        themes: &ThemeChoice,
+        excluded: Option<&HashSet<PuzzleId>>,
+    ) -> anyhow::Result<TrainingSetPreview> {
+        let nothing_excluded = HashSet::new();
+        let all_ratings = u16::MIN..=u16::MAX;
+        let repository = &self.puzzle_repository;
+
+        let (available, matching_rating, matching_themes, matching_unseen) = tokio::try_join!(
+            repository.count_matching(rating, themes, excluded.unwrap_or(&nothing_excluded)),
+            repository.count_matching(rating, &ThemeChoice::HealthyMix, &nothing_excluded),
+            repository.count_matching(&all_ratings, themes, &nothing_excluded),
+            async {
+                match excluded {
+                    Some(excluded) => repository
+                        .count_matching(&all_ratings, &ThemeChoice::HealthyMix, excluded)
+                        .await
+                        .map(Some),
+                    None => Ok(None),
+                }
+            },
+        )?;
+
+        let most_restrictive = [
+            (CriteriaFilter::Rating, matching_rating),
+            (CriteriaFilter::Themes, matching_themes),
+            (
+                CriteriaFilter::ExcludeSeen,
+                matching_unseen.unwrap_or(usize::MAX),
+            ),
+        ]
+        .into_iter()
+        .min_by_key(|(_, count)| *count)
+        .map(|(filter, _)| filter)
+        .unwrap();
+
+        Ok(TrainingSetPreview {
+            requested: size,
+            available,
+            matching_rating,
+            matching_themes,
+            matching_unseen,
+            most_restrictive,
+        })
     }
 }
 
-impl<P, T> PuzzleService for PuzzleServiceImpl<P, T>
+#[async_trait]
+impl<P, T, A, R> PuzzleService for PuzzleServiceImpl<P, T, A, R>
 where
     P: PuzzleRepository,
     T: TrainingSetRepository,
+    A: AttemptRepository,
+    R: ReviewRepository,
 {
-    fn import_puzzle(&self, lichess_puzzle: LichessPuzzleImport) -> anyhow::Result<Puzzle> {
+    async fn import_puzzle(&self, lichess_puzzle: LichessPuzzleImport) -> anyhow::Result<Puzzle> {
         ensure!(
             (-100..=100).contains(&lichess_puzzle.popularity),
             "puzzle {}: popularity {} is out of range [-100, 100].",
             lichess_puzzle.puzzle_id,
             lichess_puzzle.popularity
         );
-        self.puzzle_repository.create(lichess_puzzle.into())
+        self.puzzle_repository.create(lichess_puzzle.into()).await
+    }
+
+    async fn list_puzzles(&self) -> anyhow::Result<Vec<Puzzle>> {
+        self.puzzle_repository.find().await
     }
 
-    fn list_puzzles(&self) -> Vec<Puzzle> {
-        self.puzzle_repository.find()
+    async fn estimate_puzzle_count(&self) -> anyhow::Result<usize> {
+        self.puzzle_repository.estimate_count().await
     }
 
-    fn create_set(
+    async fn has_puzzles(&self) -> anyhow::Result<bool> {
+        self.puzzle_repository.has_puzzles().await
+    }
+
+    #[instrument(skip_all, fields(size = options.size, rating = ?options.rating, adaptive = ?options.adaptive, themes = ?options.themes))]
+    async fn create_set(
         &self,
+        user_id: UserId,
         options: CreateTrainingSetOptions,
     ) -> Result<TrainingSet, CreateTrainingSetError> {
         if options.name.is_empty() {
             return Err(CreateTrainingSetError::EmptyName);
         }
-        if options.name.len() > MAX_SET_NAME_LENGTH {
-            return Err(CreateTrainingSetError::NameLengthLimitExceeded);
-        }
-        if options.size < MIN_SET_SIZE {
-            return Err(CreateTrainingSetError::SizeTooSmall);
-        }
-        if options.size > MAX_SET_SIZE {
-            return Err(CreateTrainingSetError::SizeLimitExceeded);
+        if options.name.len() > self.limits.max_set_name_length {
+            return Err(CreateTrainingSetError::NameLengthLimitExceeded {
+                max: self.limits.max_set_name_length,
+            });
         }
+        self.validate_size(options.size)?;
+
+        let rating = match (options.rating, options.adaptive) {
+            (Some(_), Some(_)) => return Err(CreateTrainingSetError::ConflictingDifficulty),
+            (Some(rating), None) => rating,
+            (None, Some(adaptive)) => {
+                if !difficulty::TARGET_SUCCESS_PERCENT.contains(&adaptive.target_success_percent) {
+                    return Err(CreateTrainingSetError::TargetSuccessOutOfRange {
+                        min: *difficulty::TARGET_SUCCESS_PERCENT.start(),
+                        max: *difficulty::TARGET_SUCCESS_PERCENT.end(),
+                    });
+                }
+                self.find_adaptive_range(user_id, adaptive)
+                    .await
+                    .map_err(|source| CreateTrainingSetError::RepositoryError { source })?
+            }
+            (None, None) => glicko::suggested_range(
+                &self
+                    .find_rating(user_id)
+                    .await
+                    .map_err(|source| CreateTrainingSetError::RepositoryError { source })?,
+            ),
+        };
+        let excluded = self
+            .find_excluded(
+                user_id,
+                options.exclude_seen,
+                options.exclude_attempted_days,
+            )
+            .await
+            .map_err(|source| CreateTrainingSetError::RepositoryError { source })?;
+        let nothing_excluded = HashSet::new();
 
+        let started = Instant::now();
         let puzzles = self
             .puzzle_repository
-            .find_random(options.size, &options.rating, &options.themes)
+            .find_random(
+                options.size,
+                &rating,
+                &options.themes,
+                excluded.as_ref().unwrap_or(&nothing_excluded),
+            )
+            .await
             .map_err(|source| CreateTrainingSetError::RepositoryError { source })?;
+        tracing::debug!(
+            found = puzzles.len(),
+            excluded = excluded.as_ref().map_or(0, HashSet::len),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "found random puzzles"
+        );
 
         if puzzles.len() != options.size {
-            return Err(CreateTrainingSetError::CriteriaUnmet);
+            let preview = self
+                .count_matching(options.size, &rating, &options.themes, excluded.as_ref())
+                .await
+                .map_err(|source| CreateTrainingSetError::RepositoryError { source })?;
+            return Err(CreateTrainingSetError::CriteriaUnmet {
+                requested: options.size,
+                available: puzzles.len(),
+                most_restrictive: preview.most_restrictive,
+            });
         }
 
         let puzzle_ids = puzzles.iter().map(|puzzle| puzzle.id).collect();
 
         let create_set = training_set_repository::CreateTrainingSet {
+            user_id,
             puzzle_ids,
             name: options.name,
-            rating: options.rating,
+            rating,
             themes: options.themes,
+            adaptive: options.adaptive,
             current_progress: 0,
             cycles_done: 0,
         };
         self.training_set_repository
             .create(create_set)
+            .await
             .map_err(|source| CreateTrainingSetError::RepositoryError { source })
     }
+
+    #[instrument(skip_all, fields(size = options.size, rating = ?options.rating, themes = ?options.themes))]
+    async fn preview_set(
+        &self,
+        user_id: UserId,
+        options: PreviewTrainingSetOptions,
+    ) -> Result<TrainingSetPreview, CreateTrainingSetError> {
+        self.validate_size(options.size)?;
+
+        let excluded = self
+            .find_excluded(
+                user_id,
+                options.exclude_seen,
+                options.exclude_attempted_days,
+            )
+            .await
+            .map_err(|source| CreateTrainingSetError::RepositoryError { source })?;
+
+        self.count_matching(
+            options.size,
+            &options.rating,
+            &options.themes,
+            excluded.as_ref(),
+        )
+        .await
+        .map_err(|source| CreateTrainingSetError::RepositoryError { source })
+    }
+
+    async fn list_sets(&self, user_id: UserId) -> anyhow::Result<Vec<TrainingSet>> {
+        self.training_set_repository.find_by_user(user_id).await
+    }
+
+    async fn get_set(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+    ) -> anyhow::Result<Option<TrainingSet>> {
+        self.training_set_repository.find(user_id, id).await
+    }
+
+    #[instrument(skip(self, options), fields(puzzle_id = options.puzzle_id, solved = options.solved))]
+    async fn record_attempt(
+        &self,
+        user_id: UserId,
+        set_id: TrainingSetId,
+        options: RecordAttemptOptions,
+    ) -> Result<AttemptResult, RecordAttemptError> {
+        let training_set = self
+            .training_set_repository
+            .find(user_id, set_id)
+            .await
+            .map_err(|source| RecordAttemptError::RepositoryError { source })?
+            .ok_or(RecordAttemptError::SetNotFound)?;
+        let expected = training_set
+            .puzzle_ids
+            .get(training_set.current_progress as usize)
+            .copied()
+            .with_context(|| format!("progress of set {} is out of range", set_id))
+            .map_err(|source| RecordAttemptError::RepositoryError { source })?;
+        if options.puzzle_id != expected {
+            return Err(RecordAttemptError::NotCurrentPuzzle { expected });
+        }
+        let puzzle = self
+            .puzzle_repository
+            .find_by_ids(&[expected])
+            .await
+            .and_then(|puzzles| {
+                puzzles
+                    .into_iter()
+                    .next()
+                    .with_context(|| format!("puzzle {} of set not found", expected))
+            })
+            .map_err(|source| RecordAttemptError::RepositoryError { source })?;
+
+        let from = Progress::of(&training_set);
+        let to = if from.current_progress as usize + 1 < training_set.puzzle_ids.len() {
+            Progress {
+                current_progress: from.current_progress + 1,
+                ..from
+            }
+        } else {
+            Progress {
+                current_progress: 0,
+                cycles_done: from.cycles_done + 1,
+            }
+        };
+        let attempted_at = Utc::now().trunc_subsecs(6);
+        let quality = srs::quality(options.solved, options.solve_time_ms);
+        let review = srs::needs_review(quality).then(|| {
+            let schedule = srs::restart(quality);
+            SaveReview {
+                user_id,
+                puzzle_id: expected,
+                schedule,
+                due_at: srs::due_at(attempted_at, &schedule),
+                reviewed_at: attempted_at,
+            }
+        });
+        let RecordedAttempt {
+            attempt,
+            rating,
+            review,
+        } = self
+            .attempt_repository
+            .record(RecordAttempt {
+                user_id,
+                training_set_id: set_id,
+                puzzle_id: expected,
+                from,
+                to,
+                solved: options.solved,
+                solve_time_ms: options.solve_time_ms,
+                attempted_at,
+                outcome: glicko::Outcome {
+                    rating: f64::from(puzzle.lichess_rating),
+                    deviation: f64::from(puzzle.lichess_rating_deviation),
+                    score: if options.solved { 1.0 } else { 0.0 },
+                },
+                review,
+            })
+            .await
+            .map_err(|source| RecordAttemptError::RepositoryError { source })?
+            .ok_or(RecordAttemptError::ProgressConflict)?;
+
+        Ok(AttemptResult {
+            attempt,
+            training_set: TrainingSet {
+                current_progress: to.current_progress,
+                cycles_done: to.cycles_done,
+                ..training_set
+            },
+            rating,
+            review,
+        })
+    }
+
+    async fn get_rating(&self, user_id: UserId) -> anyhow::Result<RatingSummary> {
+        let rating = self.find_rating(user_id).await?;
+        Ok(RatingSummary {
+            rating,
+            suggested_range: glicko::suggested_range(&rating),
+        })
+    }
+
+    async fn rating_history(&self, user_id: UserId) -> anyhow::Result<Vec<RatingHistoryEntry>> {
+        self.attempt_repository.find_rating_history(user_id).await
+    }
+
+    async fn due_reviews(&self, user_id: UserId) -> anyhow::Result<Vec<Review>> {
+        self.review_repository.find_due(user_id, Utc::now()).await
+    }
+
+    #[instrument(skip(self, options), fields(solved = options.solved))]
+    async fn record_review(
+        &self,
+        user_id: UserId,
+        id: ReviewId,
+        options: RecordReviewOptions,
+    ) -> Result<Review, RecordReviewError> {
+        let review = self
+            .review_repository
+            .find(user_id, id)
+            .await
+            .map_err(|source| RecordReviewError::RepositoryError { source })?
+            .ok_or(RecordReviewError::ReviewNotFound)?;
+        let now = Utc::now().trunc_subsecs(6);
+        if review.due_at > now {
+            return Err(RecordReviewError::NotDue {
+                due_at: review.due_at,
+            });
+        }
+        self.schedule_review(
+            user_id,
+            review.puzzle_id,
+            &review.schedule,
+            srs::quality(options.solved, options.solve_time_ms),
+            now,
+        )
+        .await
+        .map_err(|source| RecordReviewError::RepositoryError { source })
+    }
+
+    async fn daily_session(&self, user_id: UserId, size: usize) -> anyhow::Result<DailySession> {
+        let (reviews, sets) = tokio::try_join!(
+            self.review_repository.find_due(user_id, Utc::now()),
+            self.training_set_repository.find_by_user(user_id),
+        )?;
+        let mut items: Vec<SessionItem> = reviews
+            .iter()
+            .take(size)
+            .map(|review| SessionItem::Review {
+                review_id: review.id,
+                puzzle_id: review.puzzle_id,
+            })
+            .collect();
+
+        let mut upcoming: Vec<_> = sets
+            .iter()
+            .map(|set| {
+                (set.current_progress..)
+                    .zip(&set.puzzle_ids[set.current_progress as usize..])
+                    .map(|(position, &puzzle_id)| SessionItem::Set {
+                        training_set_id: set.id,
+                        puzzle_id,
+                        position,
+                    })
+            })
+            .collect();
+        while items.len() < size {
+            let before = items.len();
+            for set in upcoming.iter_mut() {
+                if items.len() == size {
+                    break;
+                }
+                items.extend(set.next());
+            }
+            if items.len() == before {
+                break;
+            }
+        }
+
+        Ok(DailySession {
+            reviews_due: reviews.len(),
+            items,
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn set_plan(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+        plan: CyclePlan,
+    ) -> Result<PlanStatus, CyclePlanError> {
+        if !(1..=woodpecker::MAX_CYCLES).contains(&plan.cycle_days.len()) {
+            return Err(CyclePlanError::CycleCountOutOfRange {
+                max: woodpecker::MAX_CYCLES,
+            });
+        }
+        if !plan
+            .cycle_days
+            .iter()
+            .all(|days| (1..=woodpecker::MAX_CYCLE_DAYS).contains(days))
+        {
+            return Err(CyclePlanError::CycleDaysOutOfRange {
+                max: woodpecker::MAX_CYCLE_DAYS,
+            });
+        }
+        let updated = self
+            .training_set_repository
+            .update_plan(user_id, id, plan)
+            .await
+            .map_err(|source| CyclePlanError::RepositoryError { source })?;
+        if !updated {
+            return Err(CyclePlanError::SetNotFound);
+        }
+        self.plan_status(user_id, id).await
+    }
+
+    async fn plan_status(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+    ) -> Result<PlanStatus, CyclePlanError> {
+        let (training_set, attempts) = tokio::try_join!(
+            self.training_set_repository.find(user_id, id),
+            self.attempt_repository.find_by_set(user_id, id),
+        )
+        .map_err(|source| CyclePlanError::RepositoryError { source })?;
+        let training_set = training_set.ok_or(CyclePlanError::SetNotFound)?;
+        let plan = training_set
+            .plan
+            .as_ref()
+            .ok_or(CyclePlanError::PlanNotFound)?;
+        Ok(woodpecker::status(
+            &training_set,
+            plan,
+            &attempts,
+            Utc::now(),
+        ))
+    }
+
+    async fn set_stats(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+    ) -> anyhow::Result<Option<Statistics>> {
+        let (training_set, attempts) = tokio::try_join!(
+            self.training_set_repository.find(user_id, id),
+            self.attempt_repository.find_by_set(user_id, id),
+        )?;
+        match training_set {
+            Some(_) => Ok(Some(self.statistics(attempts).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn user_stats(&self, user_id: UserId) -> anyhow::Result<Statistics> {
+        let attempts = self
+            .attempt_repository
+            .find_recent(user_id, stats::RECENT_ATTEMPTS)
+            .await?;
+        self.statistics(attempts).await
+    }
+
+    #[instrument(skip(self))]
+    async fn suggest_set(&self, user_id: UserId) -> anyhow::Result<SetSuggestion> {
+        let (rating, attempts) = tokio::try_join!(
+            self.find_rating(user_id),
+            self.attempt_repository
+                .find_recent(user_id, stats::RECENT_ATTEMPTS),
+        )?;
+        let puzzle_ids: Vec<PuzzleId> = attempts
+            .iter()
+            .map(|attempt| attempt.puzzle_id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let puzzles: HashMap<PuzzleId, Puzzle> = self
+            .puzzle_repository
+            .find_by_ids(&puzzle_ids)
+            .await?
+            .into_iter()
+            .map(|puzzle| (puzzle.id, puzzle))
+            .collect();
+        let weaknesses = weakness::detect(&rating, &attempts, &puzzles);
+        if weaknesses.is_empty() {
+            return Ok(SetSuggestion {
+                weaknesses,
+                options: None,
+            });
+        }
+
+        let themes: Vec<Theme> = weaknesses
+            .iter()
+            .take(weakness::SUGGESTED_THEMES)
+            .map(|weakness| weakness.theme)
+            .collect();
+        let rating = weakness::target_range(&rating, &attempts, &puzzles, &themes);
+        let mut name = format!(
+            "Weaknesses: {}",
+            themes
+                .iter()
+                .map(Theme::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        name.truncate(self.limits.max_set_name_length);
+        let themes = ThemeChoice::Themes(themes);
+        let excluded = self
+            .find_excluded(user_id, true, None)
+            .await?
+            .unwrap_or_default();
+        let available = self
+            .puzzle_repository
+            .count_matching(&rating, &themes, &excluded)
+            .await?;
+        if available < self.limits.min_set_size {
+            return Ok(SetSuggestion {
+                weaknesses,
+                options: None,
+            });
+        }
+        let size = weakness::SUGGESTED_SIZE
+            .min(available)
+            .clamp(self.limits.min_set_size, self.limits.max_set_size);
+
+        Ok(SetSuggestion {
+            weaknesses,
+            options: Some(CreateTrainingSetOptions {
+                name,
+                size,
+                rating: Some(rating),
+                adaptive: None,
+                themes,
+                exclude_seen: true,
+                exclude_attempted_days: None,
+            }),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use std::iter::repeat_with;
 
+    use chrono::{DateTime, Duration, Utc};
     use parking_lot::Mutex;
-    use uuid::uuid;
+    use uuid::{uuid, Uuid};
 
-    use crate::puzzle::errors::CreateTrainingSetError;
-    use crate::puzzle::puzzle_repository::MockPuzzleRepository;
-    use crate::puzzle::service::PuzzleServiceImplBuilder;
-    use crate::puzzle::training_set_repository::MockTrainingSetRepository;
+    use crate::puzzle::attempt_repository::{InMemoryAttemptRepository, MockAttemptRepository};
+    use crate::puzzle::config::SetLimits;
+    use crate::puzzle::errors::{
+        CreateTrainingSetError, CyclePlanError, RecordAttemptError, RecordReviewError,
+    };
+    use crate::puzzle::puzzle_repository::{InMemoryPuzzleRepository, MockPuzzleRepository};
+    use crate::puzzle::review_repository::{
+        InMemoryReviewRepository, MockReviewRepository, ReviewRepository, SaveReview,
+    };
+    use crate::puzzle::service::{PuzzleServiceImpl, PuzzleServiceImplBuilder};
+    use crate::puzzle::training_set_repository::{
+        CreateTrainingSet, InMemoryTrainingSetRepository, MockTrainingSetRepository,
+        TrainingSetRepository,
+    };
     use crate::puzzle::types::{
-        CreateTrainingSetOptions, CreateTrainingSetOptionsBuilder, LichessPuzzleImportBuilder,
-        Puzzle, PuzzleBuilder, PuzzleId, Theme, ThemeChoice, TrainingSet, TrainingSetId,
+        AdaptiveDifficulty, Attempt, CreateTrainingSetOptions, CreateTrainingSetOptionsBuilder,
+        CriteriaFilter, CyclePlan, LichessPuzzleImportBuilder, PreviewTrainingSetOptionsBuilder,
+        Puzzle, PuzzleBuilder, PuzzleId, Rating, RecordAttemptOptions, RecordReviewOptions, Review,
+        Schedule, ScheduleState, SessionItem, Theme, ThemeChoice, TrainingSet, TrainingSetId,
+        TrainingSetPreview,
     };
     use crate::puzzle::PuzzleService;
+    use crate::puzzle::{difficulty, stats};
+    use crate::user::types::UserId;
 
     fn sample_puzzle() -> PuzzleBuilder {
         let mut builder = PuzzleBuilder::default();
@@ -155,7 +997,8 @@ mod tests {
             .name("sample-training-set-name".to_string())
             .size(10)
             .rating(1500..=1600)
-            .themes(ThemeChoice::HealthyMix);
+            .themes(ThemeChoice::HealthyMix)
+            .exclude_seen(false);
         builder
     }
 
@@ -163,10 +1006,21 @@ mod tests {
         uuid!("e649d0cc-3244-483d-922a-e8269d006ffe")
     }
 
-    fn make_service() -> PuzzleServiceImplBuilder<MockPuzzleRepository, MockTrainingSetRepository> {
+    fn sample_user_id() -> UserId {
+        uuid!("0b8e3c0e-5a4f-4d8e-9f53-2f1c7f0f4a11")
+    }
+
+    fn make_service() -> PuzzleServiceImplBuilder<
+        MockPuzzleRepository,
+        MockTrainingSetRepository,
+        MockAttemptRepository,
+        MockReviewRepository,
+    > {
         PuzzleServiceImplBuilder::default()
             .puzzle_repository(MockPuzzleRepository::new())
             .training_set_repository(MockTrainingSetRepository::new())
+            .attempt_repository(MockAttemptRepository::new())
+            .review_repository(MockReviewRepository::new())
     }
 
     fn stub_puzzle_repository_creates(puzzle_repository: &mut MockPuzzleRepository) {
@@ -196,7 +1050,7 @@ mod tests {
     ) {
         puzzle_repository
             .expect_find_random()
-            .returning(move |size, _, _| {
+            .returning(move |size, _, _, _| {
                 Ok((0..usize::min(size, size_limit.unwrap_or(usize::MAX)))
                     .map(|id| sample_puzzle().id(id as PuzzleId).build().unwrap())
                     .take(size)
@@ -210,18 +1064,21 @@ mod tests {
             .returning_st(move |set| {
                 Ok(TrainingSet {
                     id: sample_training_set_id(),
+                    user_id: set.user_id,
                     puzzle_ids: set.puzzle_ids,
                     name: set.name,
                     rating: set.rating,
                     themes: set.themes,
+                    adaptive: set.adaptive,
+                    plan: None,
                     current_progress: set.current_progress,
                     cycles_done: set.cycles_done,
                 })
             });
     }
 
-    #[test]
-    fn should_import_lichess_puzzle() {
+    #[tokio::test]
+    async fn should_import_lichess_puzzle() {
         // given Lichess puzzle:
         let lichess_puzzle = sample_lichess_puzzle().build().unwrap();
 
@@ -234,15 +1091,15 @@ mod tests {
             .puzzle_repository(puzzle_repository)
             .build()
             .unwrap();
-        let imported_puzzle = service.import_puzzle(lichess_puzzle).unwrap();
+        let imported_puzzle = service.import_puzzle(lichess_puzzle).await.unwrap();
 
         // then it has correct data:
         let expected_puzzle = sample_puzzle().build().unwrap();
         assert_eq!(imported_puzzle, expected_puzzle);
     }
 
-    #[test]
-    fn should_create_set() {
+    #[tokio::test]
+    async fn should_create_set() {
         // given set options:
         let name = "My training set";
         let rating = 1500..=1600;
@@ -250,8 +1107,11 @@ mod tests {
         let options = CreateTrainingSetOptions {
             name: name.to_string(),
             size: 10,
-            rating,
+            rating: Some(rating.clone()),
             themes,
+            adaptive: None,
+            exclude_seen: false,
+            exclude_attempted_days: None,
         };
 
         // and repository that finds random puzzles:
@@ -268,23 +1128,29 @@ mod tests {
             .training_set_repository(training_set_repository)
             .build()
             .unwrap();
-        let set = service.create_set(options.clone()).unwrap();
+        let set = service
+            .create_set(sample_user_id(), options.clone())
+            .await
+            .unwrap();
 
         // then it has correct data:
         let expected = TrainingSet {
             id: sample_training_set_id(),
+            user_id: sample_user_id(),
             puzzle_ids: vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
             name: name.to_string(),
-            rating: options.rating,
+            rating,
             themes: options.themes,
+            adaptive: None,
+            plan: None,
             current_progress: 0,
             cycles_done: 0,
         };
         assert_eq!(set, expected);
     }
 
-    #[test]
-    fn should_disallow_creating_sets_with_name_empty() {
+    #[tokio::test]
+    async fn should_disallow_creating_sets_with_name_empty() {
         // given too long name:
         let options = sample_create_training_set_options()
             .name("".to_string())
@@ -293,7 +1159,7 @@ mod tests {
 
         // when set is created:
         let service = make_service().build().unwrap();
-        let create_set_result = service.create_set(options);
+        let create_set_result = service.create_set(sample_user_id(), options).await;
 
         // then error is returned:
         assert!(matches!(
@@ -302,8 +1168,8 @@ mod tests {
         ));
     }
 
-    #[test]
-    fn should_disallow_creating_sets_with_name_too_long() {
+    #[tokio::test]
+    async fn should_disallow_creating_sets_with_name_too_long() {
         // given too long name:
         let name: String = repeat_with(|| "a").take(101).collect();
         let options = sample_create_training_set_options()
@@ -313,17 +1179,17 @@ mod tests {
 
         // when set is created:
         let service = make_service().build().unwrap();
-        let create_set_result = service.create_set(options);
+        let create_set_result = service.create_set(sample_user_id(), options).await;
 
         // then error is returned:
         assert!(matches!(
             create_set_result,
-            Err(CreateTrainingSetError::NameLengthLimitExceeded)
+            Err(CreateTrainingSetError::NameLengthLimitExceeded { max: 100 })
         ));
     }
 
-    #[test]
-    fn should_disallow_creating_sets_with_size_too_small() {
+    #[tokio::test]
+    async fn should_disallow_creating_sets_with_size_too_small() {
         // given too small size:
         let options = sample_create_training_set_options()
             .size(4)
@@ -332,17 +1198,17 @@ mod tests {
 
         // when set is created:
         let service = make_service().build().unwrap();
-        let create_set_result = service.create_set(options);
+        let create_set_result = service.create_set(sample_user_id(), options).await;
 
         // then error is returned:
         assert!(matches!(
             create_set_result,
-            Err(CreateTrainingSetError::SizeTooSmall)
+            Err(CreateTrainingSetError::SizeTooSmall { min: 5 })
         ));
     }
 
-    #[test]
-    fn should_disallow_creating_sets_with_size_too_large() {
+    #[tokio::test]
+    async fn should_disallow_creating_sets_with_size_too_large() {
         // given too large size:
         let options = sample_create_training_set_options()
             .size(1001)
@@ -351,17 +1217,42 @@ mod tests {
 
         // when set is created:
         let service = make_service().build().unwrap();
-        let create_set_result = service.create_set(options);
+        let create_set_result = service.create_set(sample_user_id(), options).await;
 
         // then error is returned:
         assert!(matches!(
             create_set_result,
-            Err(CreateTrainingSetError::SizeLimitExceeded)
+            Err(CreateTrainingSetError::SizeLimitExceeded { max: 1000 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_apply_configured_limits() {
+        // given size allowed by default limits:
+        let options = sample_create_training_set_options()
+            .size(100)
+            .build()
+            .unwrap();
+
+        // when set is created with lower limit configured:
+        let service = make_service()
+            .limits(SetLimits {
+                max_set_size: 50,
+                ..SetLimits::default()
+            })
+            .build()
+            .unwrap();
+        let create_set_result = service.create_set(sample_user_id(), options).await;
+
+        // then error with configured limit is returned:
+        assert!(matches!(
+            create_set_result,
+            Err(CreateTrainingSetError::SizeLimitExceeded { max: 50 })
         ));
     }
 
-    #[test]
-    fn should_fail_when_creating_set_with_unmet_criteria() {
+    #[tokio::test]
+    async fn should_fail_when_creating_set_with_unmet_criteria() {
         // given valid criteria:
         let size = 20;
         let options = sample_create_training_set_options()
@@ -369,21 +1260,885 @@ mod tests {
             .build()
             .unwrap();
 
-        // and repository that finds less than requested puzzles:
+        // and repository that finds less than requested puzzles due to rating:
         let mut puzzle_repository = MockPuzzleRepository::default();
         stub_puzzle_repository_finds_random(&mut puzzle_repository, Some(10));
+        puzzle_repository
+            .expect_count_matching()
+            .returning(|rating, _, _| Ok(if *rating == (0..=u16::MAX) { 5000 } else { 10 }));
 
         // when set is created:
         let service = make_service()
             .puzzle_repository(puzzle_repository)
             .build()
             .unwrap();
-        let create_set_result = service.create_set(options);
+        let create_set_result = service.create_set(sample_user_id(), options).await;
 
         // then error is returned:
         assert!(matches!(
             create_set_result,
-            Err(CreateTrainingSetError::CriteriaUnmet)
+            Err(CreateTrainingSetError::CriteriaUnmet {
+                requested: 20,
+                available: 10,
+                most_restrictive: CriteriaFilter::Rating,
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_preview_set() {
+        // given preview options:
+        let rating = 1500..=1600;
+        let themes = ThemeChoice::Themes(vec![Theme::Fork]);
+        let options = PreviewTrainingSetOptionsBuilder::default()
+            .size(50)
+            .rating(rating.clone())
+            .themes(themes.clone())
+            .exclude_seen(true)
+            .build()
+            .unwrap();
+
+        // and repository that knows puzzles from other sets:
+        let seen: HashSet<PuzzleId> = [1, 2].into();
+        let mut training_set_repository = MockTrainingSetRepository::new();
+        let seen_clone = seen.clone();
+        training_set_repository
+            .expect_find_puzzle_ids()
+            .withf(|user_id| *user_id == sample_user_id())
+            .returning(move |_| Ok(seen_clone.clone()));
+
+        // and repository that counts matching puzzles:
+        let mut puzzle_repository = MockPuzzleRepository::new();
+        puzzle_repository
+            .expect_count_matching()
+            .returning(move |r, t, excluded| {
+                Ok(match (*r == rating, *t == themes, *excluded == seen) {
+                    (true, true, true) => 30,
+                    (true, false, false) => 400,
+                    (false, true, false) => 200,
+                    (false, false, true) => 9998,
+                    _ => unreachable!(),
+                })
+            });
+
+        // when set is previewed:
+        let service = make_service()
+            .puzzle_repository(puzzle_repository)
+            .training_set_repository(training_set_repository)
+            .build()
+            .unwrap();
+        let preview = service
+            .preview_set(sample_user_id(), options)
+            .await
+            .unwrap();
+
+        // then counts are reported:
+        let expected = TrainingSetPreview {
+            requested: 50,
+            available: 30,
+            matching_rating: 400,
+            matching_themes: 200,
+            matching_unseen: Some(9998),
+            most_restrictive: CriteriaFilter::Themes,
+        };
+        assert_eq!(preview, expected);
+    }
+
+    #[tokio::test]
+    async fn should_exclude_puzzles_seen_in_other_sets() {
+        // given options excluding seen puzzles:
+        let options = sample_create_training_set_options()
+            .exclude_seen(true)
+            .build()
+            .unwrap();
+
+        // and repository that knows puzzles from other sets:
+        let seen: HashSet<PuzzleId> = [100, 101, 102].into();
+        let mut training_set_repository = MockTrainingSetRepository::new();
+        let seen_clone = seen.clone();
+        training_set_repository
+            .expect_find_puzzle_ids()
+            .withf(|user_id| *user_id == sample_user_id())
+            .returning(move |_| Ok(seen_clone.clone()));
+        stub_set_repository_creates(&mut training_set_repository);
+
+        // and repository that finds random puzzles:
+        let mut puzzle_repository = MockPuzzleRepository::new();
+        puzzle_repository
+            .expect_find_random()
+            .withf(move |_, _, _, excluded| *excluded == seen)
+            .returning(|size, _, _, _| {
+                Ok((0..size)
+                    .map(|id| sample_puzzle().id(id as PuzzleId).build().unwrap())
+                    .collect())
+            });
+
+        // when set is created:
+        let service = make_service()
+            .puzzle_repository(puzzle_repository)
+            .training_set_repository(training_set_repository)
+            .build()
+            .unwrap();
+        let create_set_result = service.create_set(sample_user_id(), options).await;
+
+        // then seen puzzles are excluded by the repository:
+        assert!(create_set_result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_exclude_puzzles_attempted_recently() {
+        // given options excluding seen puzzles and puzzles attempted in the last week:
+        let options = sample_create_training_set_options()
+            .exclude_seen(true)
+            .exclude_attempted_days(7)
+            .build()
+            .unwrap();
+
+        // and repository that knows puzzles from other sets:
+        let mut training_set_repository = MockTrainingSetRepository::new();
+        training_set_repository
+            .expect_find_puzzle_ids()
+            .returning(|_| Ok([100, 101].into()));
+        stub_set_repository_creates(&mut training_set_repository);
+
+        // and repository that knows puzzles attempted since a week ago:
+        let mut attempt_repository = MockAttemptRepository::new();
+        attempt_repository
+            .expect_find_attempted_puzzle_ids()
+            .withf(|user_id, since| {
+                let week_ago = Utc::now() - Duration::days(7);
+                *user_id == sample_user_id() && (week_ago - *since).num_seconds().abs() < 60
+            })
+            .returning(|_, _| Ok([101, 200].into()));
+
+        // and repository that finds random puzzles:
+        let mut puzzle_repository = MockPuzzleRepository::new();
+        puzzle_repository
+            .expect_find_random()
+            .withf(|_, _, _, excluded| *excluded == [100, 101, 200].into())
+            .returning(|size, _, _, _| {
+                Ok((0..size)
+                    .map(|id| sample_puzzle().id(id as PuzzleId).build().unwrap())
+                    .collect())
+            });
+
+        // when set is created:
+        let service = make_service()
+            .puzzle_repository(puzzle_repository)
+            .training_set_repository(training_set_repository)
+            .attempt_repository(attempt_repository)
+            .build()
+            .unwrap();
+        let create_set_result = service.create_set(sample_user_id(), options).await;
+
+        // then both seen and recently attempted puzzles are excluded by the repository:
+        assert!(create_set_result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_create_set_around_rating_by_default() {
+        // given options without rating:
+        let mut options = sample_create_training_set_options().build().unwrap();
+        options.rating = None;
+
+        // and user rated 1800:
+        let mut attempt_repository = MockAttemptRepository::new();
+        attempt_repository.expect_find_rating().returning(|_| {
+            Ok(Some(Rating {
+                rating: 1800.0,
+                deviation: 60.0,
+                volatility: 0.06,
+            }))
+        });
+
+        // and repositories that find random puzzles and create sets:
+        let mut puzzle_repository = MockPuzzleRepository::new();
+        puzzle_repository
+            .expect_find_random()
+            .withf(|_, rating, _, _| *rating == (1700..=1900))
+            .returning(|size, _, _, _| {
+                Ok((0..size)
+                    .map(|id| sample_puzzle().id(id as PuzzleId).build().unwrap())
+                    .collect())
+            });
+        let mut training_set_repository = MockTrainingSetRepository::new();
+        stub_set_repository_creates(&mut training_set_repository);
+
+        // when set is created:
+        let service = make_service()
+            .puzzle_repository(puzzle_repository)
+            .training_set_repository(training_set_repository)
+            .attempt_repository(attempt_repository)
+            .build()
+            .unwrap();
+        let set = service.create_set(sample_user_id(), options).await.unwrap();
+
+        // then it's centred on the rating:
+        assert_eq!(set.rating, 1700..=1900);
+    }
+
+    #[tokio::test]
+    async fn should_create_adaptive_set_from_recent_form() {
+        // given adaptive options:
+        let adaptive = AdaptiveDifficulty {
+            target_success_percent: 75,
+        };
+        let mut options = sample_create_training_set_options()
+            .adaptive(adaptive)
+            .build()
+            .unwrap();
+        options.rating = None;
+
+        // and user rated 1800 who recently failed puzzles rated 1800:
+        let mut attempt_repository = MockAttemptRepository::new();
+        attempt_repository.expect_find_rating().returning(|_| {
+            Ok(Some(Rating {
+                rating: 1800.0,
+                deviation: 60.0,
+                volatility: 0.06,
+            }))
+        });
+        attempt_repository
+            .expect_find_recent()
+            .withf(|_, limit| *limit == difficulty::RECENT_ATTEMPTS)
+            .returning(|user_id, _| {
+                Ok((0..5)
+                    .map(|puzzle_id| Attempt {
+                        id: Uuid::new_v4(),
+                        user_id,
+                        training_set_id: sample_training_set_id(),
+                        puzzle_id,
+                        cycle: 0,
+                        solved: false,
+                        solve_time_ms: 20_000,
+                        attempted_at: Utc::now(),
+                    })
+                    .collect())
+            });
+
+        // and repositories that find puzzles and create sets:
+        let mut puzzle_repository = MockPuzzleRepository::new();
+        puzzle_repository.expect_find_by_ids().returning(|ids| {
+            Ok(ids
+                .iter()
+                .map(|&id| sample_puzzle().id(id).lichess_rating(1800).build().unwrap())
+                .collect())
+        });
+        stub_puzzle_repository_finds_random(&mut puzzle_repository, None);
+        let mut training_set_repository = MockTrainingSetRepository::new();
+        stub_set_repository_creates(&mut training_set_repository);
+
+        // when set is created:
+        let service = make_service()
+            .puzzle_repository(puzzle_repository)
+            .training_set_repository(training_set_repository)
+            .attempt_repository(attempt_repository)
+            .build()
+            .unwrap();
+        let set = service.create_set(sample_user_id(), options).await.unwrap();
+
+        // then it's below the range for 75% success on the rating alone:
+        let rating_alone = 1506..=1706;
+        assert!(set.rating.start() < rating_alone.start());
+        assert_eq!(set.rating.end() - set.rating.start(), 200);
+        assert_eq!(set.adaptive, Some(adaptive));
+    }
+
+    #[tokio::test]
+    async fn should_disallow_invalid_adaptive_difficulty() {
+        // given adaptive options with rating, or unreachable target:
+        let adaptive = |target_success_percent| AdaptiveDifficulty {
+            target_success_percent,
+        };
+        let with_rating = sample_create_training_set_options()
+            .adaptive(adaptive(75))
+            .build()
+            .unwrap();
+        let mut too_hard = sample_create_training_set_options()
+            .adaptive(adaptive(30))
+            .build()
+            .unwrap();
+        too_hard.rating = None;
+
+        // when sets are created:
+        let service = make_service().build().unwrap();
+        let with_rating = service.create_set(sample_user_id(), with_rating).await;
+        let too_hard = service.create_set(sample_user_id(), too_hard).await;
+
+        // then errors are returned:
+        assert!(matches!(
+            with_rating,
+            Err(CreateTrainingSetError::ConflictingDifficulty)
+        ));
+        assert!(matches!(
+            too_hard,
+            Err(CreateTrainingSetError::TargetSuccessOutOfRange { min: 50, max: 95 })
         ));
     }
+
+    type InMemoryService = PuzzleServiceImpl<
+        InMemoryPuzzleRepository,
+        InMemoryTrainingSetRepository,
+        InMemoryAttemptRepository,
+        InMemoryReviewRepository,
+    >;
+
+    async fn make_attempt_service(size: usize) -> (InMemoryService, TrainingSet) {
+        let puzzle_repository = InMemoryPuzzleRepository::new();
+        let training_set_repository = InMemoryTrainingSetRepository::new();
+        let review_repository = InMemoryReviewRepository::new();
+        let service = PuzzleServiceImplBuilder::default()
+            .puzzle_repository(puzzle_repository)
+            .training_set_repository(training_set_repository.clone())
+            .attempt_repository(InMemoryAttemptRepository::new(
+                training_set_repository,
+                review_repository.clone(),
+            ))
+            .review_repository(review_repository)
+            .build()
+            .unwrap();
+        let mut puzzle_ids = Vec::new();
+        for i in 0..size {
+            let lichess_puzzle = sample_lichess_puzzle()
+                .puzzle_id(format!("puzzle-{}", i))
+                .build()
+                .unwrap();
+            puzzle_ids.push(service.import_puzzle(lichess_puzzle).await.unwrap().id);
+        }
+        let set = service
+            .training_set_repository
+            .create(CreateTrainingSet {
+                user_id: sample_user_id(),
+                puzzle_ids,
+                name: "set".to_string(),
+                rating: 1500..=1500,
+                themes: ThemeChoice::HealthyMix,
+                adaptive: None,
+                current_progress: 0,
+                cycles_done: 0,
+            })
+            .await
+            .unwrap();
+        (service, set)
+    }
+
+    fn attempt(puzzle_id: PuzzleId, solved: bool) -> RecordAttemptOptions {
+        RecordAttemptOptions {
+            puzzle_id,
+            solved,
+            solve_time_ms: 20_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn should_record_attempts_through_cycle() {
+        // given set of two puzzles:
+        let (service, set) = make_attempt_service(2).await;
+
+        // when both are attempted:
+        let first = service
+            .record_attempt(sample_user_id(), set.id, attempt(set.puzzle_ids[0], true))
+            .await
+            .unwrap();
+        let second = service
+            .record_attempt(sample_user_id(), set.id, attempt(set.puzzle_ids[1], false))
+            .await
+            .unwrap();
+
+        // then set advances to the next cycle:
+        assert_eq!(first.training_set.current_progress, 1);
+        assert_eq!(second.attempt.cycle, 0);
+        assert_eq!(second.training_set.current_progress, 0);
+        assert_eq!(second.training_set.cycles_done, 1);
+        assert_eq!(
+            service.get_set(sample_user_id(), set.id).await.unwrap(),
+            Some(second.training_set)
+        );
+
+        // and rating follows results:
+        assert!(first.rating.rating > 1500.0);
+        assert!(second.rating.rating < first.rating.rating);
+        assert!(second.rating.deviation < first.rating.deviation);
+        let summary = service.get_rating(sample_user_id()).await.unwrap();
+        assert_eq!(summary.rating, second.rating);
+        let history = service.rating_history(sample_user_id()).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].attempt_id, second.attempt.id);
+    }
+
+    #[tokio::test]
+    async fn should_reject_attempt_out_of_order() {
+        // given set:
+        let (service, set) = make_attempt_service(3).await;
+
+        // when other than the next puzzle is attempted, or other user's set:
+        let out_of_order = service
+            .record_attempt(sample_user_id(), set.id, attempt(set.puzzle_ids[1], true))
+            .await;
+        let not_owned = service
+            .record_attempt(
+                uuid!("3f1f6b52-8f55-4bb4-9b35-6d1c4f0c2d11"),
+                set.id,
+                attempt(set.puzzle_ids[0], true),
+            )
+            .await;
+
+        // then they're rejected and nothing changes:
+        assert!(matches!(
+            out_of_order,
+            Err(RecordAttemptError::NotCurrentPuzzle { expected }) if expected == set.puzzle_ids[0]
+        ));
+        assert!(matches!(not_owned, Err(RecordAttemptError::SetNotFound)));
+        assert!(service
+            .rating_history(sample_user_id())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_queue_failed_and_slow_attempts_for_review() {
+        // given set of three puzzles:
+        let (service, set) = make_attempt_service(3).await;
+
+        // when they're failed, solved slowly and solved quickly:
+        let mut results = Vec::new();
+        for (puzzle_id, solved, solve_time_ms) in [
+            (set.puzzle_ids[0], false, 20_000),
+            (set.puzzle_ids[1], true, 90_000),
+            (set.puzzle_ids[2], true, 20_000),
+        ] {
+            let options = RecordAttemptOptions {
+                puzzle_id,
+                solved,
+                solve_time_ms,
+            };
+            results.push(
+                service
+                    .record_attempt(sample_user_id(), set.id, options)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        // then the first two are queued for review tomorrow:
+        let reviews: Vec<_> = results
+            .iter()
+            .map(|result| result.review.as_ref().map(|review| review.puzzle_id))
+            .collect();
+        assert_eq!(
+            reviews,
+            vec![Some(set.puzzle_ids[0]), Some(set.puzzle_ids[1]), None]
+        );
+        let failed = results[0].review.as_ref().unwrap();
+        assert_eq!(failed.schedule.interval_days, 1);
+        assert_eq!(failed.due_at, failed.reviewed_at + Duration::days(1));
+        assert!(service
+            .due_reviews(sample_user_id())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_restart_review_of_puzzle_missed_in_set() {
+        // given puzzle of the set already known well:
+        let (service, set) = make_attempt_service(1).await;
+        let known = service
+            .review_repository
+            .save(SaveReview {
+                user_id: sample_user_id(),
+                puzzle_id: set.puzzle_ids[0],
+                schedule: Schedule {
+                    repetitions: 4,
+                    interval_days: 40,
+                    ease: 2.5,
+                },
+                due_at: Utc::now() + Duration::days(40),
+                reviewed_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+
+        // when it's solved slowly in the set:
+        let options = RecordAttemptOptions {
+            puzzle_id: set.puzzle_ids[0],
+            solved: true,
+            solve_time_ms: 90_000,
+        };
+        let result = service
+            .record_attempt(sample_user_id(), set.id, options)
+            .await
+            .unwrap();
+
+        // then its review starts over, due tomorrow:
+        let review = result.review.unwrap();
+        assert_eq!(review.id, known.id);
+        assert_eq!(review.schedule.interval_days, 1);
+        assert_eq!(
+            review.due_at,
+            result.attempt.attempted_at + Duration::days(1)
+        );
+        assert_eq!(
+            service
+                .review_repository
+                .find(sample_user_id(), known.id)
+                .await
+                .unwrap(),
+            Some(review)
+        );
+    }
+
+    fn sample_review(puzzle_id: PuzzleId, due_at: DateTime<Utc>) -> Review {
+        Review {
+            id: Uuid::new_v4(),
+            user_id: sample_user_id(),
+            puzzle_id,
+            schedule: Schedule {
+                repetitions: 1,
+                interval_days: 1,
+                ease: 2.5,
+            },
+            due_at,
+            reviewed_at: due_at - Duration::days(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_reschedule_due_review() {
+        // given reviews due yesterday and tomorrow:
+        let due = sample_review(1, Utc::now() - Duration::days(1));
+        let not_due = sample_review(2, Utc::now() + Duration::days(1));
+        let mut review_repository = MockReviewRepository::new();
+        let reviews = [due.clone(), not_due.clone()];
+        review_repository
+            .expect_find()
+            .returning(move |_, id| Ok(reviews.iter().find(|review| review.id == id).cloned()));
+
+        // and repository that saves reviews:
+        review_repository
+            .expect_save()
+            .withf(|review| review.puzzle_id == 1)
+            .returning(move |review| {
+                Ok(Review {
+                    id: due.id,
+                    user_id: review.user_id,
+                    puzzle_id: review.puzzle_id,
+                    schedule: review.schedule,
+                    due_at: review.due_at,
+                    reviewed_at: review.reviewed_at,
+                })
+            });
+
+        // when they're recalled, along with unknown one:
+        let service = make_service()
+            .review_repository(review_repository)
+            .build()
+            .unwrap();
+        let recall = || RecordReviewOptions {
+            solved: true,
+            solve_time_ms: 10_000,
+        };
+        let rescheduled = service
+            .record_review(sample_user_id(), due.id, recall())
+            .await;
+        let early = service
+            .record_review(sample_user_id(), not_due.id, recall())
+            .await;
+        let unknown = service
+            .record_review(sample_user_id(), Uuid::new_v4(), recall())
+            .await;
+
+        // then the due one is pushed back and others are rejected:
+        let rescheduled = rescheduled.unwrap();
+        assert_eq!(rescheduled.schedule.repetitions, 2);
+        assert_eq!(rescheduled.schedule.interval_days, 6);
+        assert_eq!(
+            rescheduled.due_at,
+            rescheduled.reviewed_at + Duration::days(6)
+        );
+        assert!(matches!(
+            early,
+            Err(RecordReviewError::NotDue { due_at }) if due_at == not_due.due_at
+        ));
+        assert!(matches!(unknown, Err(RecordReviewError::ReviewNotFound)));
+    }
+
+    #[tokio::test]
+    async fn should_merge_due_reviews_with_set_progress() {
+        // given two due reviews:
+        let reviews = vec![
+            sample_review(10, Utc::now() - Duration::days(2)),
+            sample_review(11, Utc::now() - Duration::days(1)),
+        ];
+        let mut review_repository = MockReviewRepository::new();
+        let due = reviews.clone();
+        review_repository
+            .expect_find_due()
+            .returning(move |_, _| Ok(due.clone()));
+
+        // and two sets, one of them in progress:
+        let set = |id, puzzle_ids: Vec<PuzzleId>, current_progress| TrainingSet {
+            id,
+            user_id: sample_user_id(),
+            puzzle_ids,
+            name: "set".to_string(),
+            rating: 1500..=1600,
+            themes: ThemeChoice::HealthyMix,
+            adaptive: None,
+            plan: None,
+            current_progress,
+            cycles_done: 0,
+        };
+        let first = set(Uuid::new_v4(), vec![0, 1, 2], 1);
+        let second = set(Uuid::new_v4(), vec![3, 4], 0);
+        let mut training_set_repository = MockTrainingSetRepository::new();
+        let sets = vec![first.clone(), second.clone()];
+        training_set_repository
+            .expect_find_by_user()
+            .returning(move |_| Ok(sets.clone()));
+
+        // when session of five is requested:
+        let service = make_service()
+            .training_set_repository(training_set_repository)
+            .review_repository(review_repository)
+            .build()
+            .unwrap();
+        let session = service.daily_session(sample_user_id(), 5).await.unwrap();
+
+        // then reviews come first, followed by the sets in turns:
+        let review = |review: &Review| SessionItem::Review {
+            review_id: review.id,
+            puzzle_id: review.puzzle_id,
+        };
+        let next = |set: &TrainingSet, position: u32| SessionItem::Set {
+            training_set_id: set.id,
+            puzzle_id: set.puzzle_ids[position as usize],
+            position,
+        };
+        assert_eq!(session.reviews_due, 2);
+        assert_eq!(
+            session.items,
+            vec![
+                review(&reviews[0]),
+                review(&reviews[1]),
+                next(&first, 1),
+                next(&second, 0),
+                next(&first, 2),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_track_set_against_plan() {
+        // given set of two puzzles with the first one attempted:
+        let (service, set) = make_attempt_service(2).await;
+        service
+            .record_attempt(sample_user_id(), set.id, attempt(set.puzzle_ids[0], true))
+            .await
+            .unwrap();
+
+        // when plan is set and its status is read:
+        let plan = CyclePlan {
+            cycle_days: vec![14, 7],
+        };
+        let saved = service
+            .set_plan(sample_user_id(), set.id, plan.clone())
+            .await
+            .unwrap();
+        let status = service.plan_status(sample_user_id(), set.id).await.unwrap();
+
+        // then the first cycle is under way, ahead of plan:
+        assert_eq!(saved.cycles, status.cycles);
+        assert_eq!(status.state, ScheduleState::Ahead);
+        assert_eq!(status.current_progress, 1);
+        let started_at = status.cycles[0].started_at.unwrap();
+        assert_eq!(
+            status.cycles[0].deadline,
+            Some(started_at + Duration::days(14))
+        );
+        assert_eq!(status.cycles[1].started_at, None);
+        assert_eq!(
+            service
+                .get_set(sample_user_id(), set.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .plan,
+            Some(plan)
+        );
+    }
+
+    #[tokio::test]
+    async fn should_disallow_invalid_plans() {
+        // given set without plan:
+        let (service, set) = make_attempt_service(2).await;
+        let plan = |cycle_days: Vec<u32>| CyclePlan { cycle_days };
+
+        // when plans are set, invalid or for unknown set, and status is read:
+        let empty = service
+            .set_plan(sample_user_id(), set.id, plan(vec![]))
+            .await;
+        let zero_days = service
+            .set_plan(sample_user_id(), set.id, plan(vec![7, 0]))
+            .await;
+        let unknown = service
+            .set_plan(sample_user_id(), Uuid::new_v4(), plan(vec![7]))
+            .await;
+        let missing = service.plan_status(sample_user_id(), set.id).await;
+
+        // then errors are returned:
+        assert!(matches!(
+            empty,
+            Err(CyclePlanError::CycleCountOutOfRange { max: 20 })
+        ));
+        assert!(matches!(
+            zero_days,
+            Err(CyclePlanError::CycleDaysOutOfRange { max: 365 })
+        ));
+        assert!(matches!(unknown, Err(CyclePlanError::SetNotFound)));
+        assert!(matches!(missing, Err(CyclePlanError::PlanNotFound)));
+    }
+
+    #[tokio::test]
+    async fn should_aggregate_statistics_of_set_and_user() {
+        // given set of two puzzles with the first one failed and the second one solved:
+        let (service, set) = make_attempt_service(2).await;
+        for (puzzle_id, solved) in [(set.puzzle_ids[0], false), (set.puzzle_ids[1], true)] {
+            service
+                .record_attempt(sample_user_id(), set.id, attempt(puzzle_id, solved))
+                .await
+                .unwrap();
+        }
+
+        // when statistics of the set, of the user and of unknown set are read:
+        let set_stats = service
+            .set_stats(sample_user_id(), set.id)
+            .await
+            .unwrap()
+            .unwrap();
+        let user_stats = service.user_stats(sample_user_id()).await.unwrap();
+        let unknown = service
+            .set_stats(sample_user_id(), Uuid::new_v4())
+            .await
+            .unwrap();
+
+        // then the attempts are aggregated with the failed puzzle first among the worst:
+        assert_eq!(set_stats.overall.attempts, 2);
+        assert_eq!(set_stats.overall.accuracy, 0.5);
+        assert_eq!(set_stats.cycles[0].performance.solved, 1);
+        let themes: Vec<_> = set_stats.themes.iter().map(|stats| stats.theme).collect();
+        assert_eq!(themes, [Theme::DiscoveredAttack, Theme::MateIn2]);
+        assert_eq!(set_stats.rating_bands[0].rating_min, 1500);
+        assert_eq!(set_stats.worst_puzzles[0].puzzle_id, set.puzzle_ids[0]);
+        assert_eq!(user_stats, set_stats);
+        assert_eq!(unknown, None);
+    }
+
+    #[tokio::test]
+    async fn should_not_suggest_set_without_enough_puzzles() {
+        // given five failed puzzles of the same themes, and nothing else to practise them on:
+        let (service, set) = make_attempt_service(5).await;
+        for puzzle_id in set.puzzle_ids.clone() {
+            service
+                .record_attempt(sample_user_id(), set.id, attempt(puzzle_id, false))
+                .await
+                .unwrap();
+        }
+
+        // when set is suggested:
+        let suggestion = service.suggest_set(sample_user_id()).await.unwrap();
+
+        // then weaknesses are reported without a set that couldn't be created:
+        assert!(!suggestion.weaknesses.is_empty());
+        assert_eq!(suggestion.options, None);
+    }
+
+    #[tokio::test]
+    async fn should_limit_user_wide_analysis_to_recent_attempts() {
+        // given repositories expecting a bounded number of attempts:
+        let mut attempt_repository = MockAttemptRepository::new();
+        attempt_repository
+            .expect_find_recent()
+            .withf(|_, limit| *limit == stats::RECENT_ATTEMPTS)
+            .times(1)
+            .returning(|_, _| Ok(vec![]));
+        attempt_repository
+            .expect_find_recent()
+            .withf(|_, limit| *limit == stats::RECENT_ATTEMPTS)
+            .times(1)
+            .returning(|_, _| Ok(vec![]));
+        attempt_repository
+            .expect_find_rating()
+            .returning(|_| Ok(None));
+        let mut puzzle_repository = MockPuzzleRepository::new();
+        puzzle_repository
+            .expect_find_by_ids()
+            .returning(|_| Ok(vec![]));
+
+        // when user statistics and weaknesses are requested:
+        let service = make_service()
+            .attempt_repository(attempt_repository)
+            .puzzle_repository(puzzle_repository)
+            .build()
+            .unwrap();
+        let statistics = service.user_stats(sample_user_id()).await.unwrap();
+        let suggestion = service.suggest_set(sample_user_id()).await.unwrap();
+
+        // then only the recent attempts are loaded:
+        assert_eq!(statistics.overall.attempts, 0);
+        assert_eq!(suggestion.options, None);
+    }
+
+    #[tokio::test]
+    async fn should_suggest_set_targeting_weak_themes() {
+        // given five failed puzzles of the same themes, and easier ones not seen yet:
+        let (service, set) = make_attempt_service(5).await;
+        let before = service.suggest_set(sample_user_id()).await.unwrap();
+        for puzzle_id in set.puzzle_ids.clone() {
+            service
+                .record_attempt(sample_user_id(), set.id, attempt(puzzle_id, false))
+                .await
+                .unwrap();
+        }
+        for i in 0..100 {
+            let lichess_puzzle = sample_lichess_puzzle()
+                .puzzle_id(format!("easier-{}", i))
+                .rating(500 + i * 10)
+                .themes(vec![Theme::DiscoveredAttack])
+                .build()
+                .unwrap();
+            service.import_puzzle(lichess_puzzle).await.unwrap();
+        }
+
+        // when set is suggested and the suggestion is accepted:
+        let suggestion = service.suggest_set(sample_user_id()).await.unwrap();
+        let options = suggestion.options.unwrap();
+        let created = service
+            .create_set(sample_user_id(), options.clone())
+            .await
+            .unwrap();
+
+        // then it's a set of easier puzzles of the weak themes:
+        assert_eq!(before.weaknesses, []);
+        assert_eq!(before.options, None);
+        let themes: Vec<_> = suggestion
+            .weaknesses
+            .iter()
+            .map(|weakness| weakness.theme)
+            .collect();
+        assert_eq!(themes, [Theme::DiscoveredAttack, Theme::MateIn2]);
+        assert_eq!(options.name, "Weaknesses: discoveredAttack, mateIn2");
+        assert_eq!(
+            options.themes,
+            ThemeChoice::Themes(vec![Theme::DiscoveredAttack, Theme::MateIn2])
+        );
+        assert!(options.rating.as_ref().unwrap().end() < &1500);
+        assert!(options.exclude_seen);
+        assert_eq!(created.puzzle_ids.len(), options.size);
+        assert!(created
+            .puzzle_ids
+            .iter()
+            .all(|puzzle_id| !set.puzzle_ids.contains(puzzle_id)));
+    }
 }
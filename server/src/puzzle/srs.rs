@@ -0,0 +1,157 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::puzzle::difficulty::SLOW_SOLVE_MS;
+use crate::puzzle::types::Schedule;
+
+const FAILED: u8 = 1;
+const SLOW: u8 = 3;
+const PERFECT: u8 = 5;
+const MIN_EASE: f64 = 1.3;
+
+pub fn quality(solved: bool, solve_time_ms: u32) -> u8 {
+    match solved {
+        false => FAILED,
+        true if solve_time_ms > SLOW_SOLVE_MS => SLOW,
+        true => PERFECT,
+    }
+}
+
+pub fn needs_review(quality: u8) -> bool {
+    quality < PERFECT
+}
+
+pub fn next(schedule: &Schedule, quality: u8) -> Schedule {
+    if quality < SLOW {
+        return Schedule {
+            repetitions: 0,
+            interval_days: 1,
+            ..*schedule
+        };
+    }
+
+    let repetitions = schedule.repetitions + 1;
+    let interval_days = match repetitions {
+        1 => 1,
+        2 => 6,
+        _ => (f64::from(schedule.interval_days) * schedule.ease).round() as u32,
+    };
+    let lapse = f64::from(PERFECT - quality);
+    Schedule {
+        repetitions,
+        interval_days,
+        ease: (schedule.ease + 0.1 - lapse * (0.08 + lapse * 0.02)).max(MIN_EASE),
+    }
+}
+
+// Set attempts aren't reviews: a puzzle missed in a set starts its schedule over instead of
+// building on one it may already have.
+pub fn restart(quality: u8) -> Schedule {
+    next(&Schedule::default(), quality)
+}
+
+pub fn due_at(reviewed_at: DateTime<Utc>, schedule: &Schedule) -> DateTime<Utc> {
+    reviewed_at + Duration::days(i64::from(schedule.interval_days))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::puzzle::srs::{due_at, needs_review, next, quality, restart};
+    use crate::puzzle::types::Schedule;
+
+    #[test]
+    fn should_grade_failed_and_slow_solves_for_review() {
+        assert_eq!(quality(false, 5_000), 1);
+        assert_eq!(quality(true, 90_000), 3);
+        assert_eq!(quality(true, 5_000), 5);
+        assert!(needs_review(quality(false, 5_000)));
+        assert!(needs_review(quality(true, 90_000)));
+        assert!(!needs_review(quality(true, 5_000)));
+    }
+
+    #[test]
+    fn should_grow_intervals_on_recall() {
+        // when new review is recalled repeatedly:
+        let first = next(&Schedule::default(), 5);
+        let second = next(&first, 5);
+        let third = next(&second, 5);
+
+        // then intervals follow SM-2 and ease grows:
+        assert_eq!(
+            [first, second, third].map(|schedule| schedule.interval_days),
+            [1, 6, 16]
+        );
+        assert_eq!(third.repetitions, 3);
+        assert!(third.ease > 2.5);
+    }
+
+    #[test]
+    fn should_restart_on_lapse_and_lower_ease_on_slow_recall() {
+        // given well-known review:
+        let known = Schedule {
+            repetitions: 4,
+            interval_days: 40,
+            ease: 2.5,
+        };
+
+        // when it's failed or recalled slowly:
+        let failed = next(&known, 1);
+        let slow = next(&known, 3);
+
+        // then failure restarts it and slow recall makes it harder:
+        assert_eq!(
+            failed,
+            Schedule {
+                repetitions: 0,
+                interval_days: 1,
+                ease: 2.5
+            }
+        );
+        assert_eq!(slow.interval_days, 100);
+        assert!((slow.ease - 2.36).abs() < 0.000001);
+    }
+
+    #[test]
+    fn should_restart_regardless_of_schedule() {
+        // when set puzzles are failed or solved slowly:
+        let failed = restart(1);
+        let slow = restart(3);
+
+        // then they're scheduled like new reviews, for tomorrow:
+        assert_eq!(failed, next(&Schedule::default(), 1));
+        assert_eq!(slow, next(&Schedule::default(), 3));
+        assert_eq!([failed.interval_days, slow.interval_days], [1, 1]);
+    }
+
+    #[test]
+    fn should_not_drop_ease_below_minimum() {
+        // given review of minimal ease:
+        let hard = Schedule {
+            repetitions: 2,
+            interval_days: 6,
+            ease: 1.3,
+        };
+
+        // when it's recalled slowly:
+        let slow = next(&hard, 3);
+
+        // then ease stays at minimum:
+        assert_eq!(slow.ease, 1.3);
+        assert_eq!(slow.interval_days, 8);
+    }
+
+    #[test]
+    fn should_be_due_after_interval() {
+        let reviewed_at = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let schedule = Schedule {
+            repetitions: 2,
+            interval_days: 6,
+            ease: 2.5,
+        };
+        assert_eq!(
+            due_at(reviewed_at, &schedule),
+            Utc.with_ymd_and_hms(2024, 1, 7, 12, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::puzzle::types::{
+    Attempt, CycleStats, Performance, Puzzle, PuzzleId, PuzzleStats, RatingBandStats, Statistics,
+    Theme, ThemeStats,
+};
+
+pub const RATING_BAND_WIDTH: u16 = 100;
+pub const RECENT_ATTEMPTS: usize = 1000;
+pub const WORST_PUZZLES: usize = 10;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tally {
+    attempts: u32,
+    solved: u32,
+    solve_time_ms: u64,
+}
+
+impl Tally {
+    pub fn add(&mut self, attempt: &Attempt) {
+        self.attempts += 1;
+        self.solved += u32::from(attempt.solved);
+        self.solve_time_ms += u64::from(attempt.solve_time_ms);
+    }
+
+    pub fn performance(&self) -> Performance {
+        if self.attempts == 0 {
+            return Performance {
+                attempts: 0,
+                solved: 0,
+                accuracy: 0.0,
+                average_solve_time_ms: 0,
+            };
+        }
+        Performance {
+            attempts: self.attempts,
+            solved: self.solved,
+            accuracy: f64::from(self.solved) / f64::from(self.attempts),
+            average_solve_time_ms: (self.solve_time_ms / u64::from(self.attempts)) as u32,
+        }
+    }
+}
+
+pub fn aggregate(attempts: &[Attempt], puzzles: &[Puzzle]) -> Statistics {
+    let puzzles: HashMap<PuzzleId, &Puzzle> =
+        puzzles.iter().map(|puzzle| (puzzle.id, puzzle)).collect();
+    let mut overall = Tally::default();
+    let mut cycles: BTreeMap<u32, Tally> = BTreeMap::new();
+    let mut themes: HashMap<Theme, Tally> = HashMap::new();
+    let mut bands: BTreeMap<u16, Tally> = BTreeMap::new();
+    let mut by_puzzle: HashMap<PuzzleId, Tally> = HashMap::new();
+    for attempt in attempts {
+        overall.add(attempt);
+        cycles.entry(attempt.cycle).or_default().add(attempt);
+        let Some(puzzle) = puzzles.get(&attempt.puzzle_id) else {
+            continue;
+        };
+        for theme in &puzzle.themes {
+            themes.entry(*theme).or_default().add(attempt);
+        }
+        bands
+            .entry(band_start(puzzle.lichess_rating))
+            .or_default()
+            .add(attempt);
+        by_puzzle.entry(puzzle.id).or_default().add(attempt);
+    }
+
+    let mut themes: Vec<ThemeStats> = themes
+        .into_iter()
+        .map(|(theme, tally)| ThemeStats {
+            theme,
+            performance: tally.performance(),
+        })
+        .collect();
+    themes.sort_by(|a, b| {
+        b.performance
+            .attempts
+            .cmp(&a.performance.attempts)
+            .then_with(|| a.theme.to_string().cmp(&b.theme.to_string()))
+    });
+
+    let mut worst_puzzles: Vec<PuzzleStats> = by_puzzle
+        .into_iter()
+        .map(|(puzzle_id, tally)| PuzzleStats {
+            puzzle_id,
+            lichess_id: puzzles[&puzzle_id].lichess_id.clone(),
+            rating: puzzles[&puzzle_id].lichess_rating,
+            performance: tally.performance(),
+        })
+        .collect();
+    worst_puzzles.sort_by(|a, b| {
+        a.performance
+            .accuracy
+            .total_cmp(&b.performance.accuracy)
+            .then_with(|| {
+                b.performance
+                    .average_solve_time_ms
+                    .cmp(&a.performance.average_solve_time_ms)
+            })
+            .then_with(|| a.puzzle_id.cmp(&b.puzzle_id))
+    });
+    worst_puzzles.truncate(WORST_PUZZLES);
+
+    Statistics {
+        overall: overall.performance(),
+        cycles: cycles
+            .into_iter()
+            .map(|(cycle, tally)| CycleStats {
+                cycle,
+                performance: tally.performance(),
+            })
+            .collect(),
+        themes,
+        rating_bands: bands
+            .into_iter()
+            .map(|(rating_min, tally)| RatingBandStats {
+                rating_min,
+                rating_max: rating_min.saturating_add(RATING_BAND_WIDTH - 1),
+                performance: tally.performance(),
+            })
+            .collect(),
+        worst_puzzles,
+    }
+}
+
+fn band_start(rating: u16) -> u16 {
+    rating / RATING_BAND_WIDTH * RATING_BAND_WIDTH
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::stats::aggregate;
+    use crate::puzzle::test_fixtures::{attempt, puzzle};
+    use crate::puzzle::types::{Performance, Puzzle, Theme};
+
+    fn performance(attempts: u32, solved: u32, average_solve_time_ms: u32) -> Performance {
+        Performance {
+            attempts,
+            solved,
+            accuracy: f64::from(solved) / f64::from(attempts),
+            average_solve_time_ms,
+        }
+    }
+
+    fn sample_puzzles() -> Vec<Puzzle> {
+        vec![
+            puzzle(1, 1450, vec![Theme::Fork, Theme::Short]),
+            puzzle(2, 1520, vec![Theme::Pin]),
+            puzzle(3, 1580, vec![Theme::Fork]),
+        ]
+    }
+
+    #[test]
+    fn should_aggregate_by_cycle() {
+        // given two cycles through the puzzles, the second one faster:
+        let attempts = [
+            attempt(1, 0, false, 30_000),
+            attempt(2, 0, true, 20_000),
+            attempt(3, 0, true, 40_000),
+            attempt(1, 1, true, 10_000),
+            attempt(2, 1, true, 10_000),
+            attempt(3, 1, true, 10_000),
+        ];
+
+        // when statistics are aggregated:
+        let statistics = aggregate(&attempts, &sample_puzzles());
+
+        // then each cycle has its own accuracy and average time:
+        assert_eq!(statistics.overall, performance(6, 5, 20_000));
+        assert_eq!(statistics.cycles.len(), 2);
+        assert_eq!(statistics.cycles[0].performance, performance(3, 2, 30_000));
+        assert_eq!(statistics.cycles[1].performance, performance(3, 3, 10_000));
+    }
+
+    #[test]
+    fn should_aggregate_by_theme_and_rating_band() {
+        // given attempts at puzzles of different themes and ratings:
+        let attempts = [
+            attempt(1, 0, false, 30_000),
+            attempt(2, 0, true, 20_000),
+            attempt(3, 0, true, 40_000),
+        ];
+
+        // when statistics are aggregated:
+        let statistics = aggregate(&attempts, &sample_puzzles());
+
+        // then themes are ordered by attempts and then name:
+        let themes: Vec<_> = statistics
+            .themes
+            .iter()
+            .map(|stats| (stats.theme, stats.performance))
+            .collect();
+        assert_eq!(
+            themes,
+            [
+                (Theme::Fork, performance(2, 1, 35_000)),
+                (Theme::Pin, performance(1, 1, 20_000)),
+                (Theme::Short, performance(1, 0, 30_000)),
+            ]
+        );
+
+        // and rating bands are ordered by rating:
+        let bands: Vec<_> = statistics
+            .rating_bands
+            .iter()
+            .map(|stats| (stats.rating_min, stats.rating_max, stats.performance))
+            .collect();
+        assert_eq!(
+            bands,
+            [
+                (1400, 1499, performance(1, 0, 30_000)),
+                (1500, 1599, performance(2, 2, 30_000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_rank_worst_puzzles() {
+        // given puzzles failed or solved slowly to a varying degree:
+        let attempts = [
+            attempt(1, 0, true, 10_000),
+            attempt(2, 0, false, 20_000),
+            attempt(3, 0, true, 50_000),
+            attempt(1, 1, true, 10_000),
+            attempt(2, 1, true, 20_000),
+            attempt(3, 1, true, 50_000),
+        ];
+
+        // when statistics are aggregated:
+        let statistics = aggregate(&attempts, &sample_puzzles());
+
+        // then the least accurate and then the slowest come first:
+        let worst: Vec<_> = statistics
+            .worst_puzzles
+            .iter()
+            .map(|stats| (stats.puzzle_id, stats.lichess_id.as_str(), stats.rating))
+            .collect();
+        assert_eq!(
+            worst,
+            [
+                (2, "puzzle-2", 1520),
+                (3, "puzzle-3", 1580),
+                (1, "puzzle-1", 1450)
+            ]
+        );
+    }
+
+    #[test]
+    fn should_aggregate_no_attempts() {
+        // when statistics are aggregated without attempts:
+        let statistics = aggregate(&[], &[]);
+
+        // then they're empty:
+        assert_eq!(statistics.overall.attempts, 0);
+        assert_eq!(statistics.overall.accuracy, 0.0);
+        assert!(statistics.cycles.is_empty());
+        assert!(statistics.themes.is_empty());
+        assert!(statistics.worst_puzzles.is_empty());
+    }
+}
@@ -0,0 +1,35 @@
+//! Puzzles and attempts shared by the analysis tests.
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::puzzle::types::{Attempt, Puzzle, PuzzleBuilder, PuzzleId, Theme};
+
+pub fn puzzle(id: PuzzleId, rating: u16, themes: Vec<Theme>) -> Puzzle {
+    PuzzleBuilder::default()
+        .id(id)
+        .fen("fen".to_string())
+        .moves("e2e4".to_string())
+        .lichess_id(format!("puzzle-{}", id))
+        .lichess_rating(rating)
+        .lichess_rating_deviation(80)
+        .lichess_popularity(90)
+        .lichess_play_count(100)
+        .themes(themes)
+        .lichess_game_url("https://lichess.org/game".to_string())
+        .build()
+        .unwrap()
+}
+
+pub fn attempt(puzzle_id: PuzzleId, cycle: u32, solved: bool, solve_time_ms: u32) -> Attempt {
+    Attempt {
+        id: Uuid::new_v4(),
+        user_id: Uuid::nil(),
+        training_set_id: Uuid::nil(),
+        puzzle_id,
+        cycle,
+        solved,
+        solve_time_ms,
+        attempted_at: Utc::now(),
+    }
+}
@@ -1,25 +1,180 @@
+use std::collections::HashSet;
 use std::ops::RangeInclusive;
+use std::sync::Arc;
 
-use crate::puzzle::types::{PuzzleId, ThemeChoice, TrainingSet};
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::puzzle::types::{
+    AdaptiveDifficulty, CyclePlan, PuzzleId, ThemeChoice, TrainingSet, TrainingSetId,
+};
+use crate::user::types::UserId;
 
 #[cfg_attr(test, mockall::automock)]
-pub trait TrainingSetRepository {
-    fn create(&self, training_set: CreateTrainingSet) -> anyhow::Result<TrainingSet>;
+#[async_trait]
+pub trait TrainingSetRepository: Send + Sync {
+    async fn create(&self, training_set: CreateTrainingSet) -> anyhow::Result<TrainingSet>;
+    async fn find_puzzle_ids(&self, user_id: UserId) -> anyhow::Result<HashSet<PuzzleId>>;
+    async fn find_by_user(&self, user_id: UserId) -> anyhow::Result<Vec<TrainingSet>>;
+    async fn find(&self, user_id: UserId, id: TrainingSetId)
+        -> anyhow::Result<Option<TrainingSet>>;
+    async fn update_plan(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+        plan: CyclePlan,
+    ) -> anyhow::Result<bool>;
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub current_progress: u32,
+    pub cycles_done: u32,
+}
+
+impl Progress {
+    pub fn of(training_set: &TrainingSet) -> Progress {
+        Progress {
+            current_progress: training_set.current_progress,
+            cycles_done: training_set.cycles_done,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub struct CreateTrainingSet {
+    pub user_id: UserId,
     pub puzzle_ids: Vec<PuzzleId>,
     pub name: String,
     pub rating: RangeInclusive<u16>,
     pub themes: ThemeChoice,
+    pub adaptive: Option<AdaptiveDifficulty>,
     pub current_progress: u32,
     pub cycles_done: u32,
 }
 
-pub struct DummyTrainingSetRepository;
+#[derive(Default, Clone)]
+pub struct InMemoryTrainingSetRepository {
+    sets: Arc<RwLock<Vec<TrainingSet>>>,
+}
+
+impl InMemoryTrainingSetRepository {
+    pub fn new() -> InMemoryTrainingSetRepository {
+        InMemoryTrainingSetRepository::default()
+    }
+
+    pub(crate) fn compare_and_set_progress(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+        from: Progress,
+        to: Progress,
+    ) -> bool {
+        let mut sets = self.sets.write();
+        let set = sets
+            .iter_mut()
+            .find(|set| set.user_id == user_id && set.id == id && Progress::of(set) == from);
+        match set {
+            Some(set) => {
+                set.current_progress = to.current_progress;
+                set.cycles_done = to.cycles_done;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[async_trait]
+impl TrainingSetRepository for InMemoryTrainingSetRepository {
+    #[instrument(level = "debug", skip_all, fields(size = training_set.puzzle_ids.len()))]
+    async fn create(&self, training_set: CreateTrainingSet) -> anyhow::Result<TrainingSet> {
+        let training_set = TrainingSet {
+            id: Uuid::new_v4(),
+            user_id: training_set.user_id,
+            puzzle_ids: training_set.puzzle_ids,
+            name: training_set.name,
+            rating: training_set.rating,
+            themes: training_set.themes,
+            adaptive: training_set.adaptive,
+            plan: None,
+            current_progress: training_set.current_progress,
+            cycles_done: training_set.cycles_done,
+        };
+        self.sets.write().push(training_set.clone());
+        Ok(training_set)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_puzzle_ids(&self, user_id: UserId) -> anyhow::Result<HashSet<PuzzleId>> {
+        Ok(self
+            .sets
+            .read()
+            .iter()
+            .filter(|set| set.user_id == user_id)
+            .flat_map(|set| set.puzzle_ids.iter().copied())
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_user(&self, user_id: UserId) -> anyhow::Result<Vec<TrainingSet>> {
+        Ok(self
+            .sets
+            .read()
+            .iter()
+            .filter(|set| set.user_id == user_id)
+            .cloned()
+            .collect())
+    }
 
-impl TrainingSetRepository for DummyTrainingSetRepository {
-    fn create(&self, _training_set: CreateTrainingSet) -> anyhow::Result<TrainingSet> {
-        unimplemented!()
+    #[instrument(level = "debug", skip(self))]
+    async fn find(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+    ) -> anyhow::Result<Option<TrainingSet>> {
+        Ok(self
+            .sets
+            .read()
+            .iter()
+            .find(|set| set.user_id == user_id && set.id == id)
+            .cloned())
     }
+
+    #[instrument(level = "debug", skip(self, plan))]
+    async fn update_plan(
+        &self,
+        user_id: UserId,
+        id: TrainingSetId,
+        plan: CyclePlan,
+    ) -> anyhow::Result<bool> {
+        let mut sets = self.sets.write();
+        let set = sets
+            .iter_mut()
+            .find(|set| set.user_id == user_id && set.id == id);
+        Ok(match set {
+            Some(set) => {
+                set.plan = Some(plan);
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle::puzzle_repository::InMemoryPuzzleRepository;
+    use crate::puzzle::repository_contract::training_set_repository_contract_tests;
+    use crate::user::user_repository::InMemoryUserRepository;
+
+    use super::InMemoryTrainingSetRepository;
+
+    training_set_repository_contract_tests!((
+        InMemoryPuzzleRepository::new(),
+        InMemoryTrainingSetRepository::new(),
+        InMemoryUserRepository::new()
+    ));
 }
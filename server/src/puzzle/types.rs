@@ -1,19 +1,24 @@
 use std::ops::RangeInclusive;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_with::formats::SpaceSeparator;
 use serde_with::serde_as;
 use serde_with::StringWithSeparator;
-use strum::{Display as EnumDisplay, EnumString};
+use strum::{Display as EnumDisplay, EnumCount, EnumString};
+use utoipa::openapi::{ObjectBuilder, RefOr, Schema, SchemaType};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::puzzle::puzzle_repository::CreatePuzzle;
+use crate::user::types::UserId;
 
 pub type PuzzleId = u64;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[cfg_attr(test, derive(derive_builder::Builder))]
 pub struct Puzzle {
+    #[schema(value_type = u64)]
     pub id: PuzzleId,
     pub fen: String,
     pub moves: String,
@@ -26,7 +31,20 @@ pub struct Puzzle {
     pub lichess_game_url: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, EnumDisplay, EnumString)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    EnumDisplay,
+    EnumString,
+    EnumCount,
+    ToSchema,
+)]
 #[serde(rename_all = "camelCase")]
 #[strum(serialize_all = "camelCase")]
 pub enum Theme {
@@ -94,12 +112,21 @@ pub enum Theme {
     Zugzwang,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum ThemeChoice {
     Themes(Vec<Theme>),
     HealthyMix,
 }
 
+impl ThemeChoice {
+    pub fn matches(&self, puzzle_themes: &[Theme]) -> bool {
+        match self {
+            ThemeChoice::Themes(themes) => themes.iter().any(|theme| puzzle_themes.contains(theme)),
+            ThemeChoice::HealthyMix => true,
+        }
+    }
+}
+
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -137,22 +164,342 @@ impl From<LichessPuzzleImport> for CreatePuzzle {
 
 pub type TrainingSetId = Uuid;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub struct TrainingSet {
+    #[schema(value_type = Uuid)]
     pub id: TrainingSetId,
+    #[schema(value_type = Uuid)]
+    pub user_id: UserId,
+    #[schema(value_type = Vec<u64>)]
     pub puzzle_ids: Vec<PuzzleId>,
     pub name: String,
+    #[schema(schema_with = rating_range)]
     pub rating: RangeInclusive<u16>,
     pub themes: ThemeChoice,
+    pub adaptive: Option<AdaptiveDifficulty>,
+    pub plan: Option<CyclePlan>,
     pub current_progress: u32,
     pub cycles_done: u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CyclePlan {
+    #[schema(example = json!([28, 14, 7, 4, 2, 1]))]
+    pub cycle_days: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AdaptiveDifficulty {
+    #[schema(minimum = 50, maximum = 95, example = 75)]
+    pub target_success_percent: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[cfg_attr(test, derive(derive_builder::Builder))]
 pub struct CreateTrainingSetOptions {
     pub name: String,
+    pub size: usize,
+    #[serde(default)]
+    #[cfg_attr(test, builder(setter(strip_option)))]
+    #[schema(schema_with = optional_rating_range)]
+    pub rating: Option<RangeInclusive<u16>>,
+    #[serde(default)]
+    #[cfg_attr(test, builder(default, setter(strip_option)))]
+    pub adaptive: Option<AdaptiveDifficulty>,
+    pub themes: ThemeChoice,
+    #[serde(default)]
+    pub exclude_seen: bool,
+    /// Leaves out puzzles the user attempted within this many days.
+    #[serde(default)]
+    #[cfg_attr(test, builder(default, setter(strip_option)))]
+    pub exclude_attempted_days: Option<u32>,
+}
+
+fn rating_range() -> impl Into<RefOr<Schema>> {
+    rating_range_builder()
+}
+
+fn optional_rating_range() -> impl Into<RefOr<Schema>> {
+    rating_range_builder().nullable(true).description(Some(
+        "Defaults to the range suggested for the user's rating. Can't be combined with `adaptive`.",
+    ))
+}
+
+fn rating_range_builder() -> ObjectBuilder {
+    let bound = || {
+        ObjectBuilder::new()
+            .schema_type(SchemaType::Integer)
+            .minimum(Some(0.0))
+            .maximum(Some(u16::MAX as f64))
+    };
+    ObjectBuilder::new()
+        .property("start", bound())
+        .required("start")
+        .property("end", bound())
+        .required("end")
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(test, derive(derive_builder::Builder))]
+pub struct PreviewTrainingSetOptions {
     pub size: usize,
     pub rating: RangeInclusive<u16>,
     pub themes: ThemeChoice,
+    pub exclude_seen: bool,
+    #[cfg_attr(test, builder(default))]
+    pub exclude_attempted_days: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumDisplay, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "camelCase")]
+pub enum CriteriaFilter {
+    Rating,
+    Themes,
+    ExcludeSeen,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TrainingSetPreview {
+    pub requested: usize,
+    pub available: usize,
+    pub matching_rating: usize,
+    pub matching_themes: usize,
+    pub matching_unseen: Option<usize>,
+    pub most_restrictive: CriteriaFilter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Rating {
+    #[schema(example = 1500.0)]
+    pub rating: f64,
+    #[schema(example = 350.0)]
+    pub deviation: f64,
+    #[schema(example = 0.06)]
+    pub volatility: f64,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            rating: 1500.0,
+            deviation: 350.0,
+            volatility: 0.06,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RatingSummary {
+    #[serde(flatten)]
+    pub rating: Rating,
+    #[schema(schema_with = rating_range)]
+    pub suggested_range: RangeInclusive<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RatingHistoryEntry {
+    #[serde(flatten)]
+    pub rating: Rating,
+    #[schema(value_type = Uuid)]
+    pub attempt_id: AttemptId,
+    pub rated_at: DateTime<Utc>,
+}
+
+pub type AttemptId = Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Attempt {
+    #[schema(value_type = Uuid)]
+    pub id: AttemptId,
+    #[schema(value_type = Uuid)]
+    pub user_id: UserId,
+    #[schema(value_type = Uuid)]
+    pub training_set_id: TrainingSetId,
+    #[schema(value_type = u64)]
+    pub puzzle_id: PuzzleId,
+    pub cycle: u32,
+    pub solved: bool,
+    pub solve_time_ms: u32,
+    pub attempted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct RecordAttemptOptions {
+    #[schema(value_type = u64)]
+    pub puzzle_id: PuzzleId,
+    pub solved: bool,
+    #[schema(example = 42000)]
+    pub solve_time_ms: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleState {
+    NotStarted,
+    Ahead,
+    Behind,
+    Overdue,
+    Completed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CycleSchedule {
+    pub cycle: u32,
+    pub target_days: u32,
+    pub started_at: Option<DateTime<Utc>>,
+    pub deadline: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub on_time: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PlanStatus {
+    pub state: ScheduleState,
+    pub current_cycle: u32,
+    pub current_progress: u32,
+    pub expected_progress: Option<u32>,
+    pub puzzles_per_day: Option<f64>,
+    pub cycles: Vec<CycleSchedule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AttemptResult {
+    pub attempt: Attempt,
+    pub training_set: TrainingSet,
+    pub rating: Rating,
+    pub review: Option<Review>,
+}
+
+pub type ReviewId = Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Schedule {
+    pub repetitions: u32,
+    pub interval_days: u32,
+    #[schema(example = 2.5)]
+    pub ease: f64,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            repetitions: 0,
+            interval_days: 0,
+            ease: 2.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Review {
+    #[schema(value_type = Uuid)]
+    pub id: ReviewId,
+    #[schema(value_type = Uuid)]
+    pub user_id: UserId,
+    #[schema(value_type = u64)]
+    pub puzzle_id: PuzzleId,
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    pub due_at: DateTime<Utc>,
+    pub reviewed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct RecordReviewOptions {
+    pub solved: bool,
+    #[schema(example = 42000)]
+    pub solve_time_ms: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionItem {
+    Review {
+        #[schema(value_type = Uuid)]
+        review_id: ReviewId,
+        #[schema(value_type = u64)]
+        puzzle_id: PuzzleId,
+    },
+    Set {
+        #[schema(value_type = Uuid)]
+        training_set_id: TrainingSetId,
+        #[schema(value_type = u64)]
+        puzzle_id: PuzzleId,
+        position: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DailySession {
+    pub reviews_due: usize,
+    pub items: Vec<SessionItem>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Performance {
+    pub attempts: u32,
+    pub solved: u32,
+    #[schema(example = 0.75)]
+    pub accuracy: f64,
+    #[schema(example = 42000)]
+    pub average_solve_time_ms: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CycleStats {
+    pub cycle: u32,
+    #[serde(flatten)]
+    pub performance: Performance,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ThemeStats {
+    pub theme: Theme,
+    #[serde(flatten)]
+    pub performance: Performance,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RatingBandStats {
+    pub rating_min: u16,
+    pub rating_max: u16,
+    #[serde(flatten)]
+    pub performance: Performance,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PuzzleStats {
+    #[schema(value_type = u64)]
+    pub puzzle_id: PuzzleId,
+    pub lichess_id: String,
+    pub rating: u16,
+    #[serde(flatten)]
+    pub performance: Performance,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Statistics {
+    pub overall: Performance,
+    pub cycles: Vec<CycleStats>,
+    pub themes: Vec<ThemeStats>,
+    pub rating_bands: Vec<RatingBandStats>,
+    pub worst_puzzles: Vec<PuzzleStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ThemeWeakness {
+    pub theme: Theme,
+    #[serde(flatten)]
+    pub performance: Performance,
+    #[schema(example = 0.7)]
+    pub expected_score: f64,
+    #[schema(example = 0.45)]
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SetSuggestion {
+    pub weaknesses: Vec<ThemeWeakness>,
+    pub options: Option<CreateTrainingSetOptions>,
 }
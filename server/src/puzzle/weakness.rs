@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use crate::puzzle::difficulty;
+use crate::puzzle::glicko;
+use crate::puzzle::stats::Tally;
+use crate::puzzle::types::{Attempt, Puzzle, PuzzleId, Rating, Theme, ThemeWeakness};
+
+pub const MIN_ATTEMPTS: u32 = 5;
+pub const MIN_SCORE_GAP: f64 = 0.1;
+pub const SUGGESTED_THEMES: usize = 3;
+pub const SUGGESTED_SIZE: usize = 50;
+pub const TARGET_SUCCESS_PERCENT: u8 = 75;
+
+const NON_MOTIF_THEMES: [Theme; 10] = [
+    Theme::Advantage,
+    Theme::Crushing,
+    Theme::Equality,
+    Theme::Long,
+    Theme::Master,
+    Theme::MasterVsMaster,
+    Theme::OneMove,
+    Theme::Short,
+    Theme::SuperGM,
+    Theme::VeryLong,
+];
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ThemeTally {
+    tally: Tally,
+    expected_score: f64,
+    score: f64,
+}
+
+pub fn detect(
+    rating: &Rating,
+    attempts: &[Attempt],
+    puzzles: &HashMap<PuzzleId, Puzzle>,
+) -> Vec<ThemeWeakness> {
+    let mut themes: HashMap<Theme, ThemeTally> = HashMap::new();
+    for attempt in attempts {
+        let Some(puzzle) = puzzles.get(&attempt.puzzle_id) else {
+            continue;
+        };
+        let expected_score = glicko::expected_score(rating, f64::from(puzzle.lichess_rating));
+        let score = difficulty::score(attempt.solved, attempt.solve_time_ms);
+        for theme in &puzzle.themes {
+            if NON_MOTIF_THEMES.contains(theme) {
+                continue;
+            }
+            let entry = themes.entry(*theme).or_default();
+            entry.tally.add(attempt);
+            entry.expected_score += expected_score;
+            entry.score += score;
+        }
+    }
+
+    let mut weaknesses: Vec<ThemeWeakness> = themes
+        .into_iter()
+        .map(|(theme, tally)| {
+            let performance = tally.tally.performance();
+            let attempts = f64::from(performance.attempts);
+            ThemeWeakness {
+                theme,
+                performance,
+                expected_score: tally.expected_score / attempts,
+                score: tally.score / attempts,
+            }
+        })
+        .filter(|weakness| {
+            weakness.performance.attempts >= MIN_ATTEMPTS
+                && weakness.expected_score - weakness.score >= MIN_SCORE_GAP
+        })
+        .collect();
+    weaknesses.sort_by(|a, b| {
+        (b.expected_score - b.score)
+            .total_cmp(&(a.expected_score - a.score))
+            .then_with(|| a.theme.to_string().cmp(&b.theme.to_string()))
+    });
+    weaknesses
+}
+
+pub fn target_range(
+    rating: &Rating,
+    attempts: &[Attempt],
+    puzzles: &HashMap<PuzzleId, Puzzle>,
+    themes: &[Theme],
+) -> RangeInclusive<u16> {
+    let outcomes: Vec<glicko::Outcome> = attempts
+        .iter()
+        .filter_map(|attempt| {
+            puzzles
+                .get(&attempt.puzzle_id)
+                .map(|puzzle| (attempt, puzzle))
+        })
+        .filter(|(_, puzzle)| themes.iter().any(|theme| puzzle.themes.contains(theme)))
+        .map(|(attempt, puzzle)| difficulty::outcome(puzzle, attempt.solved, attempt.solve_time_ms))
+        .collect();
+    difficulty::adaptive_range(rating, &outcomes, TARGET_SUCCESS_PERCENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::puzzle::test_fixtures::{attempt, puzzle};
+    use crate::puzzle::types::{Attempt, Puzzle, PuzzleId, Rating, Theme};
+    use crate::puzzle::weakness::{detect, target_range};
+
+    fn settled(rating: f64) -> Rating {
+        Rating {
+            rating,
+            deviation: 60.0,
+            volatility: 0.06,
+        }
+    }
+
+    fn sample_puzzles() -> HashMap<PuzzleId, Puzzle> {
+        (0..30)
+            .map(|id| {
+                let themes = match id / 10 {
+                    0 => vec![Theme::Fork, Theme::Short],
+                    1 => vec![Theme::Deflection],
+                    _ => vec![Theme::Interference, Theme::Deflection],
+                };
+                (id, puzzle(id, 1300 + id as u16 * 10, themes))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_detect_themes_below_expected_score() {
+        // given forks solved quickly, half of deflections failed and interferences solved slowly:
+        let attempts: Vec<Attempt> = (0..30)
+            .map(|id| match id / 10 {
+                0 => attempt(id, 0, true, 10_000),
+                1 => attempt(id, 0, id % 2 == 0, 10_000),
+                _ => attempt(id, 0, true, 90_000),
+            })
+            .collect();
+
+        // when weaknesses of a 1700 player are detected:
+        let weaknesses = detect(&settled(1700.0), &attempts, &sample_puzzles());
+
+        // then themes scoring furthest below expectations come first:
+        let themes: Vec<_> = weaknesses.iter().map(|weakness| weakness.theme).collect();
+        assert_eq!(themes, [Theme::Deflection, Theme::Interference]);
+        assert_eq!(weaknesses[0].performance.attempts, 20);
+        assert_eq!(weaknesses[0].performance.accuracy, 0.75);
+        assert_eq!(weaknesses[0].score, 0.5);
+        assert!(weaknesses[0].expected_score > 0.7);
+        assert_eq!(weaknesses[1].performance.accuracy, 1.0);
+        assert_eq!(weaknesses[1].score, 0.5);
+    }
+
+    #[test]
+    fn should_require_enough_attempts() {
+        // given a few failed deflections:
+        let attempts: Vec<Attempt> = (10..14).map(|id| attempt(id, 0, false, 10_000)).collect();
+
+        // when weaknesses are detected:
+        let weaknesses = detect(&settled(1600.0), &attempts, &sample_puzzles());
+
+        // then there's not enough evidence:
+        assert!(weaknesses.is_empty());
+    }
+
+    #[test]
+    fn should_target_easier_puzzles_of_weak_themes() {
+        // given failed deflections and solved forks:
+        let attempts: Vec<Attempt> = (0..20).map(|id| attempt(id, 0, id < 10, 10_000)).collect();
+        let puzzles = sample_puzzles();
+
+        // when target ranges are found for each theme:
+        let deflection = target_range(&settled(1600.0), &attempts, &puzzles, &[Theme::Deflection]);
+        let fork = target_range(&settled(1600.0), &attempts, &puzzles, &[Theme::Fork]);
+
+        // then the weak theme is practised on easier puzzles:
+        assert!(deflection.end() < fork.start());
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::puzzle::types::{
+    Attempt, CyclePlan, CycleSchedule, PlanStatus, ScheduleState, TrainingSet,
+};
+
+pub const MAX_CYCLES: usize = 20;
+pub const MAX_CYCLE_DAYS: u32 = 365;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+pub fn status(
+    training_set: &TrainingSet,
+    plan: &CyclePlan,
+    attempts: &[Attempt],
+    now: DateTime<Utc>,
+) -> PlanStatus {
+    let mut spans: BTreeMap<u32, (DateTime<Utc>, DateTime<Utc>)> = BTreeMap::new();
+    for attempt in attempts {
+        spans
+            .entry(attempt.cycle)
+            .and_modify(|(first, last)| {
+                *first = (*first).min(attempt.attempted_at);
+                *last = (*last).max(attempt.attempted_at);
+            })
+            .or_insert((attempt.attempted_at, attempt.attempted_at));
+    }
+
+    let cycles: Vec<CycleSchedule> = (0..)
+        .zip(&plan.cycle_days)
+        .map(|(cycle, &target_days)| {
+            let span = spans.get(&cycle);
+            let started_at = span.map(|(first, _)| *first);
+            let deadline = started_at.map(|started_at| started_at + days(target_days));
+            let finished_at = span
+                .filter(|_| cycle < training_set.cycles_done)
+                .map(|(_, last)| *last);
+            CycleSchedule {
+                cycle,
+                target_days,
+                started_at,
+                deadline,
+                finished_at,
+                on_time: finished_at
+                    .zip(deadline)
+                    .map(|(finished_at, deadline)| finished_at <= deadline),
+            }
+        })
+        .collect();
+
+    let size = training_set.puzzle_ids.len() as u32;
+    let progress = training_set.current_progress;
+    let (state, expected_progress, puzzles_per_day) =
+        match cycles.get(training_set.cycles_done as usize) {
+            None => (ScheduleState::Completed, None, None),
+            Some(CycleSchedule {
+                target_days,
+                started_at: Some(started_at),
+                deadline: Some(deadline),
+                ..
+            }) => {
+                if now >= *deadline {
+                    (ScheduleState::Overdue, Some(size), None)
+                } else {
+                    let elapsed =
+                        seconds(now - *started_at) / (f64::from(*target_days) * SECONDS_PER_DAY);
+                    let expected = (f64::from(size) * elapsed).ceil().min(f64::from(size)) as u32;
+                    let days_left = seconds(*deadline - now) / SECONDS_PER_DAY;
+                    let state = if progress >= expected {
+                        ScheduleState::Ahead
+                    } else {
+                        ScheduleState::Behind
+                    };
+                    (
+                        state,
+                        Some(expected),
+                        Some(f64::from(size - progress) / days_left),
+                    )
+                }
+            }
+            Some(CycleSchedule { target_days, .. }) => (
+                ScheduleState::NotStarted,
+                None,
+                Some(f64::from(size) / f64::from(*target_days)),
+            ),
+        };
+
+    PlanStatus {
+        state,
+        current_cycle: training_set.cycles_done,
+        current_progress: progress,
+        expected_progress,
+        puzzles_per_day,
+        cycles,
+    }
+}
+
+fn days(days: u32) -> Duration {
+    Duration::days(i64::from(days))
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.num_seconds() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use uuid::Uuid;
+
+    use crate::puzzle::types::{
+        Attempt, CyclePlan, PlanStatus, ScheduleState, ThemeChoice, TrainingSet,
+    };
+    use crate::puzzle::woodpecker::status;
+
+    fn day(days: f64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+            + Duration::seconds((days * 86_400.0) as i64)
+    }
+
+    fn sample_set(current_progress: u32, cycles_done: u32) -> TrainingSet {
+        TrainingSet {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            puzzle_ids: (0..10).collect(),
+            name: "set".to_string(),
+            rating: 1500..=1600,
+            themes: ThemeChoice::HealthyMix,
+            adaptive: None,
+            plan: None,
+            current_progress,
+            cycles_done,
+        }
+    }
+
+    fn attempts(set: &TrainingSet, cycle: u32, days: &[f64]) -> Vec<Attempt> {
+        days.iter()
+            .enumerate()
+            .map(|(position, &days)| Attempt {
+                id: Uuid::new_v4(),
+                user_id: set.user_id,
+                training_set_id: set.id,
+                puzzle_id: set.puzzle_ids[position],
+                cycle,
+                solved: true,
+                solve_time_ms: 10_000,
+                attempted_at: day(days),
+            })
+            .collect()
+    }
+
+    fn plan() -> CyclePlan {
+        CyclePlan {
+            cycle_days: vec![10, 5],
+        }
+    }
+
+    #[test]
+    fn should_report_not_started_cycle() {
+        // when status of untouched set is computed:
+        let set = sample_set(0, 0);
+        let status = status(&set, &plan(), &[], day(3.0));
+
+        // then there's no deadline yet and the pace is for the whole cycle:
+        assert_eq!(status.state, ScheduleState::NotStarted);
+        assert_eq!(status.cycles[0].deadline, None);
+        assert_eq!(status.puzzles_per_day, Some(1.0));
+    }
+
+    #[test]
+    fn should_compare_progress_with_elapsed_time() {
+        // given set with four puzzles done within four days of a ten-day cycle:
+        let set = sample_set(4, 0);
+        let attempts = attempts(&set, 0, &[0.0, 1.0, 2.0, 3.5]);
+
+        // when status is computed after four and six days:
+        let ahead = status(&set, &plan(), &attempts, day(4.0));
+        let behind = status(&set, &plan(), &attempts, day(6.0));
+
+        // then the set is first ahead and then behind:
+        assert_eq!(ahead.state, ScheduleState::Ahead);
+        assert_eq!(ahead.expected_progress, Some(4));
+        assert_eq!(ahead.cycles[0].deadline, Some(day(10.0)));
+        assert_eq!(behind.state, ScheduleState::Behind);
+        assert_eq!(behind.expected_progress, Some(6));
+        assert_eq!(behind.puzzles_per_day, Some(1.5));
+    }
+
+    #[test]
+    fn should_report_overdue_cycle() {
+        // when status is computed after the deadline:
+        let set = sample_set(4, 0);
+        let attempts = attempts(&set, 0, &[0.0, 1.0, 2.0, 3.0]);
+        let status = status(&set, &plan(), &attempts, day(10.5));
+
+        // then it's overdue:
+        assert_eq!(status.state, ScheduleState::Overdue);
+        assert_eq!(status.expected_progress, Some(10));
+        assert_eq!(status.puzzles_per_day, None);
+    }
+
+    #[test]
+    fn should_track_finished_cycles() {
+        // given set with both planned cycles finished, the second one late:
+        let set = sample_set(0, 2);
+        let mut all = attempts(&set, 0, &[0.0, 9.0]);
+        all.extend(attempts(&set, 1, &[12.0, 18.0]));
+
+        // when status is computed:
+        let PlanStatus { state, cycles, .. } = status(&set, &plan(), &all, day(20.0));
+
+        // then the plan is completed with each cycle's timing:
+        assert_eq!(state, ScheduleState::Completed);
+        assert_eq!(cycles[0].finished_at, Some(day(9.0)));
+        assert_eq!(cycles[0].on_time, Some(true));
+        assert_eq!(cycles[1].started_at, Some(day(12.0)));
+        assert_eq!(cycles[1].deadline, Some(day(17.0)));
+        assert_eq!(cycles[1].on_time, Some(false));
+    }
+}
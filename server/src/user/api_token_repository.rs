@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::user::types::{ApiToken, ApiTokenId, Scope, UserId};
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait ApiTokenRepository: Send + Sync {
+    async fn create(&self, token: CreateApiToken) -> anyhow::Result<ApiToken>;
+    async fn find_by_user(&self, user_id: UserId) -> anyhow::Result<Vec<ApiToken>>;
+    async fn touch(
+        &self,
+        token_hash: &[u8],
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Option<ApiTokenRecord>>;
+    async fn delete(&self, user_id: UserId, id: ApiTokenId) -> anyhow::Result<bool>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateApiToken {
+    pub user_id: UserId,
+    pub name: String,
+    pub token_hash: Vec<u8>,
+    pub scopes: Vec<Scope>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiTokenRecord {
+    pub user_id: UserId,
+    pub api_token: ApiToken,
+}
+
+struct StoredApiToken {
+    token_hash: Vec<u8>,
+    record: ApiTokenRecord,
+}
+
+#[derive(Default)]
+pub struct InMemoryApiTokenRepository {
+    tokens: RwLock<Vec<StoredApiToken>>,
+}
+
+impl InMemoryApiTokenRepository {
+    pub fn new() -> InMemoryApiTokenRepository {
+        InMemoryApiTokenRepository::default()
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepository for InMemoryApiTokenRepository {
+    #[instrument(level = "debug", skip_all, fields(user_id = %token.user_id))]
+    async fn create(&self, token: CreateApiToken) -> anyhow::Result<ApiToken> {
+        let api_token = ApiToken {
+            id: Uuid::new_v4(),
+            name: token.name,
+            scopes: token.scopes,
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+        self.tokens.write().push(StoredApiToken {
+            token_hash: token.token_hash,
+            record: ApiTokenRecord {
+                user_id: token.user_id,
+                api_token: api_token.clone(),
+            },
+        });
+        Ok(api_token)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_user(&self, user_id: UserId) -> anyhow::Result<Vec<ApiToken>> {
+        Ok(self
+            .tokens
+            .read()
+            .iter()
+            .filter(|token| token.record.user_id == user_id)
+            .map(|token| token.record.api_token.clone())
+            .collect())
+    }
+
+    #[instrument(level = "debug", skip(self, token_hash))]
+    async fn touch(
+        &self,
+        token_hash: &[u8],
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Option<ApiTokenRecord>> {
+        Ok(self
+            .tokens
+            .write()
+            .iter_mut()
+            .find(|token| token.token_hash == token_hash)
+            .map(|token| {
+                token.record.api_token.last_used_at = Some(now);
+                token.record.clone()
+            }))
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(&self, user_id: UserId, id: ApiTokenId) -> anyhow::Result<bool> {
+        let mut tokens = self.tokens.write();
+        let count = tokens.len();
+        tokens
+            .retain(|token| !(token.record.user_id == user_id && token.record.api_token.id == id));
+        Ok(tokens.len() < count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::user::repository_contract::api_token_repository_contract_tests;
+    use crate::user::user_repository::InMemoryUserRepository;
+
+    use super::InMemoryApiTokenRepository;
+
+    api_token_repository_contract_tests!((
+        InMemoryUserRepository::new(),
+        InMemoryApiTokenRepository::new()
+    ));
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use serde_json::json;
+
+use crate::infrastructure::api_error::ApiError;
+use crate::infrastructure::rest::Context;
+use crate::puzzle::PuzzleService;
+use crate::user::types::{Authentication, Grant, Scope, User};
+
+pub struct BearerToken(pub String);
+
+pub struct AuthenticatedUser(pub Authentication);
+
+impl AuthenticatedUser {
+    pub fn user(&self) -> &User {
+        &self.0.user
+    }
+
+    pub fn require(&self, scope: Scope) -> Result<&User, ApiError> {
+        if !self.0.grant.allows(scope) {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                "insufficient_scope",
+                format!("Token lacks the {} scope.", scope),
+            )
+            .with_details(json!({ "scope": scope })));
+        }
+        Ok(&self.0.user)
+    }
+
+    pub fn require_session(&self) -> Result<&User, ApiError> {
+        if self.0.grant != Grant::Session {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                "session_required",
+                "Sign in with username and password to use this endpoint.",
+            ));
+        }
+        Ok(&self.0.user)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for BearerToken
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|authorization| authorization.to_str().ok())
+            .and_then(|authorization| authorization.strip_prefix("Bearer "))
+            .map(|token| BearerToken(token.trim().to_string()))
+            .ok_or_else(ApiError::unauthorized)
+    }
+}
+
+#[async_trait]
+impl<P> FromRequestParts<Arc<Context<P>>> for AuthenticatedUser
+where
+    P: PuzzleService + Send + Sync + 'static,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        ctx: &Arc<Context<P>>,
+    ) -> Result<Self, Self::Rejection> {
+        let BearerToken(token) = BearerToken::from_request_parts(parts, ctx).await?;
+        ctx.user_service
+            .authenticate(&token)
+            .await?
+            .map(AuthenticatedUser)
+            .ok_or_else(ApiError::unauthorized)
+    }
+}
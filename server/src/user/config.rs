@@ -0,0 +1,75 @@
+use anyhow::ensure;
+use serde::{Deserialize, Serialize};
+
+use crate::infrastructure::config::Config;
+use crate::infrastructure::postgres::PostgresPool;
+use crate::user::api_token_repository::InMemoryApiTokenRepository;
+use crate::user::postgres::{
+    PostgresApiTokenRepository, PostgresSessionRepository, PostgresUserRepository,
+};
+use crate::user::service::UserServiceImpl;
+use crate::user::session_repository::InMemorySessionRepository;
+use crate::user::user_repository::InMemoryUserRepository;
+use crate::user::UserService;
+
+pub const MAX_SESSION_TTL_HOURS: u64 = 365 * 24;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub session_ttl_hours: u64,
+    pub min_password_length: usize,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            session_ttl_hours: 30 * 24,
+            min_password_length: 8,
+        }
+    }
+}
+
+impl AuthConfig {
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.session_ttl_hours > 0,
+            "auth.session_ttl_hours must be positive."
+        );
+        ensure!(
+            self.session_ttl_hours <= MAX_SESSION_TTL_HOURS,
+            "auth.session_ttl_hours must be at most {} (one year).",
+            MAX_SESSION_TTL_HOURS
+        );
+        ensure!(
+            self.min_password_length > 0,
+            "auth.min_password_length must be positive."
+        );
+        Ok(())
+    }
+}
+
+pub fn make_service(
+    config: &Config,
+    database: Option<PostgresPool>,
+) -> Box<dyn UserService + Send + Sync> {
+    let auth = config.auth.clone();
+    match database {
+        None => Box::new(
+            UserServiceImpl::new(
+                InMemoryUserRepository::new(),
+                InMemorySessionRepository::new(),
+                InMemoryApiTokenRepository::new(),
+            )
+            .with_config(auth),
+        ),
+        Some(pool) => Box::new(
+            UserServiceImpl::new(
+                PostgresUserRepository::new(pool.clone()),
+                PostgresSessionRepository::new(pool.clone()),
+                PostgresApiTokenRepository::new(pool),
+            )
+            .with_config(auth),
+        ),
+    }
+}
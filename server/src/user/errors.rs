@@ -0,0 +1,34 @@
+use strum::IntoStaticStr;
+
+#[derive(Debug, thiserror::Error, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum RegisterError {
+    #[error("Username must be 3 to {} letters, digits, '-' or '_'.", max)]
+    InvalidUsername { max: usize },
+    #[error("Password must be at least {} characters long.", min)]
+    PasswordTooShort { min: usize },
+    #[error("Username is already taken.")]
+    UsernameTaken,
+    #[error("Repository error.")]
+    RepositoryError { source: anyhow::Error },
+}
+
+#[derive(Debug, thiserror::Error, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum LoginError {
+    #[error("Invalid username or password.")]
+    InvalidCredentials,
+    #[error("Repository error.")]
+    RepositoryError { source: anyhow::Error },
+}
+
+#[derive(Debug, thiserror::Error, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum CreateApiTokenError {
+    #[error("Token name must be 1 to {} characters long.", max)]
+    InvalidName { max: usize },
+    #[error("Token must have at least one scope.")]
+    NoScopes,
+    #[error("Repository error.")]
+    RepositoryError { source: anyhow::Error },
+}
@@ -0,0 +1,17 @@
+pub use auth::{AuthenticatedUser, BearerToken};
+pub use config::{make_service, AuthConfig};
+pub use rest::make_router;
+pub use service::UserService;
+
+pub mod api_token_repository;
+mod auth;
+mod config;
+pub mod errors;
+pub mod postgres;
+#[cfg(test)]
+pub(crate) mod repository_contract;
+pub(crate) mod rest;
+mod service;
+pub mod session_repository;
+pub mod types;
+pub mod user_repository;
@@ -0,0 +1,269 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio_postgres::Row;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::infrastructure::postgres::PostgresPool;
+use crate::user::api_token_repository::{ApiTokenRecord, ApiTokenRepository, CreateApiToken};
+use crate::user::session_repository::{CreateSession, SessionRepository};
+use crate::user::types::{ApiToken, ApiTokenId, Scope, User, UserId, UserRecord};
+use crate::user::user_repository::{CreateUser, UserRepository};
+
+fn user_from_row(row: &Row) -> anyhow::Result<User> {
+    Ok(User {
+        id: row.try_get("id")?,
+        username: row.try_get("username")?,
+    })
+}
+
+pub struct PostgresUserRepository {
+    pool: PostgresPool,
+}
+
+impl PostgresUserRepository {
+    pub fn new(pool: PostgresPool) -> PostgresUserRepository {
+        PostgresUserRepository { pool }
+    }
+}
+
+#[async_trait]
+impl UserRepository for PostgresUserRepository {
+    #[instrument(level = "debug", skip_all, fields(username = user.username))]
+    async fn create(&self, user: CreateUser) -> anyhow::Result<Option<User>> {
+        let client = self.pool.get().await?;
+        client
+            .query_opt(
+                "INSERT INTO users (id, username, password_hash) VALUES ($1, $2, $3) \
+                 ON CONFLICT (username) DO NOTHING \
+                 RETURNING id, username",
+                &[&Uuid::new_v4(), &user.username, &user.password_hash],
+            )
+            .await?
+            .as_ref()
+            .map(user_from_row)
+            .transpose()
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find(&self, id: UserId) -> anyhow::Result<Option<User>> {
+        let client = self.pool.get().await?;
+        client
+            .query_opt("SELECT id, username FROM users WHERE id = $1", &[&id])
+            .await?
+            .as_ref()
+            .map(user_from_row)
+            .transpose()
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_username(&self, username: &str) -> anyhow::Result<Option<UserRecord>> {
+        let client = self.pool.get().await?;
+        client
+            .query_opt(
+                "SELECT id, username, password_hash FROM users WHERE username = $1",
+                &[&username],
+            )
+            .await?
+            .map(|row| {
+                Ok(UserRecord {
+                    user: user_from_row(&row)?,
+                    password_hash: row.try_get("password_hash")?,
+                })
+            })
+            .transpose()
+    }
+}
+
+pub struct PostgresSessionRepository {
+    pool: PostgresPool,
+}
+
+impl PostgresSessionRepository {
+    pub fn new(pool: PostgresPool) -> PostgresSessionRepository {
+        PostgresSessionRepository { pool }
+    }
+}
+
+#[async_trait]
+impl SessionRepository for PostgresSessionRepository {
+    #[instrument(level = "debug", skip_all, fields(user_id = %session.user_id))]
+    async fn create(&self, session: CreateSession) -> anyhow::Result<()> {
+        let client = self.pool.get().await?;
+        client
+            .execute("DELETE FROM sessions WHERE expires_at <= now()", &[])
+            .await?;
+        client
+            .execute(
+                "INSERT INTO sessions (token_hash, user_id, expires_at) VALUES ($1, $2, $3)",
+                &[&session.token_hash, &session.user_id, &session.expires_at],
+            )
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self, token_hash))]
+    async fn find_user_id(
+        &self,
+        token_hash: &[u8],
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Option<UserId>> {
+        let client = self.pool.get().await?;
+        client
+            .query_opt(
+                "SELECT user_id FROM sessions WHERE token_hash = $1 AND expires_at > $2",
+                &[&token_hash, &now],
+            )
+            .await?
+            .map(|row| Ok(row.try_get(0)?))
+            .transpose()
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn delete(&self, token_hash: &[u8]) -> anyhow::Result<()> {
+        let client = self.pool.get().await?;
+        client
+            .execute("DELETE FROM sessions WHERE token_hash = $1", &[&token_hash])
+            .await?;
+        Ok(())
+    }
+}
+
+const API_TOKEN_COLUMNS: &str = "id, user_id, name, scopes, created_at, last_used_at";
+
+fn api_token_record_from_row(row: &Row) -> anyhow::Result<ApiTokenRecord> {
+    let scopes: Vec<String> = row.try_get("scopes")?;
+    Ok(ApiTokenRecord {
+        user_id: row.try_get("user_id")?,
+        api_token: ApiToken {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            scopes: scopes
+                .iter()
+                .map(|scope| Scope::from_str(scope))
+                .collect::<Result<_, _>>()?,
+            created_at: row.try_get("created_at")?,
+            last_used_at: row.try_get("last_used_at")?,
+        },
+    })
+}
+
+pub struct PostgresApiTokenRepository {
+    pool: PostgresPool,
+}
+
+impl PostgresApiTokenRepository {
+    pub fn new(pool: PostgresPool) -> PostgresApiTokenRepository {
+        PostgresApiTokenRepository { pool }
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepository for PostgresApiTokenRepository {
+    #[instrument(level = "debug", skip_all, fields(user_id = %token.user_id))]
+    async fn create(&self, token: CreateApiToken) -> anyhow::Result<ApiToken> {
+        let client = self.pool.get().await?;
+        let scopes: Vec<String> = token.scopes.iter().map(Scope::to_string).collect();
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO api_tokens (id, user_id, name, token_hash, scopes) \
+                     VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+                    API_TOKEN_COLUMNS
+                ),
+                &[
+                    &Uuid::new_v4(),
+                    &token.user_id,
+                    &token.name,
+                    &token.token_hash,
+                    &scopes,
+                ],
+            )
+            .await?;
+        Ok(api_token_record_from_row(&row)?.api_token)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_user(&self, user_id: UserId) -> anyhow::Result<Vec<ApiToken>> {
+        let client = self.pool.get().await?;
+        client
+            .query(
+                &format!(
+                    "SELECT {} FROM api_tokens WHERE user_id = $1 ORDER BY created_at, id",
+                    API_TOKEN_COLUMNS
+                ),
+                &[&user_id],
+            )
+            .await?
+            .iter()
+            .map(|row| Ok(api_token_record_from_row(row)?.api_token))
+            .collect()
+    }
+
+    #[instrument(level = "debug", skip(self, token_hash))]
+    async fn touch(
+        &self,
+        token_hash: &[u8],
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Option<ApiTokenRecord>> {
+        let client = self.pool.get().await?;
+        client
+            .query_opt(
+                &format!(
+                    "UPDATE api_tokens SET last_used_at = $2 WHERE token_hash = $1 RETURNING {}",
+                    API_TOKEN_COLUMNS
+                ),
+                &[&token_hash, &now],
+            )
+            .await?
+            .as_ref()
+            .map(api_token_record_from_row)
+            .transpose()
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(&self, user_id: UserId, id: ApiTokenId) -> anyhow::Result<bool> {
+        let client = self.pool.get().await?;
+        let deleted = client
+            .execute(
+                "DELETE FROM api_tokens WHERE user_id = $1 AND id = $2",
+                &[&user_id, &id],
+            )
+            .await?;
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::postgres::testing::test_pool;
+    use crate::user::repository_contract::{
+        api_token_repository_contract_tests, user_repository_contract_tests,
+    };
+
+    use super::{PostgresApiTokenRepository, PostgresSessionRepository, PostgresUserRepository};
+
+    user_repository_contract_tests!(
+        {
+            let pool = test_pool().await;
+            (
+                PostgresUserRepository::new(pool.clone()),
+                PostgresSessionRepository::new(pool),
+            )
+        },
+        #[ignore = "requires TEST_DATABASE_URL"]
+    );
+
+    api_token_repository_contract_tests!(
+        {
+            let pool = test_pool().await;
+            (
+                PostgresUserRepository::new(pool.clone()),
+                PostgresApiTokenRepository::new(pool),
+            )
+        },
+        #[ignore = "requires TEST_DATABASE_URL"]
+    );
+}
@@ -0,0 +1,319 @@
+//! Behavior every `UserRepository`, `SessionRepository` and `ApiTokenRepository` implementation
+//! must share.
+//!
+//! Backends instantiate the suites with `user_repository_contract_tests!`, passing an expression
+//! that yields a pair of empty user and session repositories, and
+//! `api_token_repository_contract_tests!`, passing a pair of empty user and API token
+//! repositories. Either may be followed by attributes applied to every test.
+
+use chrono::{Duration, DurationRound, Utc};
+
+use crate::user::api_token_repository::{ApiTokenRepository, CreateApiToken};
+use crate::user::session_repository::{CreateSession, SessionRepository};
+use crate::user::types::{Scope, User};
+use crate::user::user_repository::{CreateUser, UserRepository};
+
+macro_rules! user_repository_contract_tests {
+    ($make_repositories:expr $(, #[$attribute:meta])*) => {
+        mod user_repository_contract {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::user::repository_contract as contract;
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_create_and_find_users() {
+                contract::should_create_and_find_users($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_not_create_users_with_taken_username() {
+                contract::should_not_create_users_with_taken_username($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_find_user_of_session() {
+                contract::should_find_user_of_session($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_not_find_expired_sessions() {
+                contract::should_not_find_expired_sessions($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_delete_sessions() {
+                contract::should_delete_sessions($make_repositories).await;
+            }
+        }
+    };
+}
+
+pub(crate) use user_repository_contract_tests;
+
+macro_rules! api_token_repository_contract_tests {
+    ($make_repositories:expr $(, #[$attribute:meta])*) => {
+        mod api_token_repository_contract {
+            #[allow(unused_imports)]
+            use super::*;
+            use $crate::user::repository_contract as contract;
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_create_and_list_api_tokens() {
+                contract::should_create_and_list_api_tokens($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_touch_api_tokens() {
+                contract::should_touch_api_tokens($make_repositories).await;
+            }
+
+            #[tokio::test]
+            $(#[$attribute])*
+            async fn should_delete_only_own_api_tokens() {
+                contract::should_delete_only_own_api_tokens($make_repositories).await;
+            }
+        }
+    };
+}
+
+pub(crate) use api_token_repository_contract_tests;
+
+pub fn sample_user(username: &str) -> CreateUser {
+    CreateUser {
+        username: username.to_string(),
+        password_hash: format!("{}-password-hash", username),
+    }
+}
+
+pub async fn create_user(repository: &impl UserRepository, username: &str) -> User {
+    repository
+        .create(sample_user(username))
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+fn sample_session(token_hash: &[u8], user: &User, valid_for: Duration) -> CreateSession {
+    CreateSession {
+        token_hash: token_hash.to_vec(),
+        user_id: user.id,
+        expires_at: Utc::now() + valid_for,
+    }
+}
+
+pub async fn should_create_and_find_users<U, S>((repository, _): (U, S))
+where
+    U: UserRepository,
+    S: SessionRepository,
+{
+    // given users:
+    let first = create_user(&repository, "first").await;
+    let second = create_user(&repository, "second").await;
+
+    // when they're found:
+    let by_id = repository.find(first.id).await.unwrap();
+    let by_username = repository.find_by_username("second").await.unwrap();
+
+    // then they have the data given:
+    assert_eq!(first.username, "first");
+    assert_ne!(first.id, second.id);
+    assert_eq!(by_id, Some(first));
+    let by_username = by_username.unwrap();
+    assert_eq!(by_username.user, second);
+    assert_eq!(by_username.password_hash, "second-password-hash");
+    assert_eq!(repository.find_by_username("third").await.unwrap(), None);
+}
+
+pub async fn should_not_create_users_with_taken_username<U, S>((repository, _): (U, S))
+where
+    U: UserRepository,
+    S: SessionRepository,
+{
+    // given user:
+    create_user(&repository, "taken").await;
+
+    // when user with the same name is created:
+    let created = repository.create(sample_user("taken")).await.unwrap();
+
+    // then it's not:
+    assert_eq!(created, None);
+}
+
+pub async fn should_find_user_of_session<U, S>((users, sessions): (U, S))
+where
+    U: UserRepository,
+    S: SessionRepository,
+{
+    // given sessions of different users:
+    let first = create_user(&users, "first").await;
+    let second = create_user(&users, "second").await;
+    sessions
+        .create(sample_session(b"first", &first, Duration::hours(1)))
+        .await
+        .unwrap();
+    sessions
+        .create(sample_session(b"second", &second, Duration::hours(1)))
+        .await
+        .unwrap();
+
+    // when user of session is found:
+    let found = sessions.find_user_id(b"second", Utc::now()).await.unwrap();
+
+    // then it's the owner:
+    assert_eq!(found, Some(second.id));
+    assert_eq!(
+        sessions.find_user_id(b"unknown", Utc::now()).await.unwrap(),
+        None
+    );
+}
+
+pub async fn should_not_find_expired_sessions<U, S>((users, sessions): (U, S))
+where
+    U: UserRepository,
+    S: SessionRepository,
+{
+    // given session:
+    let user = create_user(&users, "user").await;
+    sessions
+        .create(sample_session(b"token", &user, Duration::hours(1)))
+        .await
+        .unwrap();
+
+    // when it's looked up after expiry:
+    let found = sessions
+        .find_user_id(b"token", Utc::now() + Duration::hours(2))
+        .await
+        .unwrap();
+
+    // then it's not found:
+    assert_eq!(found, None);
+}
+
+pub async fn should_delete_sessions<U, S>((users, sessions): (U, S))
+where
+    U: UserRepository,
+    S: SessionRepository,
+{
+    // given sessions:
+    let user = create_user(&users, "user").await;
+    for token_hash in [b"first", b"other"] {
+        sessions
+            .create(sample_session(token_hash, &user, Duration::hours(1)))
+            .await
+            .unwrap();
+    }
+
+    // when one is deleted:
+    sessions.delete(b"first").await.unwrap();
+
+    // then only the other remains:
+    assert_eq!(
+        sessions.find_user_id(b"first", Utc::now()).await.unwrap(),
+        None
+    );
+    assert_eq!(
+        sessions.find_user_id(b"other", Utc::now()).await.unwrap(),
+        Some(user.id)
+    );
+}
+
+fn sample_api_token(token_hash: &[u8], user: &User, name: &str) -> CreateApiToken {
+    CreateApiToken {
+        user_id: user.id,
+        name: name.to_string(),
+        token_hash: token_hash.to_vec(),
+        scopes: vec![Scope::ReadPuzzles, Scope::ManageSets],
+    }
+}
+
+pub async fn should_create_and_list_api_tokens<U, A>((users, tokens): (U, A))
+where
+    U: UserRepository,
+    A: ApiTokenRepository,
+{
+    // given tokens of different users:
+    let first = create_user(&users, "first").await;
+    let second = create_user(&users, "second").await;
+    let reporting = tokens
+        .create(sample_api_token(b"reporting", &first, "reporting"))
+        .await
+        .unwrap();
+    let scripts = tokens
+        .create(sample_api_token(b"scripts", &first, "scripts"))
+        .await
+        .unwrap();
+    tokens
+        .create(sample_api_token(b"other", &second, "other"))
+        .await
+        .unwrap();
+
+    // when tokens of the user are listed:
+    let listed = tokens.find_by_user(first.id).await.unwrap();
+
+    // then only theirs are, oldest first:
+    assert_eq!(reporting.name, "reporting");
+    assert_eq!(
+        reporting.scopes,
+        vec![Scope::ReadPuzzles, Scope::ManageSets]
+    );
+    assert_eq!(reporting.last_used_at, None);
+    assert_eq!(listed, vec![reporting, scripts]);
+}
+
+pub async fn should_touch_api_tokens<U, A>((users, tokens): (U, A))
+where
+    U: UserRepository,
+    A: ApiTokenRepository,
+{
+    // given token:
+    let user = create_user(&users, "user").await;
+    let created = tokens
+        .create(sample_api_token(b"token", &user, "token"))
+        .await
+        .unwrap();
+
+    // when it's used:
+    let now = Utc::now().duration_trunc(Duration::seconds(1)).unwrap();
+    let record = tokens.touch(b"token", now).await.unwrap().unwrap();
+
+    // then its owner is found and use recorded:
+    assert_eq!(record.user_id, user.id);
+    assert_eq!(record.api_token.id, created.id);
+    assert_eq!(record.api_token.last_used_at, Some(now));
+    assert_eq!(
+        tokens.find_by_user(user.id).await.unwrap()[0].last_used_at,
+        Some(now)
+    );
+    assert_eq!(tokens.touch(b"unknown", now).await.unwrap(), None);
+}
+
+pub async fn should_delete_only_own_api_tokens<U, A>((users, tokens): (U, A))
+where
+    U: UserRepository,
+    A: ApiTokenRepository,
+{
+    // given token:
+    let owner = create_user(&users, "owner").await;
+    let other = create_user(&users, "other").await;
+    let token = tokens
+        .create(sample_api_token(b"token", &owner, "token"))
+        .await
+        .unwrap();
+
+    // when other user and then owner delete it:
+    let deleted_by_other = tokens.delete(other.id, token.id).await.unwrap();
+    let deleted_by_owner = tokens.delete(owner.id, token.id).await.unwrap();
+
+    // then only owner succeeds:
+    assert!(!deleted_by_other);
+    assert!(deleted_by_owner);
+    assert_eq!(tokens.touch(b"token", Utc::now()).await.unwrap(), None);
+    assert!(!tokens.delete(owner.id, token.id).await.unwrap());
+}
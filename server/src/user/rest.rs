@@ -0,0 +1,431 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{delete, get, post, MethodRouter};
+use axum::{Json, Router};
+use serde_json::json;
+
+use crate::infrastructure::api_error::{ApiError, ApiJson};
+use crate::infrastructure::rest::Context;
+use crate::puzzle::PuzzleService;
+use crate::user::auth::{AuthenticatedUser, BearerToken};
+use crate::user::errors::{CreateApiTokenError, LoginError, RegisterError};
+use crate::user::types::{
+    ApiToken, ApiTokenId, CreateApiTokenOptions, Credentials, NewApiToken, SessionToken, User,
+};
+
+pub fn make_router<T>() -> Router<Arc<Context<T>>>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    routes()
+        .into_iter()
+        .fold(Router::new(), |router, (path, method_router)| {
+            router.route(path, method_router)
+        })
+}
+
+pub(crate) fn routes<T>() -> Vec<(&'static str, MethodRouter<Arc<Context<T>>>)>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    vec![
+        ("/users", post(register)),
+        ("/users/me", get(current_user)),
+        ("/sessions", post(login)),
+        ("/sessions/current", delete(logout)),
+        ("/tokens", get(list_api_tokens).post(create_api_token)),
+        ("/tokens/:id", delete(revoke_api_token)),
+    ]
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users",
+    tag = "users",
+    request_body = Credentials,
+    responses(
+        (status = 201, description = "User registered.", body = User),
+        (status = 400, description = "Invalid username or password.", body = ApiError),
+        (status = 409, description = "Username taken.", body = ApiError),
+    )
+)]
+pub async fn register<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    ApiJson(credentials): ApiJson<Credentials>,
+) -> Result<(StatusCode, Json<User>), ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = ctx.user_service.register(credentials).await?;
+    Ok((StatusCode::CREATED, Json(user)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me",
+    tag = "users",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Authenticated user.", body = User),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+    )
+)]
+pub async fn current_user(auth: AuthenticatedUser) -> Json<User> {
+    Json(auth.user().clone())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/sessions",
+    tag = "users",
+    request_body = Credentials,
+    responses(
+        (status = 201, description = "Session started.", body = SessionToken),
+        (status = 401, description = "Invalid credentials.", body = ApiError),
+    )
+)]
+pub async fn login<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    ApiJson(credentials): ApiJson<Credentials>,
+) -> Result<(StatusCode, Json<SessionToken>), ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let session = ctx.user_service.login(credentials).await?;
+    Ok((StatusCode::CREATED, Json(session)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/sessions/current",
+    tag = "users",
+    security(("bearer" = [])),
+    responses(
+        (status = 204, description = "Session ended."),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Authenticated with an API token.", body = ApiError),
+    )
+)]
+pub async fn logout<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    BearerToken(token): BearerToken,
+) -> Result<StatusCode, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    auth.require_session()?;
+    ctx.user_service.logout(&token).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/tokens",
+    tag = "users",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "API tokens of the user.", body = [ApiToken]),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Authenticated with an API token.", body = ApiError),
+    )
+)]
+pub async fn list_api_tokens<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+) -> Result<Json<Vec<ApiToken>>, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require_session()?;
+    Ok(Json(ctx.user_service.list_api_tokens(user.id).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/tokens",
+    tag = "users",
+    security(("bearer" = [])),
+    request_body = CreateApiTokenOptions,
+    responses(
+        (status = 201, description = "API token created. The token is shown only once.", body = NewApiToken),
+        (status = 400, description = "Invalid name or scopes.", body = ApiError),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Authenticated with an API token.", body = ApiError),
+        (status = 422, description = "Malformed body.", body = ApiError),
+    )
+)]
+pub async fn create_api_token<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    ApiJson(options): ApiJson<CreateApiTokenOptions>,
+) -> Result<(StatusCode, Json<NewApiToken>), ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require_session()?;
+    let token = ctx.user_service.create_api_token(user.id, options).await?;
+    Ok((StatusCode::CREATED, Json(token)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/tokens/{id}",
+    tag = "users",
+    security(("bearer" = [])),
+    params(("id" = Uuid, Path, description = "API token id.")),
+    responses(
+        (status = 204, description = "API token revoked."),
+        (status = 401, description = "Not authenticated.", body = ApiError),
+        (status = 403, description = "Authenticated with an API token.", body = ApiError),
+        (status = 404, description = "No such token of the user.", body = ApiError),
+    )
+)]
+pub async fn revoke_api_token<T>(
+    State(ctx): State<Arc<Context<T>>>,
+    auth: AuthenticatedUser,
+    Path(id): Path<ApiTokenId>,
+) -> Result<StatusCode, ApiError>
+where
+    T: PuzzleService + Send + Sync + 'static,
+{
+    let user = auth.require_session()?;
+    if !ctx.user_service.revoke_api_token(user.id, id).await? {
+        return Err(ApiError::not_found());
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+impl From<RegisterError> for ApiError {
+    fn from(error: RegisterError) -> Self {
+        let code = (&error).into();
+        let message = error.to_string();
+        match error {
+            RegisterError::InvalidUsername { max } => ApiError::bad_request(code, message)
+                .with_field("username")
+                .with_details(json!({ "max": max })),
+            RegisterError::PasswordTooShort { min } => ApiError::bad_request(code, message)
+                .with_field("password")
+                .with_details(json!({ "min": min })),
+            RegisterError::UsernameTaken => {
+                ApiError::new(StatusCode::CONFLICT, code, message).with_field("username")
+            }
+            RegisterError::RepositoryError { source } => ApiError::internal(source),
+        }
+    }
+}
+
+impl From<LoginError> for ApiError {
+    fn from(error: LoginError) -> Self {
+        let code = (&error).into();
+        let message = error.to_string();
+        match error {
+            LoginError::InvalidCredentials => {
+                ApiError::new(StatusCode::UNAUTHORIZED, code, message)
+            }
+            LoginError::RepositoryError { source } => ApiError::internal(source),
+        }
+    }
+}
+
+impl From<CreateApiTokenError> for ApiError {
+    fn from(error: CreateApiTokenError) -> Self {
+        let code = (&error).into();
+        let message = error.to_string();
+        match error {
+            CreateApiTokenError::InvalidName { max } => ApiError::bad_request(code, message)
+                .with_field("name")
+                .with_details(json!({ "max": max })),
+            CreateApiTokenError::NoScopes => {
+                ApiError::bad_request(code, message).with_field("scopes")
+            }
+            CreateApiTokenError::RepositoryError { source } => ApiError::internal(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE};
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    use crate::infrastructure::test_support::test_router;
+
+    async fn send(router: &Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+        (status, body)
+    }
+
+    fn post(uri: &str, body: serde_json::Value) -> Request<Body> {
+        Request::post(uri)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn authorized(method: &str, uri: &str, token: &str) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_register_log_in_and_out() {
+        // given registered user:
+        let router = test_router().await;
+        let credentials = json!({ "username": "magnus", "password": "sample-password" });
+        let (status, user) = send(&router, post("/api/v1/users", credentials.clone())).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        // when they log in:
+        let (status, session) = send(&router, post("/api/v1/sessions", credentials)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let token = session["token"].as_str().unwrap();
+
+        // then token identifies them:
+        let (status, me) = send(&router, authorized("GET", "/api/v1/users/me", token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(me, user);
+
+        // and stops working after logout:
+        let (status, _) = send(
+            &router,
+            authorized("DELETE", "/api/v1/sessions/current", token),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send(&router, authorized("GET", "/api/v1/users/me", token)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn should_reject_taken_username() {
+        // given registered user:
+        let router = test_router().await;
+        let credentials = json!({ "username": "magnus", "password": "sample-password" });
+        send(&router, post("/api/v1/users", credentials.clone())).await;
+
+        // when the name is registered again:
+        let (status, body) = send(&router, post("/api/v1/users", credentials)).await;
+
+        // then it's a conflict:
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "username_taken");
+        assert_eq!(body["field"], "username");
+    }
+
+    #[tokio::test]
+    async fn should_require_bearer_token() {
+        // when protected route is requested without token:
+        let router = test_router().await;
+        let response = router
+            .oneshot(
+                Request::get("/api/v1/users/me")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // then client is asked to authenticate:
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+    }
+
+    fn authorized_post(uri: &str, token: &str, body: serde_json::Value) -> Request<Body> {
+        Request::post(uri)
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    async fn log_in(router: &Router) -> String {
+        let credentials = json!({ "username": "magnus", "password": "sample-password" });
+        send(router, post("/api/v1/users", credentials.clone())).await;
+        let (_, session) = send(router, post("/api/v1/sessions", credentials)).await;
+        session["token"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn should_manage_api_tokens() {
+        // given logged in user:
+        let router = test_router().await;
+        let session = log_in(&router).await;
+
+        // when they create an API token:
+        let (status, created) = send(
+            &router,
+            authorized_post(
+                "/api/v1/tokens",
+                &session,
+                json!({ "name": "reporting", "scopes": ["read_puzzles"] }),
+            ),
+        )
+        .await;
+
+        // then it's listed without the secret:
+        assert_eq!(status, StatusCode::CREATED);
+        let token = created["token"].as_str().unwrap();
+        let (status, listed) = send(&router, authorized("GET", "/api/v1/tokens", &session)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed[0]["id"], created["id"]);
+        assert_eq!(listed[0]["scopes"], json!(["read_puzzles"]));
+        assert_eq!(listed[0].get("token"), None);
+
+        // and authenticates only within its scopes:
+        let (status, _) = send(&router, authorized("GET", "/api/v1/users/me", token)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = send(&router, authorized("GET", "/api/v1/sets", token)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["code"], "insufficient_scope");
+        assert_eq!(body["details"]["scope"], "manage_sets");
+        let (status, body) = send(&router, authorized("GET", "/api/v1/tokens", token)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["code"], "session_required");
+
+        // and stops working once revoked:
+        let uri = format!("/api/v1/tokens/{}", created["id"].as_str().unwrap());
+        let (status, _) = send(&router, authorized("DELETE", &uri, &session)).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = send(&router, authorized("GET", "/api/v1/users/me", token)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&router, authorized("DELETE", &uri, &session)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn should_reject_api_token_without_scopes() {
+        // given logged in user:
+        let router = test_router().await;
+        let session = log_in(&router).await;
+
+        // when they create a token without scopes:
+        let (status, body) = send(
+            &router,
+            authorized_post(
+                "/api/v1/tokens",
+                &session,
+                json!({ "name": "reporting", "scopes": [] }),
+            ),
+        )
+        .await;
+
+        // then it's rejected:
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "no_scopes");
+        assert_eq!(body["field"], "scopes");
+    }
+}
@@ -0,0 +1,571 @@
+use std::sync::LazyLock;
+
+use anyhow::Context;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::SaltString;
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use tracing::instrument;
+
+use crate::user::api_token_repository::{ApiTokenRepository, CreateApiToken};
+use crate::user::config::AuthConfig;
+use crate::user::errors::{CreateApiTokenError, LoginError, RegisterError};
+use crate::user::session_repository::{CreateSession, SessionRepository};
+use crate::user::types::{
+    ApiToken, ApiTokenId, Authentication, CreateApiTokenOptions, Credentials, Grant, NewApiToken,
+    SessionToken, User, UserId,
+};
+use crate::user::user_repository::{CreateUser, UserRepository};
+
+const MAX_USERNAME_LENGTH: usize = 32;
+const MAX_API_TOKEN_NAME_LENGTH: usize = 64;
+const TOKEN_BYTES: usize = 32;
+const API_TOKEN_PREFIX: &str = "ct_";
+
+static DUMMY_PASSWORD_HASH: LazyLock<String> =
+    LazyLock::new(|| hash_password("dummy-password").unwrap());
+
+#[async_trait]
+pub trait UserService: Send + Sync {
+    async fn register(&self, credentials: Credentials) -> Result<User, RegisterError>;
+    async fn login(&self, credentials: Credentials) -> Result<SessionToken, LoginError>;
+    async fn authenticate(&self, token: &str) -> anyhow::Result<Option<Authentication>>;
+    async fn logout(&self, token: &str) -> anyhow::Result<()>;
+    async fn create_api_token(
+        &self,
+        user_id: UserId,
+        options: CreateApiTokenOptions,
+    ) -> Result<NewApiToken, CreateApiTokenError>;
+    async fn list_api_tokens(&self, user_id: UserId) -> anyhow::Result<Vec<ApiToken>>;
+    async fn revoke_api_token(&self, user_id: UserId, id: ApiTokenId) -> anyhow::Result<bool>;
+}
+
+#[async_trait]
+impl<S> UserService for Box<S>
+where
+    S: UserService + ?Sized,
+{
+    async fn register(&self, credentials: Credentials) -> Result<User, RegisterError> {
+        (**self).register(credentials).await
+    }
+
+    async fn login(&self, credentials: Credentials) -> Result<SessionToken, LoginError> {
+        (**self).login(credentials).await
+    }
+
+    async fn authenticate(&self, token: &str) -> anyhow::Result<Option<Authentication>> {
+        (**self).authenticate(token).await
+    }
+
+    async fn logout(&self, token: &str) -> anyhow::Result<()> {
+        (**self).logout(token).await
+    }
+
+    async fn create_api_token(
+        &self,
+        user_id: UserId,
+        options: CreateApiTokenOptions,
+    ) -> Result<NewApiToken, CreateApiTokenError> {
+        (**self).create_api_token(user_id, options).await
+    }
+
+    async fn list_api_tokens(&self, user_id: UserId) -> anyhow::Result<Vec<ApiToken>> {
+        (**self).list_api_tokens(user_id).await
+    }
+
+    async fn revoke_api_token(&self, user_id: UserId, id: ApiTokenId) -> anyhow::Result<bool> {
+        (**self).revoke_api_token(user_id, id).await
+    }
+}
+
+#[cfg_attr(test, derive(derive_builder::Builder))]
+#[cfg_attr(test, builder(pattern = "owned"))]
+pub struct UserServiceImpl<U, S, A>
+where
+    U: UserRepository,
+    S: SessionRepository,
+    A: ApiTokenRepository,
+{
+    user_repository: U,
+    session_repository: S,
+    api_token_repository: A,
+    #[cfg_attr(test, builder(default))]
+    config: AuthConfig,
+}
+
+impl<U, S, A> UserServiceImpl<U, S, A>
+where
+    U: UserRepository,
+    S: SessionRepository,
+    A: ApiTokenRepository,
+{
+    pub fn new(
+        user_repository: U,
+        session_repository: S,
+        api_token_repository: A,
+    ) -> UserServiceImpl<U, S, A> {
+        UserServiceImpl {
+            user_repository,
+            session_repository,
+            api_token_repository,
+            config: AuthConfig::default(),
+        }
+    }
+
+    pub fn with_config(self, config: AuthConfig) -> UserServiceImpl<U, S, A> {
+        UserServiceImpl { config, ..self }
+    }
+
+    async fn authenticate_api_token(&self, token: &str) -> anyhow::Result<Option<Authentication>> {
+        let Some(record) = self
+            .api_token_repository
+            .touch(&hash_token(token), Utc::now())
+            .await?
+        else {
+            return Ok(None);
+        };
+        tracing::debug!(
+            user_id = %record.user_id,
+            token_id = %record.api_token.id,
+            "api token used"
+        );
+        Ok(self
+            .user_repository
+            .find(record.user_id)
+            .await?
+            .map(|user| Authentication {
+                user,
+                grant: Grant::ApiToken {
+                    id: record.api_token.id,
+                    scopes: record.api_token.scopes,
+                },
+            }))
+    }
+}
+
+#[async_trait]
+impl<U, S, A> UserService for UserServiceImpl<U, S, A>
+where
+    U: UserRepository,
+    S: SessionRepository,
+    A: ApiTokenRepository,
+{
+    #[instrument(skip_all, fields(username = credentials.username))]
+    async fn register(&self, credentials: Credentials) -> Result<User, RegisterError> {
+        let username = normalize_username(&credentials.username);
+        if !is_valid_username(&username) {
+            return Err(RegisterError::InvalidUsername {
+                max: MAX_USERNAME_LENGTH,
+            });
+        }
+        if credentials.password.chars().count() < self.config.min_password_length {
+            return Err(RegisterError::PasswordTooShort {
+                min: self.config.min_password_length,
+            });
+        }
+
+        let password = credentials.password;
+        let password_hash = tokio::task::spawn_blocking(move || hash_password(&password))
+            .await
+            .context("password hashing panicked")
+            .and_then(|hash| hash)
+            .map_err(|source| RegisterError::RepositoryError { source })?;
+        self.user_repository
+            .create(CreateUser {
+                username,
+                password_hash,
+            })
+            .await
+            .map_err(|source| RegisterError::RepositoryError { source })?
+            .ok_or(RegisterError::UsernameTaken)
+    }
+
+    #[instrument(skip_all, fields(username = credentials.username))]
+    async fn login(&self, credentials: Credentials) -> Result<SessionToken, LoginError> {
+        let record = self
+            .user_repository
+            .find_by_username(&normalize_username(&credentials.username))
+            .await
+            .map_err(|source| LoginError::RepositoryError { source })?;
+
+        let password_hash = record.as_ref().map_or_else(
+            || DUMMY_PASSWORD_HASH.clone(),
+            |record| record.password_hash.clone(),
+        );
+        let password = credentials.password;
+        let verified =
+            tokio::task::spawn_blocking(move || verify_password(&password, &password_hash))
+                .await
+                .map_err(|error| LoginError::RepositoryError {
+                    source: error.into(),
+                })?;
+        let user = match record {
+            Some(record) if verified => record.user,
+            _ => return Err(LoginError::InvalidCredentials),
+        };
+
+        let token = generate_token();
+        let expires_at = Utc::now() + Duration::hours(self.config.session_ttl_hours as i64);
+        self.session_repository
+            .create(CreateSession {
+                token_hash: hash_token(&token),
+                user_id: user.id,
+                expires_at,
+            })
+            .await
+            .map_err(|source| LoginError::RepositoryError { source })?;
+        Ok(SessionToken { token, expires_at })
+    }
+
+    async fn authenticate(&self, token: &str) -> anyhow::Result<Option<Authentication>> {
+        if token.starts_with(API_TOKEN_PREFIX) {
+            return self.authenticate_api_token(token).await;
+        }
+        let user_id = self
+            .session_repository
+            .find_user_id(&hash_token(token), Utc::now())
+            .await?;
+        let user = match user_id {
+            Some(user_id) => self.user_repository.find(user_id).await?,
+            None => None,
+        };
+        Ok(user.map(|user| Authentication {
+            user,
+            grant: Grant::Session,
+        }))
+    }
+
+    async fn logout(&self, token: &str) -> anyhow::Result<()> {
+        self.session_repository.delete(&hash_token(token)).await
+    }
+
+    #[instrument(skip(self, options), fields(name = options.name))]
+    async fn create_api_token(
+        &self,
+        user_id: UserId,
+        options: CreateApiTokenOptions,
+    ) -> Result<NewApiToken, CreateApiTokenError> {
+        let name = options.name.trim().to_string();
+        if name.is_empty() || name.chars().count() > MAX_API_TOKEN_NAME_LENGTH {
+            return Err(CreateApiTokenError::InvalidName {
+                max: MAX_API_TOKEN_NAME_LENGTH,
+            });
+        }
+        let mut scopes = options.scopes;
+        scopes.sort();
+        scopes.dedup();
+        if scopes.is_empty() {
+            return Err(CreateApiTokenError::NoScopes);
+        }
+
+        let token = format!("{}{}", API_TOKEN_PREFIX, generate_token());
+        let api_token = self
+            .api_token_repository
+            .create(CreateApiToken {
+                user_id,
+                name,
+                token_hash: hash_token(&token),
+                scopes,
+            })
+            .await
+            .map_err(|source| CreateApiTokenError::RepositoryError { source })?;
+        Ok(NewApiToken { token, api_token })
+    }
+
+    async fn list_api_tokens(&self, user_id: UserId) -> anyhow::Result<Vec<ApiToken>> {
+        self.api_token_repository.find_by_user(user_id).await
+    }
+
+    #[instrument(skip(self))]
+    async fn revoke_api_token(&self, user_id: UserId, id: ApiTokenId) -> anyhow::Result<bool> {
+        self.api_token_repository.delete(user_id, id).await
+    }
+}
+
+fn normalize_username(username: &str) -> String {
+    username.trim().to_lowercase()
+}
+
+fn is_valid_username(username: &str) -> bool {
+    (3..=MAX_USERNAME_LENGTH).contains(&username.len())
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn hash_password(password: &str) -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|error| anyhow::anyhow!("failed to hash password: {}", error))?
+        .to_string())
+}
+
+fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hash_token(token: &str) -> Vec<u8> {
+    Sha256::digest(token.as_bytes()).to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use crate::user::api_token_repository::InMemoryApiTokenRepository;
+    use crate::user::errors::{CreateApiTokenError, LoginError, RegisterError};
+    use crate::user::service::{hash_password, hash_token, UserServiceImplBuilder};
+    use crate::user::session_repository::{InMemorySessionRepository, MockSessionRepository};
+    use crate::user::types::{
+        Authentication, CreateApiTokenOptions, Credentials, Grant, Scope, User, UserRecord,
+    };
+    use crate::user::user_repository::{InMemoryUserRepository, MockUserRepository};
+    use crate::user::UserService;
+
+    fn credentials(username: &str, password: &str) -> Credentials {
+        Credentials {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    fn make_service() -> impl UserService {
+        UserServiceImplBuilder::default()
+            .user_repository(InMemoryUserRepository::new())
+            .session_repository(InMemorySessionRepository::new())
+            .api_token_repository(InMemoryApiTokenRepository::new())
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_register_and_log_in() {
+        // given registered user:
+        let service = make_service();
+        let user = service
+            .register(credentials(" Magnus ", "sample-password"))
+            .await
+            .unwrap();
+
+        // when they log in:
+        let session = service
+            .login(credentials("magnus", "sample-password"))
+            .await
+            .unwrap();
+
+        // then the token authenticates them:
+        assert_eq!(user.username, "magnus");
+        assert!(session.expires_at > Utc::now() + Duration::days(29));
+        assert_eq!(
+            service.authenticate(&session.token).await.unwrap(),
+            Some(Authentication {
+                user,
+                grant: Grant::Session
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn should_reject_wrong_password() {
+        // given registered user:
+        let service = make_service();
+        service
+            .register(credentials("magnus", "sample-password"))
+            .await
+            .unwrap();
+
+        // when they log in with other password or unknown name:
+        let wrong_password = service.login(credentials("magnus", "other-password")).await;
+        let unknown_user = service
+            .login(credentials("hikaru", "sample-password"))
+            .await;
+
+        // then credentials are rejected:
+        assert!(matches!(
+            wrong_password,
+            Err(LoginError::InvalidCredentials)
+        ));
+        assert!(matches!(unknown_user, Err(LoginError::InvalidCredentials)));
+    }
+
+    #[tokio::test]
+    async fn should_reject_invalid_registrations() {
+        // given registered user:
+        let service = make_service();
+        service
+            .register(credentials("magnus", "sample-password"))
+            .await
+            .unwrap();
+
+        // when invalid registrations are made:
+        let taken = service
+            .register(credentials("MAGNUS", "sample-password"))
+            .await;
+        let invalid = service
+            .register(credentials("a b", "sample-password"))
+            .await;
+        let short = service.register(credentials("hikaru", "short")).await;
+
+        // then they're rejected:
+        assert!(matches!(taken, Err(RegisterError::UsernameTaken)));
+        assert!(matches!(
+            invalid,
+            Err(RegisterError::InvalidUsername { max: 32 })
+        ));
+        assert!(matches!(
+            short,
+            Err(RegisterError::PasswordTooShort { min: 8 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_store_only_hashes() {
+        // given repositories checking what's stored:
+        let mut user_repository = MockUserRepository::new();
+        user_repository
+            .expect_find_by_username()
+            .returning(|username| {
+                Ok(Some(UserRecord {
+                    user: User {
+                        id: uuid::Uuid::nil(),
+                        username: username.to_string(),
+                    },
+                    password_hash: hash_password("sample-password").unwrap(),
+                }))
+            });
+        let mut session_repository = MockSessionRepository::new();
+        session_repository
+            .expect_create()
+            .withf(|session| session.token_hash.len() == 32)
+            .returning(|_| Ok(()));
+
+        // when user logs in:
+        let service = UserServiceImplBuilder::default()
+            .user_repository(user_repository)
+            .session_repository(session_repository)
+            .api_token_repository(InMemoryApiTokenRepository::new())
+            .build()
+            .unwrap();
+        let session = service
+            .login(credentials("magnus", "sample-password"))
+            .await
+            .unwrap();
+
+        // then token differs from what's stored:
+        assert_eq!(session.token.len(), 64);
+        assert_ne!(hash_token(&session.token), session.token.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn should_log_out() {
+        // given logged in user:
+        let service = make_service();
+        service
+            .register(credentials("magnus", "sample-password"))
+            .await
+            .unwrap();
+        let session = service
+            .login(credentials("magnus", "sample-password"))
+            .await
+            .unwrap();
+
+        // when they log out:
+        service.logout(&session.token).await.unwrap();
+
+        // then token no longer authenticates:
+        assert_eq!(service.authenticate(&session.token).await.unwrap(), None);
+    }
+
+    fn token_options(name: &str, scopes: &[Scope]) -> CreateApiTokenOptions {
+        CreateApiTokenOptions {
+            name: name.to_string(),
+            scopes: scopes.to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_authenticate_with_api_token() {
+        // given API token of registered user:
+        let service = make_service();
+        let user = service
+            .register(credentials("magnus", "sample-password"))
+            .await
+            .unwrap();
+        let created = service
+            .create_api_token(
+                user.id,
+                token_options(
+                    " reporting ",
+                    &[Scope::ManageSets, Scope::ReadPuzzles, Scope::ManageSets],
+                ),
+            )
+            .await
+            .unwrap();
+
+        // when it's used:
+        let authentication = service.authenticate(&created.token).await.unwrap();
+
+        // then it grants only its scopes and records use:
+        assert!(created.token.starts_with("ct_"));
+        assert_eq!(created.api_token.name, "reporting");
+        let scopes = vec![Scope::ReadPuzzles, Scope::ManageSets];
+        assert_eq!(
+            authentication,
+            Some(Authentication {
+                user: user.clone(),
+                grant: Grant::ApiToken {
+                    id: created.api_token.id,
+                    scopes
+                }
+            })
+        );
+        let listed = service.list_api_tokens(user.id).await.unwrap();
+        assert!(listed[0].last_used_at.is_some());
+
+        // and stops working once revoked:
+        assert!(service
+            .revoke_api_token(user.id, created.api_token.id)
+            .await
+            .unwrap());
+        assert_eq!(service.authenticate(&created.token).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn should_reject_invalid_api_tokens() {
+        // given registered user:
+        let service = make_service();
+        let user = service
+            .register(credentials("magnus", "sample-password"))
+            .await
+            .unwrap();
+
+        // when invalid tokens are created:
+        let unnamed = service
+            .create_api_token(user.id, token_options(" ", &[Scope::ReadPuzzles]))
+            .await;
+        let unscoped = service
+            .create_api_token(user.id, token_options("reporting", &[]))
+            .await;
+
+        // then they're rejected:
+        assert!(matches!(
+            unnamed,
+            Err(CreateApiTokenError::InvalidName { max: 64 })
+        ));
+        assert!(matches!(unscoped, Err(CreateApiTokenError::NoScopes)));
+        assert!(service.list_api_tokens(user.id).await.unwrap().is_empty());
+    }
+}
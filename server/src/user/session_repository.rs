@@ -0,0 +1,70 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use tracing::instrument;
+
+use crate::user::types::UserId;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    async fn create(&self, session: CreateSession) -> anyhow::Result<()>;
+    async fn find_user_id(
+        &self,
+        token_hash: &[u8],
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Option<UserId>>;
+    async fn delete(&self, token_hash: &[u8]) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateSession {
+    pub token_hash: Vec<u8>,
+    pub user_id: UserId,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+pub struct InMemorySessionRepository {
+    sessions: RwLock<Vec<CreateSession>>,
+}
+
+impl InMemorySessionRepository {
+    pub fn new() -> InMemorySessionRepository {
+        InMemorySessionRepository::default()
+    }
+}
+
+#[async_trait]
+impl SessionRepository for InMemorySessionRepository {
+    #[instrument(level = "debug", skip_all, fields(user_id = %session.user_id))]
+    async fn create(&self, session: CreateSession) -> anyhow::Result<()> {
+        let mut sessions = self.sessions.write();
+        let now = Utc::now();
+        sessions.retain(|session| session.expires_at > now);
+        sessions.push(session);
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip(self, token_hash))]
+    async fn find_user_id(
+        &self,
+        token_hash: &[u8],
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Option<UserId>> {
+        Ok(self
+            .sessions
+            .read()
+            .iter()
+            .find(|session| session.token_hash == token_hash && session.expires_at > now)
+            .map(|session| session.user_id))
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn delete(&self, token_hash: &[u8]) -> anyhow::Result<()> {
+        self.sessions
+            .write()
+            .retain(|session| session.token_hash != token_hash);
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use strum::{Display as EnumDisplay, EnumString};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub type UserId = Uuid;
+pub type ApiTokenId = Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct User {
+    #[schema(value_type = Uuid)]
+    pub id: UserId,
+    pub username: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserRecord {
+    pub user: User,
+    pub password_hash: String,
+}
+
+#[derive(Clone, Deserialize, ToSchema)]
+pub struct Credentials {
+    #[schema(example = "magnus")]
+    pub username: String,
+    #[schema(example = "correct horse battery staple")]
+    pub password: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SessionToken {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    EnumDisplay,
+    EnumString,
+    ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Scope {
+    ReadPuzzles,
+    ManageSets,
+    RecordAttempts,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ApiToken {
+    #[schema(value_type = Uuid)]
+    pub id: ApiTokenId,
+    #[schema(example = "nightly report")]
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct NewApiToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub api_token: ApiToken,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateApiTokenOptions {
+    #[schema(example = "nightly report")]
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Grant {
+    Session,
+    ApiToken { id: ApiTokenId, scopes: Vec<Scope> },
+}
+
+impl Grant {
+    pub fn allows(&self, scope: Scope) -> bool {
+        match self {
+            Grant::Session => true,
+            Grant::ApiToken { scopes, .. } => scopes.contains(&scope),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authentication {
+    pub user: User,
+    pub grant: Grant,
+}
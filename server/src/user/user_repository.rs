@@ -0,0 +1,87 @@
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::user::types::{User, UserId, UserRecord};
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn create(&self, user: CreateUser) -> anyhow::Result<Option<User>>;
+    async fn find(&self, id: UserId) -> anyhow::Result<Option<User>>;
+    async fn find_by_username(&self, username: &str) -> anyhow::Result<Option<UserRecord>>;
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CreateUser {
+    pub username: String,
+    pub password_hash: String,
+}
+
+#[derive(Default)]
+pub struct InMemoryUserRepository {
+    users: RwLock<Vec<UserRecord>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> InMemoryUserRepository {
+        InMemoryUserRepository::default()
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    #[instrument(level = "debug", skip_all, fields(username = user.username))]
+    async fn create(&self, user: CreateUser) -> anyhow::Result<Option<User>> {
+        let mut users = self.users.write();
+        if users
+            .iter()
+            .any(|record| record.user.username == user.username)
+        {
+            return Ok(None);
+        }
+        let record = UserRecord {
+            user: User {
+                id: Uuid::new_v4(),
+                username: user.username,
+            },
+            password_hash: user.password_hash,
+        };
+        users.push(record.clone());
+        Ok(Some(record.user))
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find(&self, id: UserId) -> anyhow::Result<Option<User>> {
+        Ok(self
+            .users
+            .read()
+            .iter()
+            .find(|record| record.user.id == id)
+            .map(|record| record.user.clone()))
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_username(&self, username: &str) -> anyhow::Result<Option<UserRecord>> {
+        Ok(self
+            .users
+            .read()
+            .iter()
+            .find(|record| record.user.username == username)
+            .cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::user::repository_contract::user_repository_contract_tests;
+    use crate::user::session_repository::InMemorySessionRepository;
+
+    use super::InMemoryUserRepository;
+
+    user_repository_contract_tests!((
+        InMemoryUserRepository::new(),
+        InMemorySessionRepository::new()
+    ));
+}